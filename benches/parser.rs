@@ -0,0 +1,33 @@
+use XML_to_JSON_Parser::{start_parser, start_pest_parser};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+
+fn wide(items: usize) -> String {
+    let items: String = (0..items).map(|index| format!("    <item{} id=\"{}\" kind='value'>Text of item {}</item{}>\n", index, index, index, index)).collect();
+    format!("<?xml version=\"1.0\"?>\n<parser>\n{}</parser>\n", items)
+}
+
+fn deep(depth: usize) -> String {
+    format!("{}text{}", "<level attribute=\"1\">".repeat(depth), "</level>".repeat(depth))
+}
+
+fn text_heavy(paragraphs: usize) -> String {
+    let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(20);
+    let paragraphs: String = (0..paragraphs).map(|index| format!("<p{}>{}<!-- note --></p{}>", index, paragraph, index)).collect();
+    format!("<document>{}</document>", paragraphs)
+}
+
+fn bench_converters(c: &mut Criterion) {
+    let inputs = [("wide", wide(5_000)), ("deep", deep(500)), ("text", text_heavy(500))];
+
+    let mut group = c.benchmark_group("convert");
+    for (name, input) in &inputs {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("lexer", name), input, |b, input| b.iter(|| start_parser(black_box(input)).unwrap()));
+        group.bench_with_input(BenchmarkId::new("pest", name), input, |b, input| b.iter(|| start_pest_parser(black_box(input)).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_converters);
+criterion_main!(benches);
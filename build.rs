@@ -0,0 +1,19 @@
+fn main() {
+    // The C header of the `ffi` feature is generated into OUT_DIR, the committed `include/xml_to_json_parser.h`
+    // is only rewritten with XML_TO_JSON_PARSER_UPDATE_HEADER set, `ffi_tests` checks that it is up to date.
+    #[cfg(feature = "ffi")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let header = format!("{}/xml_to_json_parser.h", std::env::var("OUT_DIR").unwrap());
+        let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).unwrap();
+        let bindings = cbindgen::generate_with_config(&crate_dir, config).expect("Unable to generate C header");
+        bindings.write_to_file(&header);
+        if std::env::var_os("XML_TO_JSON_PARSER_UPDATE_HEADER").is_some() {
+            bindings.write_to_file(format!("{}/include/xml_to_json_parser.h", crate_dir));
+        }
+        println!("cargo:rustc-env=XML_TO_JSON_PARSER_HEADER={}", header);
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-env-changed=XML_TO_JSON_PARSER_UPDATE_HEADER");
+    }
+}
@@ -0,0 +1,51 @@
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#ifndef XML_TO_JSON_PARSER_H
+#define XML_TO_JSON_PARSER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum XmlToJsonStatus {
+  XML_TO_JSON_STATUS_OK = 0,
+  XML_TO_JSON_STATUS_NULL_POINTER = 1,
+  XML_TO_JSON_STATUS_INVALID_UTF8 = 2,
+  XML_TO_JSON_STATUS_PARSE_ERROR = 3,
+} XmlToJsonStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Converts the NUL-terminated UTF-8 `xml` like `start_parser`. On success `*out_json` receives
+// a JSON string that must be released with `xml_to_json_free`.
+//
+// # Safety
+// `xml` must be null or a valid NUL-terminated string, `out_json` must be null or writable.
+enum XmlToJsonStatus xml_to_json_parse(const char *xml, char **out_json);
+
+// Same as `xml_to_json_parse` for a buffer of `length` bytes in any encoding supported by `parse_bytes`.
+//
+// # Safety
+// `bytes` must be null or point to `length` readable bytes, `out_json` must be null or writable.
+enum XmlToJsonStatus xml_to_json_parse_bytes(const uint8_t *bytes,
+                                             size_t length,
+                                             char **out_json);
+
+// Releases a string returned through `out_json`, null is ignored.
+//
+// # Safety
+// `json` must be null or a pointer returned by this library that was not freed yet.
+void xml_to_json_free(char *json);
+
+// Message of the last failed call on the current thread or null. The pointer is valid until the next call on that thread.
+const char *xml_to_json_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* XML_TO_JSON_PARSER_H */
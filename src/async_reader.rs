@@ -0,0 +1,236 @@
+use crate::encoding::{detect_encoding, parse_bytes};
+use crate::lexer::doctype_end;
+use crate::recover::is_valid_name;
+use crate::start_parser;
+use anyhow::*;
+use encoding_rs::{Decoder, DecoderResult};
+use futures_util::stream::{self, Stream};
+use memchr::{memchr, memmem};
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// Reads the whole input without blocking, conversion is CPU bound and runs on the blocking pool of the runtime.
+pub async fn parse_async<R: AsyncRead + Unpin>(mut reader: R) -> Result<Value> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes).await?;
+    tokio::task::spawn_blocking(move || parse_bytes(&bytes)).await?
+}
+
+// Every child of the root element as `{ name: value }`, converted on the blocking pool as soon as its close tag is read.
+// The input is decoded like in `parse_async`, only the record that is being read is kept in memory. The stream ends after the first error.
+pub fn parse_records<R: AsyncRead + Unpin>(reader: R) -> impl Stream<Item = Result<Value>> {
+    let records = RecordReader { reader, raw: vec![], decoder: None, splitter: RecordSplitter::default() };
+    stream::unfold(Some(records), |state| async move {
+        let mut records = state?;
+        loop {
+            if let Some(record) = records.splitter.next_record(false) {
+                let result = convert(record).await;
+                let state = if result.is_ok() { Some(records) } else { None };
+                return Some((result, state));
+            }
+
+            match records.read().await {
+                Result::Ok(true) => {}
+                Result::Ok(false) => return Some((convert(records.splitter.next_record(true)?).await, None)),
+                Err(error) => return Some((Err(error), None)),
+            }
+        }
+    })
+}
+
+async fn convert(record: Result<String>) -> Result<Value> {
+    let record = record?;
+    tokio::task::spawn_blocking(move || start_parser(&record)).await?
+}
+
+struct RecordReader<R> {
+    reader: R,
+    raw: Vec<u8>,
+    decoder: Option<Decoder>,
+    splitter: RecordSplitter,
+}
+
+impl<R: AsyncRead + Unpin> RecordReader<R> {
+    // Reads more input and decodes it into the buffer of the splitter, false at the end of input.
+    async fn read(&mut self) -> Result<bool> {
+        self.raw.reserve(8 * 1024);
+        let eof = self.reader.read_buf(&mut self.raw).await? == 0;
+
+        // The encoding is detected once the XML declaration is read, the same way `decode_bytes` does it.
+        if self.decoder.is_none() {
+            let declaration_read = self.raw.len() >= 5 && (!self.raw.starts_with(b"<?xml") || memmem::find(&self.raw, b"?>").is_some());
+            if !declaration_read && !eof { return Ok(true); }
+            let (encoding, bom_length) = detect_encoding(&self.raw)?;
+            self.raw.drain(..bom_length);
+            self.decoder = Some(encoding.new_decoder_without_bom_handling());
+        }
+
+        let decoder = self.decoder.as_mut().unwrap();
+        let length = decoder.max_utf8_buffer_length_without_replacement(self.raw.len()).ok_or_else(|| anyhow!("Input is too large"))?;
+        let mut text = String::with_capacity(length);
+        let (result, read) = decoder.decode_to_string_without_replacement(&self.raw, &mut text, eof);
+        if let DecoderResult::Malformed(..) = result { return Err(anyhow!("Input is not valid {}", decoder.encoding().name())); }
+        self.raw.drain(..read);
+        self.splitter.buffer.extend_from_slice(text.as_bytes());
+        Ok(!eof)
+    }
+}
+
+enum Markup {
+    Skipped,
+    Doctype,
+    Open { name: String, empty: bool },
+    Close { name: String },
+}
+
+// Tracks the nesting of the input read so far and cuts out the children of the root element.
+#[derive(Default)]
+struct RecordSplitter {
+    buffer: Vec<u8>,
+    position: usize,
+    depth: usize,
+    root: Option<String>,
+    root_closed: bool,
+    record_start: Option<usize>,
+}
+
+impl RecordSplitter {
+    // Text of the next record or error, None when more input is needed (or, at the end of input, when the document is complete).
+    // The buffer holds decoded UTF-8.
+    fn next_record(&mut self, eof: bool) -> Option<Result<String>> {
+        loop {
+            let text_end = memchr(b'<', &self.buffer[self.position..]).map_or(self.buffer.len(), |offset| self.position + offset);
+            if let Err(error) = self.text(self.position, text_end) { return Some(Err(error)); }
+            self.position = text_end;
+
+            if self.position == self.buffer.len() {
+                self.compact();
+                return if eof { self.finish() } else { None };
+            }
+
+            let start = self.position;
+            let Some((markup, length)) = markup(&self.buffer[start..]) else {
+                if eof { return Some(Err(anyhow!("Unexpected end of input"))); }
+                self.compact();
+                return None;
+            };
+            self.position = start + length;
+
+            let record = match markup {
+                Markup::Skipped => None,
+                Markup::Doctype if self.root.is_some() => return Some(Err(anyhow!("Document type declaration after the start of the root element"))),
+                Markup::Doctype => None,
+                Markup::Open { name, empty } => match self.open(name, empty, start) {
+                    Result::Ok(record) => record,
+                    Err(error) => return Some(Err(error)),
+                },
+                Markup::Close { name } => match self.close(name) {
+                    Result::Ok(record) => record,
+                    Err(error) => return Some(Err(error)),
+                },
+            };
+
+            if let Some(record_start) = record {
+                return Some(String::from_utf8(self.buffer[record_start..self.position].to_vec()).map_err(Error::from));
+            }
+        }
+    }
+
+    fn text(&self, start: usize, end: usize) -> Result<()> {
+        let outside_root = self.depth == 0;
+        if outside_root && !self.buffer[start..end].iter().all(u8::is_ascii_whitespace) {
+            return Err(anyhow!("Text outside of the root element"));
+        }
+        Ok(())
+    }
+
+    // Returns the start of a record that ends with this tag.
+    fn open(&mut self, name: String, empty: bool, start: usize) -> Result<Option<usize>> {
+        if !is_valid_name(&name) { return Err(anyhow!("Invalid tag name {:?}", name)); }
+        if self.root_closed { return Err(anyhow!("Element <{}> after the root element", name)); }
+
+        match (self.depth, empty) {
+            (0, _) => {
+                self.root = Some(name);
+                if empty { self.root_closed = true; } else { self.depth = 1; }
+            }
+            (1, true) => return Ok(Some(start)),
+            (1, false) => {
+                self.record_start = Some(start);
+                self.depth = 2;
+            }
+            (_, true) => {}
+            (_, false) => self.depth += 1,
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self, name: String) -> Result<Option<usize>> {
+        if !is_valid_name(&name) { return Err(anyhow!("Invalid tag name {:?}", name)); }
+        match self.depth {
+            0 => Err(anyhow!("Close tag </{}> has no open tag", name)),
+            1 => {
+                if self.root.as_deref() != Some(name.as_str()) { return Err(anyhow!("There are different open and close tags names")); }
+                self.depth = 0;
+                self.root_closed = true;
+                Ok(None)
+            }
+            2 => {
+                self.depth = 1;
+                Ok(self.record_start.take())
+            }
+            _ => {
+                self.depth -= 1;
+                Ok(None)
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Option<Result<String>> {
+        match &self.root {
+            None => Some(Err(anyhow!("XML is empty"))),
+            Some(root) if !self.root_closed => Some(Err(anyhow!("Element <{}> is not closed", root))),
+            Some(_) => None,
+        }
+    }
+
+    // Drops the input before the current record, the buffer never holds more than one record and a partial tag.
+    fn compact(&mut self) {
+        let keep = self.record_start.unwrap_or(self.position);
+        self.buffer.drain(..keep);
+        self.position -= keep;
+    }
+}
+
+// Markup at the start of `bytes` and its length, None if it is not complete yet.
+fn markup(bytes: &[u8]) -> Option<(Markup, usize)> {
+    if bytes.len() < 4 && b"<!--".starts_with(bytes) { return None; }
+    if bytes.starts_with(b"<!--") { return memmem::find(&bytes[4..], b"-->").map(|end| (Markup::Skipped, end + 7)); }
+    if bytes.len() < 9 && b"<!DOCTYPE".starts_with(bytes) { return None; }
+    if bytes.starts_with(b"<!DOCTYPE") { return doctype_end(bytes, 0, false).map(|end| (Markup::Doctype, end)); }
+    if bytes.starts_with(b"<?") { return memmem::find(&bytes[2..], b"?>").map(|end| (Markup::Skipped, end + 4)); }
+
+    // Quoted attribute values may contain '>'.
+    let mut quote = None;
+    let end = bytes.iter().enumerate().skip(1).find_map(|(index, byte)| {
+        match (quote, byte) {
+            (Some(q), byte) if *byte == q => quote = None,
+            (None, b'"' | b'\'') => quote = Some(*byte),
+            (None, b'>') => return Some(index),
+            _ => {}
+        }
+        None
+    })?;
+
+    let body = &bytes[1..end];
+    let (close, body) = match body.strip_prefix(b"/") {
+        Some(body) => (true, body),
+        None => (false, body),
+    };
+    let name_start = body.iter().position(|byte| !byte.is_ascii_whitespace()).unwrap_or(body.len());
+    let name_length = body[name_start..].iter().position(|byte| !(byte.is_ascii_alphanumeric() || b"-_.:".contains(byte))).unwrap_or(body.len() - name_start);
+    let name = String::from_utf8_lossy(&body[name_start..name_start + name_length]).into_owned();
+
+    let markup = if close { Markup::Close { name } } else { Markup::Open { name, empty: body.ends_with(b"/") } };
+    Some((markup, end + 1))
+}
@@ -0,0 +1,132 @@
+use crate::escape::unescape;
+use crate::tree::{build_document, DocumentParts, Nodes};
+use anyhow::*;
+use std::borrow::Cow;
+
+// Read-only counterpart of `tree::Document` that borrows from the input: names, comments and text without
+// entities are slices of it, attribute values and text are only owned when entity decoding changes them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document<'a> {
+    pub declaration: Option<Cow<'a, str>>,
+    pub doctype: Option<Cow<'a, str>>,
+    pub prolog: Vec<Node<'a>>,
+    pub root: Element<'a>,
+    pub epilog: Vec<Node<'a>>,
+}
+
+// `offset` is the byte offset of the '<' of the open tag in the input and `attribute_offsets` holds
+// the offsets of the attribute names in the order of `attributes`, validators report locations with them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Element<'a> {
+    pub name: Cow<'a, str>,
+    pub attributes: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    pub children: Vec<Node<'a>>,
+    pub offset: usize,
+    pub attribute_offsets: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node<'a> {
+    Element(Element<'a>),
+    Text(Cow<'a, str>),
+    Comment(Cow<'a, str>),
+}
+
+impl Document<'_> {
+    pub fn into_owned(self) -> Document<'static> {
+        Document {
+            declaration: self.declaration.map(|declaration| Cow::Owned(declaration.into_owned())),
+            doctype: self.doctype.map(|doctype| Cow::Owned(doctype.into_owned())),
+            prolog: self.prolog.into_iter().map(Node::into_owned).collect(),
+            root: self.root.into_owned(),
+            epilog: self.epilog.into_iter().map(Node::into_owned).collect(),
+        }
+    }
+}
+
+impl Node<'_> {
+    pub fn into_owned(self) -> Node<'static> {
+        match self {
+            Node::Element(element) => Node::Element(element.into_owned()),
+            Node::Text(text) => Node::Text(Cow::Owned(text.into_owned())),
+            Node::Comment(comment) => Node::Comment(Cow::Owned(comment.into_owned())),
+        }
+    }
+}
+
+impl<'a> Element<'a> {
+    pub fn into_owned(self) -> Element<'static> {
+        Element {
+            name: Cow::Owned(self.name.into_owned()),
+            attributes: self.attributes.into_iter().map(|(key, value)| (Cow::Owned(key.into_owned()), Cow::Owned(value.into_owned()))).collect(),
+            children: self.children.into_iter().map(Node::into_owned).collect(),
+            offset: self.offset,
+            attribute_offsets: self.attribute_offsets,
+        }
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_ref())
+    }
+
+    // Attributes together with the offsets of their names.
+    pub fn attributes_with_offsets(&self) -> impl Iterator<Item = (&str, &str, usize)> {
+        self.attributes.iter().zip(&self.attribute_offsets).map(|((name, value), offset)| (name.as_ref(), value.as_ref(), *offset))
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element<'a>> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    // Borrowed when the element has a single text node, which is the common case.
+    pub fn text(&self) -> Cow<'_, str> {
+        let mut texts = self.children.iter().filter_map(|child| match child {
+            Node::Text(text) => Some(text.as_ref()),
+            _ => None,
+        });
+
+        match (texts.next(), texts.next()) {
+            (None, _) => Cow::Borrowed(""),
+            (Some(text), None) => Cow::Borrowed(text),
+            (Some(first), Some(second)) => Cow::Owned([first, second].into_iter().chain(texts).collect()),
+        }
+    }
+}
+
+pub fn parse_borrowed(input: &str) -> Result<Document<'_>> {
+    let DocumentParts { declaration, doctype, prolog, root, epilog } = build_document::<Borrowed>(input)?;
+    Ok(Document { declaration: declaration.map(Cow::Borrowed), doctype: doctype.map(Cow::Borrowed), prolog, root, epilog })
+}
+
+// Same builder as `tree::parse_document`, text and attribute values are only owned when entity decoding changes them.
+struct Borrowed;
+
+impl<'a> Nodes<'a> for Borrowed {
+    type Element = Element<'a>;
+    type Node = Node<'a>;
+
+    fn element(name: &'a str, offset: usize, attributes: Vec<(&'a str, &'a str, usize)>) -> Element<'a> {
+        let attribute_offsets = attributes.iter().map(|(_, _, offset)| *offset).collect();
+        let attributes = attributes.into_iter().map(|(key, value, _)| (Cow::Borrowed(key), unescape(value))).collect();
+        Element { name: Cow::Borrowed(name), attributes, children: vec![], offset, attribute_offsets }
+    }
+
+    fn text(text: &'a str) -> Node<'a> {
+        Node::Text(unescape(text))
+    }
+
+    fn comment(comment: &'a str) -> Node<'a> {
+        Node::Comment(Cow::Borrowed(comment))
+    }
+
+    fn node(element: Element<'a>) -> Node<'a> {
+        Node::Element(element)
+    }
+
+    fn push(parent: &mut Element<'a>, child: Node<'a>) {
+        parent.children.push(child);
+    }
+}
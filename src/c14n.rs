@@ -0,0 +1,153 @@
+use crate::escape::*;
+use crate::tree::*;
+use anyhow::*;
+use std::collections::HashMap;
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+// Canonical XML 1.0 (https://www.w3.org/TR/xml-c14n) of a whole document, comments are kept only when `with_comments` is set.
+pub fn canonicalize(input: &str, with_comments: bool) -> Result<String> {
+    let input = input.replace("\r\n", "\n").replace('\r', "\n");
+    let document = parse_document(&input)?;
+    canonicalize_document(&document, with_comments)
+}
+
+pub fn canonicalize_document(document: &Document, with_comments: bool) -> Result<String> {
+    let mut output = String::new();
+    let scope = HashMap::from([("xml".to_string(), XML_NAMESPACE.to_string())]);
+
+    if with_comments {
+        for node in &document.prolog {
+            if let Node::Comment(comment) = node { output.push_str(&format!("<!--{}-->\n", comment)); }
+        }
+    }
+
+    write_element(&document.root, &scope, with_comments, &mut output)?;
+
+    if with_comments {
+        for node in &document.epilog {
+            if let Node::Comment(comment) = node { output.push_str(&format!("\n<!--{}-->", comment)); }
+        }
+    }
+
+    Ok(output)
+}
+
+fn escape_c14n_text(text: &str) -> String {
+    escape(text, |c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        '\r' => Some("&#xD;"),
+        _ => None,
+    }).into_owned()
+}
+
+fn escape_c14n_attribute(value: &str) -> String {
+    escape(value, |c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '"' => Some("&quot;"),
+        '\t' => Some("&#x9;"),
+        '\n' => Some("&#xA;"),
+        '\r' => Some("&#xD;"),
+        _ => None,
+    }).into_owned()
+}
+
+// Attribute value normalization: literal whitespace becomes a space, references are expanded after that.
+fn normalize_attribute_value(value: &str) -> Result<String> {
+    expand_references(&value.replace(['\t', '\n', '\r'], " "))
+}
+
+// Unknown entities can not be expanded, escaping them again would change the document.
+fn expand_references(text: &str) -> Result<String> {
+    if let Some(reference) = unknown_reference(text) { return Err(anyhow!("Unknown entity reference {}", reference)); }
+    Ok(unescape(text).into_owned())
+}
+
+fn split_name(name: &str) -> (&str, &str) {
+    match name.split_once(':') {
+        Some((prefix, local_name)) => (prefix, local_name),
+        None => ("", name),
+    }
+}
+
+fn namespace_of<'a>(prefix: &str, scope: &'a HashMap<String, String>) -> Result<&'a str> {
+    scope.get(prefix).map(String::as_str).ok_or_else(|| anyhow!("Namespace prefix {} is not declared", prefix))
+}
+
+enum Item<'a> {
+    Open(&'a Element),
+    Close(&'a Element),
+    Node(&'a Node),
+}
+
+// Elements are kept on an explicit stack together with their namespace scopes, so deep documents do not overflow.
+fn write_element(root: &Element, root_scope: &HashMap<String, String>, with_comments: bool, output: &mut String) -> Result<()> {
+    let mut scopes = vec![root_scope.clone()];
+    let mut stack = vec![Item::Open(root)];
+
+    while let Some(item) = stack.pop() {
+        match item {
+            Item::Open(element) => {
+                let scope = write_open_tag(element, scopes.last().unwrap(), output)?;
+                scopes.push(scope);
+                stack.push(Item::Close(element));
+                stack.extend(element.children.iter().rev().map(Item::Node));
+            }
+            Item::Close(element) => {
+                scopes.pop();
+                output.push_str(&format!("</{}>", element.name));
+            }
+            Item::Node(Node::Element(child)) => stack.push(Item::Open(child)),
+            Item::Node(Node::Text(text)) => output.push_str(&escape_c14n_text(&expand_references(text)?)),
+            Item::Node(Node::Comment(comment)) => {
+                if with_comments { output.push_str(&format!("<!--{}-->", comment)); }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Writes the start tag and returns the namespace scope of the element.
+fn write_open_tag(element: &Element, parent_scope: &HashMap<String, String>, output: &mut String) -> Result<HashMap<String, String>> {
+    let mut scope = parent_scope.clone();
+    let mut namespaces = vec![];
+    let mut attributes = vec![];
+
+    for (attribute_name, attribute_value) in &element.attributes {
+        let value = normalize_attribute_value(attribute_value)?;
+        let prefix = if attribute_name == "xmlns" { Some("") } else { attribute_name.strip_prefix("xmlns:") };
+
+        match prefix {
+            Some(prefix) => {
+                let in_scope = parent_scope.get(prefix).map(String::as_str).unwrap_or("");
+                if in_scope != value { namespaces.push((attribute_name.as_str(), value.clone())); }
+                scope.insert(prefix.to_string(), value);
+            }
+            None => attributes.push((attribute_name.as_str(), value)),
+        }
+    }
+
+    let (prefix, _) = split_name(&element.name);
+    if !prefix.is_empty() { namespace_of(prefix, &scope)?; }
+
+    let mut sorted_attributes = vec![];
+    for (attribute_name, value) in attributes {
+        let (prefix, local_name) = split_name(attribute_name);
+        let namespace = if prefix.is_empty() { "" } else { namespace_of(prefix, &scope)? };
+        sorted_attributes.push((namespace.to_string(), local_name, attribute_name, value));
+    }
+
+    // The default namespace declaration has an empty prefix, so it is sorted first.
+    namespaces.sort_by_key(|(name, _)| name.strip_prefix("xmlns:").unwrap_or(""));
+    sorted_attributes.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+
+    output.push('<');
+    output.push_str(&element.name);
+    for (name, value) in namespaces { output.push_str(&format!(" {}=\"{}\"", name, escape_c14n_attribute(&value))); }
+    for (_, _, name, value) in sorted_attributes { output.push_str(&format!(" {}=\"{}\"", name, escape_c14n_attribute(&value))); }
+    output.push('>');
+    Ok(scope)
+}
@@ -0,0 +1,169 @@
+use crate::lexer::{self, nesting_depth};
+use crate::{drop_value, line_column, Grammar, Rule, MAX_PEST_DEPTH};
+use pest::Parser;
+use pest::error::LineColLocation;
+use pest::iterators::{Pair, Pairs};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    Syntax,
+    Structure,
+    Validity,
+    Warning,
+}
+
+impl DiagnosticKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticKind::Syntax => "syntax",
+            DiagnosticKind::Structure => "structure",
+            DiagnosticKind::Validity => "validity",
+            DiagnosticKind::Warning => "warning",
+        }
+    }
+
+    // Exit code used by the `check` subcommand, syntax errors take precedence over structure errors, warnings do not fail.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DiagnosticKind::Syntax => 2,
+            DiagnosticKind::Structure => 3,
+            DiagnosticKind::Validity => 4,
+            DiagnosticKind::Warning => 0,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            DiagnosticKind::Syntax => "syntax error",
+            DiagnosticKind::Structure => "structure error",
+            DiagnosticKind::Validity => "validity error",
+            DiagnosticKind::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn to_text(&self, file: &str) -> String {
+        format!("{}:{}:{}: {}: {}", file, self.line, self.column, self.kind.label(), self.message)
+    }
+
+    // Validity error at a byte offset of `input`: the start of the tag for elements, the name for attributes.
+    #[cfg(feature = "validation")]
+    pub(crate) fn validity(input: &str, offset: usize, message: String) -> Diagnostic {
+        let (line, column) = line_column(input, offset);
+        Diagnostic { kind: DiagnosticKind::Validity, line, column, message }
+    }
+
+    pub fn to_json(&self, file: &str) -> Value {
+        json!({
+            "file": file,
+            "line": self.line,
+            "column": self.column,
+            "kind": self.kind.as_str(),
+            "message": self.message,
+        })
+    }
+}
+
+// Exit code for a set of diagnostics: 0 without errors, syntax errors take precedence over structure and validity errors.
+pub fn exit_code(diagnostics: &[Diagnostic]) -> i32 {
+    diagnostics.iter().map(|diagnostic| diagnostic.kind.exit_code()).filter(|code| *code != 0).min().unwrap_or(0)
+}
+
+// Pest stops at the first syntax error, so there is at most one syntax diagnostic and structure is only checked without it.
+pub fn check(input: &str) -> Vec<Diagnostic> {
+    if input.trim().is_empty() {
+        return vec![Diagnostic { kind: DiagnosticKind::Syntax, line: 1, column: 1, message: "XML is empty".to_string() }];
+    }
+
+    // Pest recurses for every nested element, deeper documents are checked by the lexer like in `start_parser`.
+    if nesting_depth(input.as_bytes()) > MAX_PEST_DEPTH {
+        return match lexer::convert(input) {
+            Ok(value) => {
+                drop_value(value);
+                vec![]
+            }
+            Err(rejection) => {
+                let (line, column) = line_column(input, rejection.offset);
+                let (kind, message) = if rejection.mismatched_close_tag {
+                    (DiagnosticKind::Structure, "Close tag does not match its open tag")
+                } else {
+                    (DiagnosticKind::Syntax, "Invalid XML")
+                };
+                vec![Diagnostic { kind, line, column, message: message.to_string() }]
+            }
+        };
+    }
+
+    let parsed = match Grammar::parse(Rule::xml, input) {
+        Ok(mut pairs) => pairs.next().unwrap(),
+        Err(error) => {
+            let (line, column) = match error.line_col {
+                LineColLocation::Pos(position) => position,
+                LineColLocation::Span(start, _) => start,
+            };
+            return vec![Diagnostic { kind: DiagnosticKind::Syntax, line, column, message: error.variant.message().to_string() }];
+        }
+    };
+
+    let mut diagnostics = vec![];
+    for pair in parsed.into_inner() {
+        if pair.as_rule() == Rule::element { check_element(pair, &mut diagnostics); }
+    }
+    diagnostics
+}
+
+// Open elements are kept on an explicit stack like in `parse_element`.
+fn check_element(pair: Pair<Rule>, diagnostics: &mut Vec<Diagnostic>) {
+    let mut stack = vec![CheckFrame::new(pair)];
+
+    while let Some(frame) = stack.last_mut() {
+        let Some(next) = frame.pairs.next() else {
+            stack.pop();
+            continue;
+        };
+
+        match next.as_rule() {
+            Rule::element => stack.push(CheckFrame::new(next)),
+            Rule::close_tag => {
+                let (line, column) = next.line_col();
+                let close_name = next.into_inner().next().unwrap().as_str();
+                if close_name != frame.name {
+                    diagnostics.push(Diagnostic {
+                        kind: DiagnosticKind::Structure,
+                        line,
+                        column,
+                        message: format!("Close tag </{}> does not match open tag <{}> at {}:{}", close_name, frame.name, frame.line, frame.column),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct CheckFrame<'a> {
+    name: &'a str,
+    line: usize,
+    column: usize,
+    pairs: Pairs<'a, Rule>,
+}
+
+impl<'a> CheckFrame<'a> {
+    fn new(pair: Pair<'a, Rule>) -> Self {
+        let mut pairs = pair.into_inner();
+        let open_tag = pairs.next().unwrap();
+        let (line, column) = open_tag.line_col();
+        let name = open_tag.into_inner().next().unwrap().as_str();
+        CheckFrame { name, line, column, pairs }
+    }
+}
@@ -0,0 +1,254 @@
+use crate::schema::{ScalarType, Shape};
+use std::collections::HashSet;
+
+// Types for the structure inferred from sample documents. Rust structs are read with `de::from_str` and written with `ser::to_string`,
+// so fields follow the converter naming (`_attribute`, `_text`) and repeated elements are `Vec`s.
+pub fn rust_types(shape: &Shape) -> String {
+    let mut generator = RustGenerator { structs: vec![], type_names: TypeNames::new(RUST_RESERVED) };
+    for (name, root) in &shape.children { generator.element_type(name, "", root); }
+
+    let mut output = "use serde::{Deserialize, Serialize};\n".to_string();
+    for item in generator.structs {
+        output.push('\n');
+        output.push_str(&item);
+    }
+    output
+}
+
+// Declarations of the JSON of `start_parser`. An element is a string when it only has text and an interface otherwise,
+// elements that were repeated can also be an array.
+pub fn typescript_types(shape: &Shape) -> String {
+    let mut generator = TsGenerator { interfaces: vec![], type_names: TypeNames::new(TS_RESERVED) };
+    generator.interface("ParsedDocument", shape);
+    generator.interfaces.join("\n")
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn", "else", "enum", "extern",
+    "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv",
+    "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized",
+    "use", "virtual", "where", "while", "yield",
+];
+
+// Keywords that can not be raw identifiers and names of types used by the generated code.
+const RUST_RESERVED: &[&str] = &["crate", "self", "Self", "super", "Deserialize", "Option", "Serialize", "String", "Vec"];
+
+fn words(name: &str) -> impl Iterator<Item = &str> {
+    name.split(|character: char| !character.is_ascii_alphanumeric()).filter(|word| !word.is_empty())
+}
+
+fn pascal_case(name: &str) -> String {
+    words(name).map(|word| word[..1].to_ascii_uppercase() + &word[1..]).collect()
+}
+
+// camelCase and separators become underscores.
+fn snake_case(name: &str) -> String {
+    let mut output = String::new();
+    for word in words(name) {
+        if !output.is_empty() { output.push('_'); }
+        let mut previous_lowercase = false;
+        for character in word.chars() {
+            if character.is_ascii_uppercase() && previous_lowercase { output.push('_'); }
+            previous_lowercase = character.is_ascii_lowercase() || character.is_ascii_digit();
+            output.push(character.to_ascii_lowercase());
+        }
+    }
+    output
+}
+
+fn rust_identifier(identifier: String, fallback: &str) -> String {
+    let identifier = if identifier.is_empty() { fallback.to_string() } else { identifier };
+    if identifier.starts_with(|character: char| character.is_ascii_digit()) { return format!("{}_{}", fallback, identifier); }
+    if RUST_RESERVED.contains(&identifier.as_str()) { return format!("{}_", identifier); }
+    if RUST_KEYWORDS.contains(&identifier.as_str()) { return format!("r#{}", identifier); }
+    identifier
+}
+
+fn unique(name: String, taken: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut number = 2;
+    while !taken.insert(candidate.clone()) {
+        candidate = format!("{}{}", name, number);
+        number += 1;
+    }
+    candidate
+}
+
+fn rust_scalar(scalar: ScalarType) -> &'static str {
+    match scalar {
+        ScalarType::Boolean => "bool",
+        ScalarType::Integer => "i64",
+        ScalarType::Number => "f64",
+        ScalarType::Date | ScalarType::DateTime | ScalarType::String => "String",
+    }
+}
+
+// Names of generated types, `reserved` are names that the generated code already uses.
+struct TypeNames {
+    taken: HashSet<String>,
+    reserved: &'static [&'static str],
+}
+
+impl TypeNames {
+    fn new(reserved: &'static [&'static str]) -> Self {
+        TypeNames { taken: HashSet::new(), reserved }
+    }
+
+    fn identifier(&self, name: String) -> String {
+        let name = if name.starts_with(|character: char| character.is_ascii_alphabetic()) { name } else { format!("Element{}", name) };
+        if self.reserved.contains(&name.as_str()) { format!("{}_", name) } else { name }
+    }
+
+    // Elements with the same name in different places get the parent type name as prefix.
+    fn name(&mut self, name: &str, parent_type: &str) -> String {
+        let type_name = self.identifier(pascal_case(name));
+        if !self.taken.contains(&type_name) || parent_type.is_empty() { return unique(type_name, &mut self.taken); }
+        let prefixed = self.identifier(format!("{}{}", parent_type.trim_end_matches('_'), pascal_case(name)));
+        unique(prefixed, &mut self.taken)
+    }
+}
+
+struct RustGenerator {
+    structs: Vec<String>,
+    type_names: TypeNames,
+}
+
+impl RustGenerator {
+    // Elements that are text or empty are a string, the text of an empty element is empty.
+    fn scalar_type(shape: &Shape) -> Option<&'static str> {
+        match shape.string {
+            Some(scalar) if shape.objects == 0 => Some(rust_scalar(scalar)),
+            Some(_) if shape.properties.is_empty() && shape.children.is_empty() => Some("String"),
+            _ => None,
+        }
+    }
+
+    fn element_type(&mut self, name: &str, parent_type: &str, shape: &Shape) -> String {
+        match Self::scalar_type(shape) {
+            Some(scalar) => scalar.to_string(),
+            None => {
+                let type_name = self.type_names.name(name, parent_type);
+                self.struct_type(&type_name, shape);
+                type_name
+            }
+        }
+    }
+
+    fn struct_type(&mut self, type_name: &str, shape: &Shape) {
+        let index = self.structs.len();
+        self.structs.push(String::new());
+
+        let always_object = shape.string.is_none();
+        let mut field_names = HashSet::new();
+        let mut fields = String::new();
+
+        for (key, property) in shape.properties.iter().filter(|(key, _)| *key != "_text") {
+            let scalar = rust_scalar(property.scalar);
+            let field_type = if property.count == shape.objects && always_object { scalar.to_string() } else { format!("Option<{}>", scalar) };
+            fields.push_str(&field(&mut field_names, key, &key[1..], &field_type, false));
+        }
+
+        // `de` always passes text to a `_text` field, text that is not in every sample may be empty.
+        let text = shape.properties.get("_text");
+        if text.is_some() || shape.string.is_some() {
+            let scalar = [text.map(|text| text.scalar), shape.string].into_iter().flatten().reduce(ScalarType::merge).unwrap();
+            let field_type = if text.is_some_and(|text| text.count == shape.objects) { rust_scalar(scalar) } else { "String" };
+            fields.push_str(&field(&mut field_names, "_text", "text", field_type, false));
+        }
+
+        // Children are named before their own children, so the closest element gets the plain name.
+        let child_types: Vec<_> = shape.children.iter().map(|(child_name, child)| match Self::scalar_type(child) {
+            Some(scalar) => (scalar.to_string(), false),
+            None => (self.type_names.name(child_name, type_name), true),
+        }).collect();
+
+        for ((child_name, child), (child_type, is_struct)) in shape.children.iter().zip(child_types) {
+            if is_struct { self.struct_type(&child_type, child); }
+            let field_text = if child.max_occurs > 1 {
+                field(&mut field_names, child_name, child_name, &format!("Vec<{}>", child_type), true)
+            } else if child.is_required_in(shape) && always_object {
+                field(&mut field_names, child_name, child_name, &child_type, false)
+            } else {
+                field(&mut field_names, child_name, child_name, &format!("Option<{}>", child_type), false)
+            };
+            fields.push_str(&field_text);
+        }
+
+        let body = if fields.is_empty() { "{}".to_string() } else { format!("{{\n{}}}", fields) };
+        self.structs[index] = format!("#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct {} {}\n", type_name, body);
+    }
+}
+
+fn field(field_names: &mut HashSet<String>, key: &str, name: &str, field_type: &str, default: bool) -> String {
+    let name = unique(rust_identifier(snake_case(name), "field"), field_names);
+
+    let mut serde = vec![];
+    if name.trim_start_matches("r#") != key { serde.push(format!("rename = {:?}", key)); }
+    if default { serde.push("default".to_string()); }
+
+    let attribute = if serde.is_empty() { String::new() } else { format!("    #[serde({})]\n", serde.join(", ")) };
+    format!("{}    pub {}: {},\n", attribute, name, field_type)
+}
+
+// Global types that declarations would shadow and the name of the document interface.
+const TS_RESERVED: &[&str] = &["Array", "Boolean", "Date", "Error", "Function", "Number", "Object", "ParsedDocument", "Promise", "Record", "String", "Symbol"];
+
+fn ts_scalar(scalar: ScalarType) -> &'static str {
+    if scalar == ScalarType::Boolean { "\"true\" | \"false\"" } else { "string" }
+}
+
+fn ts_member(key: &str, required: bool, member_type: &str) -> String {
+    let is_identifier = key.starts_with(|character: char| character.is_ascii_alphabetic() || character == '_' || character == '$')
+        && key.chars().all(|character| character.is_ascii_alphanumeric() || character == '_' || character == '$');
+    let key = if is_identifier { key.to_string() } else { format!("{:?}", key) };
+    format!("    {}{}: {};\n", key, if required { "" } else { "?" }, member_type)
+}
+
+struct TsGenerator {
+    interfaces: Vec<String>,
+    type_names: TypeNames,
+}
+
+impl TsGenerator {
+    // Objects without attributes, text and children are `{}` in the output and get no interface.
+    fn interface_name(&mut self, name: &str, parent_type: &str, shape: &Shape) -> Option<String> {
+        let has_members = !shape.properties.is_empty() || !shape.children.is_empty();
+        (shape.objects > 0 && has_members).then(|| self.type_names.name(name, parent_type))
+    }
+
+    fn element_type(shape: &Shape, interface: Option<String>) -> String {
+        let object = interface.or_else(|| (shape.objects > 0).then(|| "Record<string, never>".to_string()));
+        match (shape.string, object) {
+            (Some(scalar), Some(object)) => format!("{} | {}", ts_scalar(scalar), object),
+            (Some(scalar), None) => ts_scalar(scalar).to_string(),
+            (None, Some(object)) => object,
+            (None, None) => "never".to_string(),
+        }
+    }
+
+    fn interface(&mut self, type_name: &str, shape: &Shape) {
+        let index = self.interfaces.len();
+        self.interfaces.push(String::new());
+
+        let mut members = String::new();
+        for (key, property) in &shape.properties {
+            members.push_str(&ts_member(key, property.count == shape.objects, ts_scalar(property.scalar)));
+        }
+
+        // Children are named before their own children, so the closest element gets the plain name.
+        let interfaces: Vec<_> = shape.children.iter().map(|(child_name, child)| self.interface_name(child_name, type_name, child)).collect();
+        for ((child_name, child), interface) in shape.children.iter().zip(interfaces) {
+            if let Some(interface) = &interface { self.interface(interface, child); }
+            let element_type = Self::element_type(child, interface);
+            let member_type = match child.max_occurs > 1 {
+                true if element_type.contains(' ') => format!("{0} | ({0})[]", element_type),
+                true => format!("{0} | {0}[]", element_type),
+                false => element_type,
+            };
+            members.push_str(&ts_member(child_name, child.is_required_in(shape), &member_type));
+        }
+
+        let body = if members.is_empty() { "{}".to_string() } else { format!("{{\n{}}}", members) };
+        self.interfaces[index] = format!("export interface {} {}\n", type_name, body);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::escape::unescape;
+use crate::tree::*;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use std::fmt;
+
+// Reads XML straight into Rust types. Attributes are fields named `_attribute`, text is `_text`,
+// repeated child elements are sequences and enum variants are chosen by element names.
+pub fn from_str<T: DeserializeOwned>(input: &str) -> anyhow::Result<T> {
+    let document = parse_document(input)?;
+    Ok(T::deserialize(ElementDeserializer { element: &document.root, variant_by_name: true })?)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Error(message.to_string())
+    }
+}
+
+fn element_text(element: &Element) -> String {
+    unescape(&element.trimmed_text()).into_owned()
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let text = self.text();
+                let value = text.trim().parse().map_err(|_| Error(format!("Cannot parse {:?} as {}", text, stringify!($visit).trim_start_matches("visit_"))))?;
+                visitor.$visit(value)
+            }
+        )*
+    };
+}
+
+macro_rules! parsed_scalars {
+    () => {
+        deserialize_parsed! {
+            deserialize_bool => visit_bool,
+            deserialize_i8 => visit_i8,
+            deserialize_i16 => visit_i16,
+            deserialize_i32 => visit_i32,
+            deserialize_i64 => visit_i64,
+            deserialize_i128 => visit_i128,
+            deserialize_u8 => visit_u8,
+            deserialize_u16 => visit_u16,
+            deserialize_u32 => visit_u32,
+            deserialize_u64 => visit_u64,
+            deserialize_u128 => visit_u128,
+            deserialize_f32 => visit_f32,
+            deserialize_f64 => visit_f64,
+            deserialize_char => visit_char,
+        }
+    };
+}
+
+// Attribute values and `_text`.
+struct TextDeserializer(String);
+
+impl TextDeserializer {
+    fn text(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'de> de::Deserializer<'de> for TextDeserializer {
+    type Error = Error;
+
+    parsed_scalars!();
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ElementDeserializer<'a> {
+    element: &'a Element,
+    // Inside a list of different children the element name is the enum variant, otherwise variants come from the content.
+    variant_by_name: bool,
+}
+
+impl ElementDeserializer<'_> {
+    fn text(&self) -> String {
+        element_text(self.element)
+    }
+
+    fn is_simple(&self) -> bool {
+        self.element.attributes.is_empty() && self.element.elements().next().is_none()
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ElementDeserializer<'a> {
+    type Error = Error;
+
+    parsed_scalars!();
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.is_simple() { visitor.visit_string(self.text()) } else { self.deserialize_map(visitor) }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.text())
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.text())
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_byte_buf(self.text().into_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_byte_buf(self.text().into_bytes())
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // An element used as a sequence yields its child elements.
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let elements = self.element.elements().map(|element| ElementDeserializer { element, variant_by_name: true });
+        visitor.visit_seq(de::value::SeqDeserializer::new(elements))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(ElementMapAccess::new(self.element, false))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(ElementMapAccess::new(self.element, fields.contains(&"_text")))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        if self.variant_by_name { return visitor.visit_enum(self); }
+
+        match self.element.elements().next() {
+            Some(element) => visitor.visit_enum(ElementDeserializer { element, variant_by_name: true }),
+            None => visitor.visit_enum(self.text().into_deserializer()),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ElementDeserializer<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for ElementDeserializer<'a> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Self), Error> {
+        let variant = seed.deserialize(self.element.name.as_str().into_deserializer())?;
+        Ok((variant, ElementDeserializer { variant_by_name: false, ..self }))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for ElementDeserializer<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_struct(self, "", fields, visitor)
+    }
+}
+
+// All child elements with the same name, deserialized as one value or as a sequence.
+struct GroupDeserializer<'a>(Vec<&'a Element>);
+
+impl<'a> GroupDeserializer<'a> {
+    fn single(self) -> Result<ElementDeserializer<'a>, Error> {
+        match self.0.as_slice() {
+            [element] => Ok(ElementDeserializer { element, variant_by_name: false }),
+            elements => Err(Error(format!("Element {} is repeated {} times where a single value is expected", elements[0].name, elements.len()))),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident($($arg:ident: $ty:ty),*),)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Error> {
+                self.single()?.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for GroupDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.len() == 1 { self.single()?.deserialize_any(visitor) } else { self.deserialize_seq(visitor) }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let elements = self.0.into_iter().map(|element| ElementDeserializer { element, variant_by_name: false });
+        visitor.visit_seq(de::value::SeqDeserializer::new(elements))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_single! {
+        deserialize_bool(), deserialize_i8(), deserialize_i16(), deserialize_i32(), deserialize_i64(), deserialize_i128(),
+        deserialize_u8(), deserialize_u16(), deserialize_u32(), deserialize_u64(), deserialize_u128(),
+        deserialize_f32(), deserialize_f64(), deserialize_char(), deserialize_str(), deserialize_string(),
+        deserialize_bytes(), deserialize_byte_buf(), deserialize_unit(), deserialize_identifier(), deserialize_map(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_newtype_struct(name: &'static str),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]),
+    }
+}
+
+enum Entry<'a> {
+    Text(String),
+    Group(Vec<&'a Element>),
+}
+
+struct ElementMapAccess<'a> {
+    entries: std::vec::IntoIter<(String, Entry<'a>)>,
+    value: Option<Entry<'a>>,
+}
+
+impl<'a> ElementMapAccess<'a> {
+    fn new(element: &'a Element, with_empty_text: bool) -> Self {
+        let mut entries = vec![];
+
+        for (attribute_name, attribute_value) in &element.attributes {
+            entries.push((format!("_{}", attribute_name), Entry::Text(unescape(attribute_value).into_owned())));
+        }
+
+        let mut groups: Vec<(String, Entry<'a>)> = vec![];
+        for child in element.elements() {
+            match groups.iter_mut().find(|(name, _)| *name == child.name) {
+                Some((_, Entry::Group(group))) => group.push(child),
+                _ => groups.push((child.name.clone(), Entry::Group(vec![child]))),
+            }
+        }
+        entries.extend(groups);
+
+        let text = element_text(element);
+        if !text.is_empty() || with_empty_text { entries.push(("_text".to_string(), Entry::Text(text))); }
+
+        ElementMapAccess { entries: entries.into_iter(), value: None }
+    }
+}
+
+impl<'de, 'a> de::MapAccess<'de> for ElementMapAccess<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, Error> {
+        match self.value.take() {
+            Some(Entry::Text(text)) => seed.deserialize(TextDeserializer(text)),
+            Some(Entry::Group(group)) => seed.deserialize(GroupDeserializer(group)),
+            None => Err(Error("Value is requested before key".to_string())),
+        }
+    }
+}
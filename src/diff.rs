@@ -0,0 +1,152 @@
+use crate::clone_value;
+use crate::escape::escape_pointer;
+use crate::tree::*;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added { path: String, value: String },
+    Removed { path: String, value: String },
+    Changed { path: String, old: String, new: String },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {}: {}", path, value),
+            Change::Removed { path, value } => write!(f, "- {}: {}", path, value),
+            Change::Changed { path, old, new } => write!(f, "~ {}: {} -> {}", path, old, new),
+        }
+    }
+}
+
+// Compares two documents element by element, attribute order and whitespace in text are not significant.
+pub fn diff_documents(old: &Document, new: &Document) -> Vec<Change> {
+    let mut changes = vec![];
+
+    if old.root.name == new.root.name {
+        diff_elements(&old.root, &new.root, &format!("/{}", old.root.name), &mut changes);
+    } else {
+        changes.push(Change::Removed { path: format!("/{}", old.root.name), value: old.root.content_to_json().to_string() });
+        changes.push(Change::Added { path: format!("/{}", new.root.name), value: new.root.content_to_json().to_string() });
+    }
+
+    changes
+}
+
+fn normalized_text(element: &Element) -> String {
+    element.text().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn diff_elements(old: &Element, new: &Element, path: &str, changes: &mut Vec<Change>) {
+    let attribute_names: BTreeSet<&str> = old.attributes.iter().chain(&new.attributes).map(|(name, _)| name.as_str()).collect();
+
+    for attribute_name in attribute_names {
+        let attribute_path = format!("{}/@{}", path, attribute_name);
+        match (old.attribute(attribute_name), new.attribute(attribute_name)) {
+            (Some(old_value), None) => changes.push(Change::Removed { path: attribute_path, value: format!("{:?}", old_value) }),
+            (None, Some(new_value)) => changes.push(Change::Added { path: attribute_path, value: format!("{:?}", new_value) }),
+            (Some(old_value), Some(new_value)) if old_value != new_value => {
+                changes.push(Change::Changed { path: attribute_path, old: format!("{:?}", old_value), new: format!("{:?}", new_value) });
+            }
+            _ => {}
+        }
+    }
+
+    let (old_text, new_text) = (normalized_text(old), normalized_text(new));
+    let text_path = format!("{}/text()", path);
+    match (old_text.is_empty(), new_text.is_empty()) {
+        (false, true) => changes.push(Change::Removed { path: text_path, value: format!("{:?}", old_text) }),
+        (true, false) => changes.push(Change::Added { path: text_path, value: format!("{:?}", new_text) }),
+        (false, false) if old_text != new_text => {
+            changes.push(Change::Changed { path: text_path, old: format!("{:?}", old_text), new: format!("{:?}", new_text) });
+        }
+        _ => {}
+    }
+
+    // Children with the same name are matched by their position among siblings with that name.
+    let mut names: Vec<&str> = vec![];
+    for element in old.elements().chain(new.elements()) {
+        if !names.contains(&element.name.as_str()) { names.push(&element.name); }
+    }
+
+    for name in names {
+        let old_children: Vec<&Element> = old.elements().filter(|element| element.name == name).collect();
+        let new_children: Vec<&Element> = new.elements().filter(|element| element.name == name).collect();
+        let repeated = old_children.len() > 1 || new_children.len() > 1;
+
+        for index in 0..old_children.len().max(new_children.len()) {
+            let child_path = if repeated { format!("{}/{}[{}]", path, name, index + 1) } else { format!("{}/{}", path, name) };
+            match (old_children.get(index), new_children.get(index)) {
+                (Some(old_child), Some(new_child)) => diff_elements(old_child, new_child, &child_path, changes),
+                (Some(old_child), None) => changes.push(Change::Removed { path: child_path, value: old_child.content_to_json().to_string() }),
+                (None, Some(new_child)) => changes.push(Change::Added { path: child_path, value: new_child.content_to_json().to_string() }),
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+// RFC 6902 JSON Patch that turns the converted `old` value into the converted `new` value.
+// Values are compared with an explicit stack, the converter produces values of any depth.
+pub fn json_patch(old: &Value, new: &Value) -> Value {
+    enum Step<'a> {
+        Compare(&'a Value, &'a Value, String),
+        Operation(Value),
+    }
+
+    let mut operations = vec![];
+    let mut stack = vec![Step::Compare(old, new, String::new())];
+    while let Some(step) = stack.pop() {
+        let (old, new, pointer) = match step {
+            Step::Compare(old, new, pointer) => (old, new, pointer),
+            Step::Operation(operation) => {
+                operations.push(operation);
+                continue;
+            }
+        };
+
+        let mut steps = vec![];
+        match (old, new) {
+            (Value::Object(old_object), Value::Object(new_object)) => {
+                for (key, old_value) in old_object {
+                    let path = format!("{}/{}", pointer, escape_pointer(key));
+                    steps.push(match new_object.get(key) {
+                        Some(new_value) => Step::Compare(old_value, new_value, path),
+                        None => Step::Operation(operation("remove", path, None)),
+                    });
+                }
+                for (key, new_value) in new_object {
+                    if !old_object.contains_key(key) {
+                        steps.push(Step::Operation(operation("add", format!("{}/{}", pointer, escape_pointer(key)), Some(new_value))));
+                    }
+                }
+            }
+            (Value::Array(old_array), Value::Array(new_array)) => {
+                for (index, (old_value, new_value)) in old_array.iter().zip(new_array).enumerate() {
+                    steps.push(Step::Compare(old_value, new_value, format!("{}/{}", pointer, index)));
+                }
+                for index in (new_array.len()..old_array.len()).rev() {
+                    steps.push(Step::Operation(operation("remove", format!("{}/{}", pointer, index), None)));
+                }
+                for (index, new_value) in new_array.iter().enumerate().skip(old_array.len()) {
+                    steps.push(Step::Operation(operation("add", format!("{}/{}", pointer, index), Some(new_value))));
+                }
+            }
+            _ if old != new => steps.push(Step::Operation(operation("replace", pointer, Some(new)))),
+            _ => {}
+        }
+        stack.extend(steps.into_iter().rev());
+    }
+    Value::Array(operations)
+}
+
+fn operation(op: &str, path: String, value: Option<&Value>) -> Value {
+    let mut operation = Map::new();
+    operation.insert("op".to_string(), Value::String(op.to_string()));
+    operation.insert("path".to_string(), Value::String(path));
+    if let Some(value) = value { operation.insert("value".to_string(), clone_value(value)); }
+    Value::Object(operation)
+}
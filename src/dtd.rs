@@ -0,0 +1,688 @@
+use crate::borrowed::{self, parse_borrowed};
+use crate::check::Diagnostic;
+use crate::encoding::decode_bytes;
+use crate::tree;
+use crate::{start_parser, Grammar, Rule};
+use anyhow::*;
+use pest::Parser;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Element and attribute-list declarations of a DTD. Parameter entities and conditional sections are expanded,
+// general entities and notations are read but not used.
+#[derive(Debug, Clone, Default)]
+pub struct Dtd {
+    elements: HashMap<String, ContentModel>,
+    attributes: HashMap<String, Vec<AttributeDef>>,
+}
+
+#[derive(Debug, Clone)]
+enum ContentModel {
+    Empty,
+    Any,
+    Mixed(Vec<String>),
+    // Children are matched as a string of `<name>` tokens.
+    Children { pattern: Regex, source: String },
+}
+
+#[derive(Debug, Clone)]
+struct AttributeDef {
+    name: String,
+    kind: AttributeKind,
+    default: AttributeDefault,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AttributeKind {
+    Cdata,
+    Id,
+    IdRef,
+    IdRefs,
+    Entity,
+    Entities,
+    NmToken,
+    NmTokens,
+    Enumeration(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AttributeDefault {
+    Required,
+    Implied,
+    Fixed(String),
+    Value(String),
+}
+
+#[derive(Debug, Clone)]
+enum ParameterEntity {
+    Internal(String),
+    External(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Literal(&'a str),
+    Group(&'a str),
+}
+
+// Nesting of parameter entity references, deeper references are circular.
+const MAX_DEPTH: usize = 64;
+
+fn is_name_char(character: char) -> bool {
+    character.is_alphanumeric() || matches!(character, '-' | '_' | '.' | ':')
+}
+
+fn is_name(value: &str) -> bool {
+    value.starts_with(|character: char| character.is_alphabetic() || character == '_' || character == ':') && value.chars().all(is_name_char)
+}
+
+fn is_nm_token(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(is_name_char)
+}
+
+// Words, quoted literals and parenthesized groups with their occurrence indicator.
+fn tokens(body: &str) -> Result<Vec<Token<'_>>> {
+    let mut tokens = vec![];
+    let mut rest = body.trim_start();
+
+    while let Some(first) = rest.chars().next() {
+        let length = match first {
+            '"' | '\'' => {
+                let end = rest[1..].find(first).ok_or_else(|| anyhow!("Literal in DTD is not closed: {}", body.trim()))?;
+                tokens.push(Token::Literal(&rest[1..end + 1]));
+                end + 2
+            }
+            '(' => {
+                let mut depth = 0;
+                let end = rest.char_indices().find_map(|(index, character)| {
+                    match character {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    (depth == 0).then_some(index)
+                }).ok_or_else(|| anyhow!("Group in DTD is not closed: {}", body.trim()))?;
+                let end = if rest[end + 1..].starts_with(['?', '*', '+']) { end + 2 } else { end + 1 };
+                tokens.push(Token::Group(&rest[..end]));
+                end
+            }
+            _ => {
+                let end = rest.find(|character: char| character.is_whitespace() || matches!(character, '"' | '\'' | '(')).unwrap_or(rest.len());
+                tokens.push(Token::Word(&rest[..end]));
+                end
+            }
+        };
+        rest = rest[length..].trim_start();
+    }
+    Ok(tokens)
+}
+
+// Index of the '>' that ends the markup declaration at the start of `text`, '>' in literals does not count.
+fn declaration_end(text: &str) -> Result<usize> {
+    let mut quote = None;
+    for (index, character) in text.char_indices() {
+        match (quote, character) {
+            (Some(open), character) if character == open => quote = None,
+            (None, '"' | '\'') => quote = Some(character),
+            (None, '>') => return Ok(index),
+            _ => {}
+        }
+    }
+    Err(anyhow!("Declaration in DTD is not closed: {}", text.lines().next().unwrap_or("")))
+}
+
+// Content of the conditional section at the start of `text` and the text after its "]]>".
+fn conditional_section(text: &str) -> Result<(&str, &str)> {
+    let mut depth = 1;
+    let mut index = 0;
+    while index < text.len() {
+        if text[index..].starts_with("<![") {
+            depth += 1;
+            index += 3;
+        } else if text[index..].starts_with("]]>") {
+            depth -= 1;
+            if depth == 0 { return Ok((&text[..index], &text[index + 3..])); }
+            index += 3;
+        } else {
+            index += text[index..].chars().next().unwrap().len_utf8();
+        }
+    }
+    Err(anyhow!("Conditional section in DTD is not closed"))
+}
+
+fn content_model(spec: &Token) -> Result<ContentModel> {
+    let group = match spec {
+        Token::Word("EMPTY") => return Ok(ContentModel::Empty),
+        Token::Word("ANY") => return Ok(ContentModel::Any),
+        Token::Group(group) => *group,
+        _ => return Err(anyhow!("Invalid content model in DTD")),
+    };
+
+    let source = group.split_whitespace().collect::<Vec<_>>().join(" ");
+    if group.contains("#PCDATA") {
+        let names = group.trim_end_matches('*').trim_start_matches('(').trim_end_matches(')').split('|').map(str::trim);
+        return Ok(ContentModel::Mixed(names.filter(|name| *name != "#PCDATA").map(str::to_string).collect()));
+    }
+
+    let mut pattern = "^".to_string();
+    let mut name = String::new();
+    for character in group.chars().chain([' ']) {
+        if is_name_char(character) {
+            name.push(character);
+            continue;
+        }
+        if !name.is_empty() {
+            pattern.push_str(&format!("(?:{})", regex::escape(&format!("<{}>", name))));
+            name.clear();
+        }
+        match character {
+            '(' => pattern.push_str("(?:"),
+            ')' | '|' | '?' | '*' | '+' => pattern.push(character),
+            ',' => {}
+            character if character.is_whitespace() => {}
+            _ => return Err(anyhow!("Invalid content model {} in DTD", source)),
+        }
+    }
+    pattern.push('$');
+
+    let pattern = Regex::new(&pattern).map_err(|_| anyhow!("Invalid content model {} in DTD", source))?;
+    Ok(ContentModel::Children { pattern, source })
+}
+
+fn enumeration(group: &str) -> AttributeKind {
+    AttributeKind::Enumeration(group.trim_start_matches('(').trim_end_matches(')').split('|').map(|value| value.trim().to_string()).collect())
+}
+
+fn attribute_definitions(element: &str, tokens: &[Token]) -> Result<Vec<AttributeDef>> {
+    let invalid = || anyhow!("Invalid attribute list for element {} in DTD", element);
+    let mut definitions = vec![];
+    let mut tokens = tokens.iter();
+
+    while let Some(name) = tokens.next() {
+        let Token::Word(name) = name else { return Err(invalid()) };
+        let kind = match tokens.next().ok_or_else(invalid)? {
+            Token::Word("CDATA") => AttributeKind::Cdata,
+            Token::Word("ID") => AttributeKind::Id,
+            Token::Word("IDREF") => AttributeKind::IdRef,
+            Token::Word("IDREFS") => AttributeKind::IdRefs,
+            Token::Word("ENTITY") => AttributeKind::Entity,
+            Token::Word("ENTITIES") => AttributeKind::Entities,
+            Token::Word("NMTOKEN") => AttributeKind::NmToken,
+            Token::Word("NMTOKENS") => AttributeKind::NmTokens,
+            Token::Group(group) => enumeration(group),
+            Token::Word("NOTATION") => match tokens.next() {
+                Some(Token::Group(group)) => enumeration(group),
+                _ => return Err(invalid()),
+            },
+            _ => return Err(invalid()),
+        };
+
+        let default = match tokens.next().ok_or_else(invalid)? {
+            Token::Word("#REQUIRED") => AttributeDefault::Required,
+            Token::Word("#IMPLIED") => AttributeDefault::Implied,
+            Token::Word("#FIXED") => match tokens.next() {
+                Some(Token::Literal(value)) => AttributeDefault::Fixed(value.to_string()),
+                _ => return Err(invalid()),
+            },
+            Token::Literal(value) => AttributeDefault::Value(value.to_string()),
+            _ => return Err(invalid()),
+        };
+        definitions.push(AttributeDef { name: name.to_string(), kind, default });
+    }
+    Ok(definitions)
+}
+
+// Reads declarations into `dtd`, the first declaration of an entity or attribute is binding.
+#[derive(Default)]
+struct DeclarationReader {
+    dtd: Dtd,
+    parameter_entities: HashMap<String, ParameterEntity>,
+    depth: usize,
+}
+
+impl DeclarationReader {
+    fn file(&mut self, path: &Path) -> Result<()> {
+        let bytes = fs::read(path).with_context(|| format!("Error reading DTD {}", path.display()))?;
+        let text = decode_bytes(&bytes).with_context(|| format!("Error decoding DTD {}", path.display()))?;
+        self.declarations(&text, path.parent())
+    }
+
+    fn location(location: &str, base: Option<&Path>) -> Result<PathBuf> {
+        if location.contains("://") { return Err(anyhow!("Only local DTD files are supported, got {}", location)); }
+        Ok(base.map_or_else(|| PathBuf::from(location), |base| base.join(location)))
+    }
+
+    // Replacement text of `%name;` inside a declaration.
+    fn replacement(&mut self, name: &str) -> Result<String> {
+        let value = match self.parameter_entities.get(name).cloned() {
+            Some(ParameterEntity::Internal(value)) => value,
+            Some(ParameterEntity::External(path)) => decode_bytes(&fs::read(&path).with_context(|| format!("Error reading DTD {}", path.display()))?)?,
+            None => return Err(anyhow!("Unknown parameter entity %{}; in DTD", name)),
+        };
+        self.expand(&value)
+    }
+
+    fn nested<T>(&mut self, read: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= MAX_DEPTH { return Err(anyhow!("Parameter entities in DTD are circular")); }
+        self.depth += 1;
+        let result = read(self);
+        self.depth -= 1;
+        result
+    }
+
+    // Expands `%name;` references inside a declaration.
+    fn expand(&mut self, text: &str) -> Result<String> {
+        let mut output = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('%') {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let length = after.find(|character: char| !is_name_char(character)).unwrap_or(after.len());
+            if length == 0 || !after[length..].starts_with(';') {
+                output.push('%');
+                rest = after;
+                continue;
+            }
+            let name = &after[..length];
+            output.push_str(&self.nested(|reader| reader.replacement(name))?);
+            rest = &after[length + 1..];
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+
+    fn reference(&mut self, name: &str, base: Option<&Path>) -> Result<()> {
+        match self.parameter_entities.get(name).cloned() {
+            Some(ParameterEntity::External(path)) => self.nested(|reader| reader.file(&path)),
+            Some(ParameterEntity::Internal(value)) => self.nested(|reader| reader.declarations(&value, base)),
+            None => Err(anyhow!("Unknown parameter entity %{}; in DTD", name)),
+        }
+    }
+
+    fn declarations(&mut self, text: &str, base: Option<&Path>) -> Result<()> {
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("<!--") {
+                let end = after.find("-->").ok_or_else(|| anyhow!("Comment in DTD is not closed"))?;
+                rest = &after[end + 3..];
+            } else if let Some(after) = rest.strip_prefix("<?") {
+                let end = after.find("?>").ok_or_else(|| anyhow!("Processing instruction in DTD is not closed"))?;
+                rest = &after[end + 2..];
+            } else if let Some(after) = rest.strip_prefix('%') {
+                let end = after.find(';').ok_or_else(|| anyhow!("Parameter entity reference in DTD is not closed"))?;
+                self.reference(&after[..end], base)?;
+                rest = &after[end + 1..];
+            } else if let Some(after) = rest.strip_prefix("<![") {
+                let open = after.find('[').ok_or_else(|| anyhow!("Invalid conditional section in DTD"))?;
+                let keyword = self.expand(&after[..open])?;
+                let (content, after) = conditional_section(&after[open + 1..])?;
+                match keyword.trim() {
+                    "INCLUDE" => self.declarations(content, base)?,
+                    "IGNORE" => {}
+                    keyword => return Err(anyhow!("Invalid conditional section {} in DTD", keyword)),
+                }
+                rest = after;
+            } else if rest.starts_with("<!") {
+                let end = declaration_end(rest)?;
+                self.declaration(&rest[2..end], base)?;
+                rest = &rest[end + 1..];
+            } else {
+                return Err(anyhow!("Unexpected text in DTD: {}", rest.lines().next().unwrap_or("")));
+            }
+            rest = rest.trim_start();
+        }
+        Ok(())
+    }
+
+    fn declaration(&mut self, body: &str, base: Option<&Path>) -> Result<()> {
+        let (keyword, rest) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+        match keyword {
+            "ELEMENT" => {
+                let expanded = self.expand(rest)?;
+                let tokens = tokens(&expanded)?;
+                let [Token::Word(name), spec] = tokens.as_slice() else { return Err(anyhow!("Invalid element declaration <!ELEMENT {}> in DTD", rest.trim())) };
+                if self.dtd.elements.contains_key(*name) { return Err(anyhow!("Element {} is declared twice in DTD", name)); }
+                let model = content_model(spec)?;
+                self.dtd.elements.insert(name.to_string(), model);
+            }
+            "ATTLIST" => {
+                let expanded = self.expand(rest)?;
+                let tokens = tokens(&expanded)?;
+                let Some((Token::Word(element), definitions)) = tokens.split_first() else { return Err(anyhow!("Invalid attribute list <!ATTLIST {}> in DTD", rest.trim())) };
+                let existing = self.dtd.attributes.entry(element.to_string()).or_default();
+                for definition in attribute_definitions(element, definitions)? {
+                    if !existing.iter().any(|attribute| attribute.name == definition.name) { existing.push(definition); }
+                }
+            }
+            "ENTITY" => {
+                let expanded = self.expand(rest)?;
+                let tokens = tokens(&expanded)?;
+                // General entities are not expanded in documents.
+                let [Token::Word("%"), Token::Word(name), definition @ ..] = tokens.as_slice() else { return Ok(()) };
+                let entity = match definition {
+                    [Token::Literal(value)] => ParameterEntity::Internal(value.to_string()),
+                    [Token::Word("SYSTEM"), Token::Literal(location)] | [Token::Word("PUBLIC"), Token::Literal(_), Token::Literal(location)] => {
+                        ParameterEntity::External(Self::location(location, base)?)
+                    }
+                    _ => return Err(anyhow!("Invalid entity declaration <!ENTITY {}> in DTD", rest.trim())),
+                };
+                self.parameter_entities.entry(name.to_string()).or_insert(entity);
+            }
+            "NOTATION" => {}
+            _ => return Err(anyhow!("Unknown declaration <!{}> in DTD", keyword)),
+        }
+        Ok(())
+    }
+}
+
+// Document type declaration of `input`, found after the XML declaration and comments.
+pub fn find_doctype(input: &str) -> Option<&str> {
+    let mut rest = input.trim_start();
+    if rest.starts_with("<?xml") { rest = rest[rest.find("?>")? + 2..].trim_start(); }
+    while let Some(after) = rest.strip_prefix("<!--") { rest = after[after.find("-->")? + 3..].trim_start(); }
+    if !rest.starts_with("<!DOCTYPE") { return None; }
+    Grammar::parse(Rule::doctype, rest).ok()?.next().map(|pair| pair.as_str())
+}
+
+// Name of the root element given by the document type declaration.
+fn doctype_name(doctype: &str) -> &str {
+    let rest = doctype["<!DOCTYPE".len()..].trim_start();
+    &rest[..rest.find(|character: char| !is_name_char(character)).unwrap_or(rest.len())]
+}
+
+// Index of the '[' that starts the internal subset, '[' in literals does not count.
+fn subset_start(doctype: &str) -> Option<usize> {
+    let mut quote = None;
+    doctype.char_indices().find_map(|(index, character)| {
+        match (quote, character) {
+            (Some(open), character) if character == open => quote = None,
+            (None, '"' | '\'') => quote = Some(character),
+            (None, '[') => return Some(index),
+            _ => {}
+        }
+        None
+    })
+}
+
+// System identifier of the external subset, `SYSTEM "uri"` or `PUBLIC "id" "uri"` after the root element name.
+fn system_id(doctype: &str) -> Option<&str> {
+    let header = doctype[..subset_start(doctype).unwrap_or(doctype.len() - 1)]["<!DOCTYPE".len()..].trim_start();
+    match tokens(&header[doctype_name(doctype).len()..]).ok()?.as_slice() {
+        [Token::Word("SYSTEM"), Token::Literal(location)] | [Token::Word("PUBLIC"), Token::Literal(_), Token::Literal(location)] => Some(location),
+        _ => None,
+    }
+}
+
+// The internal subset ends at the first ']' outside comments and literals, like in the `doctype` rule.
+fn internal_subset(doctype: &str) -> Option<&str> {
+    let start = subset_start(doctype)? + 1;
+
+    let mut index = start;
+    while index < doctype.len() {
+        let rest = &doctype[index..];
+        if rest.starts_with("<!--") && let Some(end) = rest.find("-->") {
+            index += end + 3;
+            continue;
+        }
+        let character = rest.chars().next()?;
+        if (character == '"' || character == '\'') && let Some(end) = rest[1..].find(character) {
+            index += end + 2;
+            continue;
+        }
+        if character == ']' { return Some(&doctype[start..index]); }
+        index += character.len_utf8();
+    }
+    None
+}
+
+impl Dtd {
+    // External parameter entities are resolved from the current directory.
+    pub fn parse(declarations: &str) -> Result<Dtd> {
+        let mut reader = DeclarationReader::default();
+        reader.declarations(declarations, None)?;
+        Ok(reader.dtd)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Dtd> {
+        let mut reader = DeclarationReader::default();
+        reader.file(path.as_ref())?;
+        Ok(reader.dtd)
+    }
+
+    // Declarations of the internal subset, the external identifier is not followed.
+    pub fn from_doctype(doctype: &str) -> Result<Dtd> {
+        internal_subset(doctype).map_or_else(|| Ok(Dtd::default()), Dtd::parse)
+    }
+
+    // Internal subset of `input` together with the external subset, declarations of the internal subset take precedence.
+    // The external subset is `external` when given, otherwise the system identifier of the document type declaration
+    // is read as a local file relative to `document_path`. Without a path it is not followed.
+    pub fn for_document(input: &str, document_path: Option<&Path>, external: Option<&Dtd>) -> Result<Dtd> {
+        let Some(doctype) = find_doctype(input) else { return Ok(external.cloned().unwrap_or_default()) };
+        let base = document_path.and_then(Path::parent);
+
+        let mut reader = DeclarationReader::default();
+        if let Some(subset) = internal_subset(doctype) { reader.declarations(subset, base)?; }
+        let mut dtd = reader.dtd;
+
+        match (external, base, system_id(doctype)) {
+            (Some(external), _, _) => dtd.extend(external.clone()),
+            (None, Some(base), Some(location)) => dtd.extend(Dtd::from_file(DeclarationReader::location(location, Some(base))?)?),
+            _ => {}
+        }
+        Ok(dtd)
+    }
+
+    pub fn extend(&mut self, other: Dtd) {
+        for (name, model) in other.elements { self.elements.entry(name).or_insert(model); }
+        for (element, definitions) in other.attributes {
+            let existing = self.attributes.entry(element).or_default();
+            for definition in definitions {
+                if !existing.iter().any(|attribute| attribute.name == definition.name) { existing.push(definition); }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty() && self.attributes.is_empty()
+    }
+
+    pub fn validate(&self, input: &str) -> Result<Vec<Diagnostic>> {
+        Ok(self.validate_document(&parse_borrowed(input)?, input))
+    }
+
+    // Locations are the offsets in `document`, which must be parsed from `input`.
+    pub fn validate_document(&self, document: &borrowed::Document, input: &str) -> Vec<Diagnostic> {
+        let mut validator = Validator { dtd: self, input, diagnostics: vec![], ids: HashSet::new(), references: vec![] };
+        let root = &document.root;
+        if let Some(doctype) = &document.doctype && doctype_name(doctype) != root.name {
+            validator.report(root.offset, format!("Root element <{}> does not match the document type {}", root.name, doctype_name(doctype)));
+        }
+
+        // Elements are kept on an explicit stack like in the converter.
+        let mut stack = vec![root];
+        while let Some(element) = stack.pop() {
+            validator.element(element);
+            stack.extend(element.elements().collect::<Vec<_>>().into_iter().rev());
+        }
+
+        for (offset, name, id) in std::mem::take(&mut validator.references) {
+            if !validator.ids.contains(id) { validator.report(offset, format!("Attribute {}: no element has the ID {:?}", name, id)); }
+        }
+        validator.diagnostics
+    }
+
+    // Default and #FIXED attribute values declared for `element`.
+    fn defaults(&self, element: &str) -> impl Iterator<Item = (&str, &String)> {
+        self.attributes.get(element).into_iter().flatten().filter_map(|attribute| match &attribute.default {
+            AttributeDefault::Fixed(default) | AttributeDefault::Value(default) => Some((attribute.name.as_str(), default)),
+            _ => None,
+        })
+    }
+
+    // Adds default and #FIXED attribute values that are missing from the output of `start_parser`,
+    // an element with only text becomes an object with `_text` when it gets attributes.
+    pub fn apply_defaults(&self, json: &mut Value) {
+        let Value::Object(root) = json else { return };
+        let mut stack: Vec<_> = root.iter_mut().collect();
+
+        while let Some((name, value)) = stack.pop() {
+            if name.starts_with('_') { continue; }
+            if let Value::Array(values) = value {
+                stack.extend(values.iter_mut().map(|value| (name, value)));
+                continue;
+            }
+            let defaults: Vec<_> = self.defaults(name).map(|(attribute, default)| (format!("_{}", attribute), default)).collect();
+
+            if let Value::String(text) = value {
+                if defaults.is_empty() { continue; }
+                let mut object = Map::new();
+                object.insert("_text".to_string(), Value::String(std::mem::take(text)));
+                *value = Value::Object(object);
+            }
+            let Value::Object(object) = value else { continue };
+            for (key, default) in defaults { object.entry(key).or_insert_with(|| Value::String(default.clone())); }
+            stack.extend(object.iter_mut());
+        }
+    }
+
+    // Same defaults as `apply_defaults` added to the document tree, missing attributes are appended in declaration order.
+    pub fn apply_tree_defaults(&self, root: &mut tree::Element) {
+        let mut stack = vec![root];
+        while let Some(element) = stack.pop() {
+            for (attribute, default) in self.defaults(&element.name) {
+                if !element.attributes.iter().any(|(name, _)| name == attribute) { element.attributes.push((attribute.to_string(), default.clone())); }
+            }
+            stack.extend(element.children.iter_mut().filter_map(|child| match child {
+                tree::Node::Element(element) => Some(element),
+                _ => None,
+            }));
+        }
+    }
+}
+
+// Converts `input` like `start_parser` and adds the attribute defaults of the DTD found by `Dtd::for_document`.
+pub fn parse_with_dtd(input: &str, document_path: Option<&Path>, external: Option<&Dtd>) -> Result<Value> {
+    let mut json = start_parser(input)?;
+    let dtd = Dtd::for_document(input, document_path, external)?;
+    if !dtd.is_empty() { dtd.apply_defaults(&mut json); }
+    Ok(json)
+}
+
+struct Validator<'d, 'i> {
+    dtd: &'d Dtd,
+    input: &'i str,
+    diagnostics: Vec<Diagnostic>,
+    ids: HashSet<&'i str>,
+    // Attribute offsets and names with the IDs they refer to, checked once all IDs are known.
+    references: Vec<(usize, &'i str, &'i str)>,
+}
+
+impl<'i> Validator<'_, 'i> {
+    fn report(&mut self, offset: usize, message: String) {
+        self.diagnostics.push(Diagnostic::validity(self.input, offset, message));
+    }
+
+    fn element(&mut self, element: &borrowed::Element) {
+        let Some(model) = self.dtd.elements.get(element.name.as_ref()) else {
+            self.report(element.offset, format!("Element <{}> is not declared", element.name));
+            return;
+        };
+        self.attributes(element);
+
+        let has_text = !element.text().trim().is_empty();
+        match model {
+            ContentModel::Any => {}
+            ContentModel::Empty => {
+                if has_text || element.elements().next().is_some() { self.report(element.offset, format!("Element <{}> must be empty", element.name)); }
+            }
+            ContentModel::Mixed(names) => {
+                for child in element.elements().filter(|child| !names.iter().any(|name| *name == child.name)) {
+                    self.report(child.offset, format!("Element <{}> can not contain <{}>", element.name, child.name));
+                }
+            }
+            ContentModel::Children { pattern, source } => {
+                if has_text { self.report(element.offset, format!("Text is not allowed in element <{}>", element.name)); }
+                let children: String = element.elements().map(|child| format!("<{}>", child.name)).collect();
+                if !pattern.is_match(&children) {
+                    let found = element.elements().map(|child| child.name.as_ref()).collect::<Vec<_>>().join(", ");
+                    self.report(element.offset, format!("Content of element <{}> must be {}, found ({})", element.name, source, found));
+                }
+            }
+        }
+    }
+
+    fn attributes(&mut self, element: &borrowed::Element) {
+        let definitions = self.dtd.attributes.get(element.name.as_ref()).map_or(&[][..], Vec::as_slice);
+
+        for (name, value, offset) in element.attributes_with_offsets() {
+            let Some(definition) = definitions.iter().find(|definition| definition.name == name) else {
+                self.report(offset, format!("Attribute {} is not declared for element <{}>", name, element.name));
+                continue;
+            };
+            if let AttributeDefault::Fixed(fixed) = &definition.default && fixed != value {
+                self.report(offset, format!("Attribute {} must have the fixed value {:?}", name, fixed));
+            }
+            self.attribute_value(name, value, offset, &definition.kind);
+        }
+
+        for definition in definitions.iter().filter(|definition| definition.default == AttributeDefault::Required) {
+            if element.attribute(&definition.name).is_none() {
+                self.report(element.offset, format!("Element <{}> is missing required attribute {}", element.name, definition.name));
+            }
+        }
+    }
+
+    fn attribute_value(&mut self, name: &str, value: &str, offset: usize, kind: &AttributeKind) {
+        let tokens: Vec<_> = value.split_whitespace().collect();
+        let valid = match kind {
+            AttributeKind::Cdata => true,
+            AttributeKind::Id | AttributeKind::IdRef | AttributeKind::Entity => tokens.len() == 1 && is_name(tokens[0]),
+            AttributeKind::IdRefs | AttributeKind::Entities => !tokens.is_empty() && tokens.iter().all(|token| is_name(token)),
+            AttributeKind::NmToken => tokens.len() == 1 && is_nm_token(tokens[0]),
+            AttributeKind::NmTokens => !tokens.is_empty() && tokens.iter().all(|token| is_nm_token(token)),
+            AttributeKind::Enumeration(values) => {
+                if tokens.len() != 1 || !values.iter().any(|allowed| allowed == tokens[0]) {
+                    self.report(offset, format!("Attribute {}: {:?} is not one of {}", name, value, values.join(", ")));
+                }
+                return;
+            }
+        };
+        if !valid {
+            let kind_name = match kind {
+                AttributeKind::Id => "ID",
+                AttributeKind::IdRef => "IDREF",
+                AttributeKind::IdRefs => "IDREFS",
+                AttributeKind::Entity => "ENTITY",
+                AttributeKind::Entities => "ENTITIES",
+                AttributeKind::NmToken => "NMTOKEN",
+                _ => "NMTOKENS",
+            };
+            self.report(offset, format!("Attribute {}: {:?} is not a valid {}", name, value, kind_name));
+            return;
+        }
+
+        // IDs are compared on the value in the input, values with entities are not tracked.
+        let Some((name, raw)) = self.raw_attribute(offset) else { return };
+        match kind {
+            AttributeKind::Id if !self.ids.insert(raw.trim()) => self.report(offset, format!("Attribute {}: ID {:?} is already used", name, raw.trim())),
+            AttributeKind::IdRef | AttributeKind::IdRefs => self.references.extend(raw.split_whitespace().map(|id| (offset, name, id))),
+            _ => {}
+        }
+    }
+
+    // Name and value of the attribute at `offset` as written in the input, when the value has no entities.
+    fn raw_attribute(&self, offset: usize) -> Option<(&'i str, &'i str)> {
+        let input = self.input.get(offset..)?;
+        let name = &input[..input.find(|character: char| !is_name_char(character)).unwrap_or(input.len())];
+        let rest = &input[name.len()..];
+        let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+        let quote = rest.chars().next()?;
+        let value = &rest[1..1 + rest[1..].find(quote)?];
+        (!value.contains('&')).then_some((name, value))
+    }
+}
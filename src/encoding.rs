@@ -0,0 +1,53 @@
+use crate::start_parser;
+use anyhow::*;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde_json::Value;
+
+pub fn parse_bytes(bytes: &[u8]) -> Result<Value> {
+    start_parser(&decode_bytes(bytes)?)
+}
+
+pub fn decode_bytes(bytes: &[u8]) -> Result<String> {
+    let (encoding, bom_length) = detect_encoding(bytes)?;
+    decode(encoding, &bytes[bom_length..])
+}
+
+// Detects the encoding the way XML 1.0 Appendix F describes it: byte order mark first,
+// then UTF-16 without a mark, then the `encoding` of the XML declaration, UTF-8 otherwise.
+// Returns the encoding and the length of the byte order mark.
+pub(crate) fn detect_encoding(bytes: &[u8]) -> Result<(&'static Encoding, usize)> {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) { return Ok((encoding, bom_length)); }
+
+    if bytes.starts_with(&[0x3C, 0x00, 0x3F, 0x00]) { return Ok((UTF_16LE, 0)); }
+    if bytes.starts_with(&[0x00, 0x3C, 0x00, 0x3F]) { return Ok((UTF_16BE, 0)); }
+
+    let encoding = match declared_encoding(bytes) {
+        Some(label) => {
+            let encoding = Encoding::for_label(label.as_bytes()).ok_or_else(|| anyhow!("Unknown encoding {}", label))?;
+            if encoding == UTF_16LE || encoding == UTF_16BE { return Err(anyhow!("Encoding {} is declared but there is no byte order mark", label)); }
+            encoding
+        }
+        None => UTF_8,
+    };
+    Ok((encoding, 0))
+}
+
+fn decode(encoding: &'static Encoding, bytes: &[u8]) -> Result<String> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(|text| text.into_owned())
+        .ok_or_else(|| anyhow!("Input is not valid {}", encoding.name()))
+}
+
+fn declared_encoding(bytes: &[u8]) -> Option<String> {
+    if !bytes.starts_with(b"<?xml") { return None; }
+
+    let end = bytes.windows(2).position(|window| window == b"?>")?;
+    let declaration = String::from_utf8_lossy(&bytes[..end]);
+    let rest = &declaration[declaration.find("encoding")? + "encoding".len()..];
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &rest[1..];
+    Some(value[..value.find(quote)?].to_string())
+}
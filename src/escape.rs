@@ -0,0 +1,102 @@
+use std::borrow::Cow;
+
+// Replaces predefined entities and character references, unknown references are kept as written.
+pub fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') { return Cow::Borrowed(text); }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut changed = false;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').and_then(|end| decode_reference(&rest[1..end]).map(|c| (c, end)));
+        match decoded {
+            Some((c, end)) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+                changed = true;
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    if !changed { return Cow::Borrowed(text); }
+    result.push_str(rest);
+    Cow::Owned(result)
+}
+
+// First '&' in `text` that does not start a predefined entity or a character reference, with the text up to its ';'.
+pub(crate) fn unknown_reference(text: &str) -> Option<&str> {
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        rest = &rest[start..];
+        match rest.find(';').filter(|end| decode_reference(&rest[1..*end]).is_some()) {
+            Some(end) => rest = &rest[end + 1..],
+            None => {
+                let end = rest[1..].find(|c: char| c == ';' || c == '&' || c == '<' || c.is_whitespace()).map_or(rest.len(), |end| end + 1);
+                return Some(&rest[..if rest[end..].starts_with(';') { end + 1 } else { end }]);
+            }
+        }
+    }
+    None
+}
+
+fn decode_reference(reference: &str) -> Option<char> {
+    match reference {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => {
+            let code = if let Some(hex) = reference.strip_prefix("#x") {
+                u32::from_str_radix(hex, 16).ok()?
+            } else {
+                reference.strip_prefix('#')?.parse().ok()?
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+pub fn escape_text(text: &str) -> Cow<'_, str> {
+    escape(text, |c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        _ => None,
+    })
+}
+
+pub fn escape_attribute(value: &str) -> Cow<'_, str> {
+    escape(value, |c| match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '"' => Some("&quot;"),
+        _ => None,
+    })
+}
+
+pub(crate) fn escape(text: &str, replacement: impl Fn(char) -> Option<&'static str>) -> Cow<'_, str> {
+    if !text.chars().any(|c| replacement(c).is_some()) { return Cow::Borrowed(text); }
+
+    let mut result = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match replacement(c) {
+            Some(escaped) => result.push_str(escaped),
+            None => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
+// Reference token of a JSON Pointer (RFC 6901).
+pub(crate) fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
@@ -0,0 +1,93 @@
+// C API of the converter, the header is generated into include/xml_to_json_parser.h by the build script.
+use crate::encoding::parse_bytes;
+use crate::start_parser;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmlToJsonStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    ParseError = 3,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+fn finish(result: anyhow::Result<serde_json::Value>, out_json: *mut *mut c_char) -> XmlToJsonStatus {
+    match result.and_then(|json| Ok(serde_json::to_string(&json)?)) {
+        Ok(json) => {
+            LAST_ERROR.with(|last_error| *last_error.borrow_mut() = None);
+            // SAFETY: `out_json` was checked for null by the caller.
+            unsafe { *out_json = CString::new(json).unwrap_or_default().into_raw() };
+            XmlToJsonStatus::Ok
+        }
+        Err(error) => {
+            set_last_error(error.to_string());
+            XmlToJsonStatus::ParseError
+        }
+    }
+}
+
+/// Converts the NUL-terminated UTF-8 `xml` like `start_parser`. On success `*out_json` receives
+/// a JSON string that must be released with `xml_to_json_free`.
+///
+/// # Safety
+/// `xml` must be null or a valid NUL-terminated string, `out_json` must be null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xml_to_json_parse(xml: *const c_char, out_json: *mut *mut c_char) -> XmlToJsonStatus {
+    if xml.is_null() || out_json.is_null() {
+        set_last_error("Null pointer passed to xml_to_json_parse".to_string());
+        return XmlToJsonStatus::NullPointer;
+    }
+    unsafe { *out_json = ptr::null_mut() };
+
+    let xml = match unsafe { CStr::from_ptr(xml) }.to_str() {
+        Ok(xml) => xml,
+        Err(error) => {
+            set_last_error(format!("Input is not valid UTF-8: {}", error));
+            return XmlToJsonStatus::InvalidUtf8;
+        }
+    };
+    finish(start_parser(xml), out_json)
+}
+
+/// Same as `xml_to_json_parse` for a buffer of `length` bytes in any encoding supported by `parse_bytes`.
+///
+/// # Safety
+/// `bytes` must be null or point to `length` readable bytes, `out_json` must be null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xml_to_json_parse_bytes(bytes: *const u8, length: usize, out_json: *mut *mut c_char) -> XmlToJsonStatus {
+    if bytes.is_null() || out_json.is_null() {
+        set_last_error("Null pointer passed to xml_to_json_parse_bytes".to_string());
+        return XmlToJsonStatus::NullPointer;
+    }
+    unsafe { *out_json = ptr::null_mut() };
+
+    let bytes = unsafe { std::slice::from_raw_parts(bytes, length) };
+    finish(parse_bytes(bytes), out_json)
+}
+
+/// Releases a string returned through `out_json`, null is ignored.
+///
+/// # Safety
+/// `json` must be null or a pointer returned by this library that was not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xml_to_json_free(json: *mut c_char) {
+    if !json.is_null() { drop(unsafe { CString::from_raw(json) }); }
+}
+
+/// Message of the last failed call on the current thread or null. The pointer is valid until the next call on that thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn xml_to_json_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
@@ -0,0 +1,101 @@
+use crate::tree::*;
+use anyhow::*;
+
+const INDENT: &str = "    ";
+
+pub fn format_xml(input: &str) -> Result<String> {
+    let document = parse_document(input)?;
+    Ok(format_document(&document))
+}
+
+pub fn is_formatted(input: &str) -> Result<bool> {
+    Ok(format_xml(input)? == input)
+}
+
+pub fn format_document(document: &Document) -> String {
+    let mut output = String::new();
+
+    if let Some(declaration) = &document.declaration {
+        output.push_str(declaration);
+        output.push('\n');
+    }
+    // The document type declaration is kept as written, comments before it are moved after it.
+    if let Some(doctype) = &document.doctype {
+        output.push_str(doctype);
+        output.push('\n');
+    }
+    for node in &document.prolog { write_node(node, 0, &mut output); }
+    write_element(&document.root, 0, &mut output);
+    for node in &document.epilog { write_node(node, 0, &mut output); }
+
+    output
+}
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn write_open_tag(element: &Element, output: &mut String) {
+    output.push('<');
+    output.push_str(&element.name);
+    // Double quotes are used everywhere except values that contain them, those keep single quotes.
+    for (attribute_name, attribute_value) in &element.attributes {
+        let quote = if attribute_value.contains('"') { '\'' } else { '"' };
+        output.push_str(&format!(" {}={}{}{}", attribute_name, quote, attribute_value, quote));
+    }
+    output.push('>');
+}
+
+enum Item<'a> {
+    Node(&'a Node, usize),
+    Element(&'a Element, usize),
+    Close(&'a Element, usize),
+}
+
+fn write_node(node: &Node, depth: usize, output: &mut String) {
+    write_items(Item::Node(node, depth), output);
+}
+
+fn write_element(element: &Element, depth: usize, output: &mut String) {
+    write_items(Item::Element(element, depth), output);
+}
+
+// Children are kept on an explicit stack like in `parse_element`, so deep documents do not overflow.
+fn write_items(item: Item, output: &mut String) {
+    let mut stack = vec![item];
+
+    while let Some(item) = stack.pop() {
+        match item {
+            Item::Node(Node::Element(element), depth) => stack.push(Item::Element(element, depth)),
+            Item::Node(Node::Text(text), depth) => {
+                let text = normalize_text(text);
+                if !text.is_empty() {
+                    output.push_str(&INDENT.repeat(depth));
+                    output.push_str(&text);
+                    output.push('\n');
+                }
+            }
+            Item::Node(Node::Comment(comment), depth) => {
+                output.push_str(&INDENT.repeat(depth));
+                output.push_str(&format!("<!-- {} -->\n", comment.trim()));
+            }
+            Item::Element(element, depth) => {
+                output.push_str(&INDENT.repeat(depth));
+                write_open_tag(element, output);
+
+                if element.children.iter().all(|child| matches!(child, Node::Text(_))) {
+                    output.push_str(&normalize_text(&element.text()));
+                    output.push_str(&format!("</{}>\n", element.name));
+                } else {
+                    output.push('\n');
+                    stack.push(Item::Close(element, depth));
+                    stack.extend(element.children.iter().rev().map(|child| Item::Node(child, depth + 1)));
+                }
+            }
+            Item::Close(element, depth) => {
+                output.push_str(&INDENT.repeat(depth));
+                output.push_str(&format!("</{}>\n", element.name));
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::recover::*;
+use std::collections::HashMap;
+
+// Rules of the lenient mode: `auto_close` maps an element to the elements whose opening closes it,
+// void elements never have content and are closed right after the open tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LenientOptions {
+    pub auto_close: HashMap<String, Vec<String>>,
+    pub void_elements: Vec<String>,
+    pub case_insensitive: bool,
+}
+
+impl Default for LenientOptions {
+    fn default() -> Self {
+        let block = ["p", "div", "ul", "ol", "table", "h1", "h2", "h3", "h4", "h5", "h6", "pre", "blockquote", "form", "hr"];
+        let rules: [(&str, &[&str]); 8] = [
+            ("li", &["li"]),
+            ("p", &block),
+            ("dt", &["dt", "dd"]),
+            ("dd", &["dt", "dd"]),
+            ("tr", &["tr"]),
+            ("td", &["td", "th", "tr"]),
+            ("th", &["td", "th", "tr"]),
+            ("option", &["option"]),
+        ];
+        let void_elements = ["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr"];
+
+        LenientOptions {
+            auto_close: rules.iter().map(|(name, closers)| (name.to_string(), closers.iter().map(|closer| closer.to_string()).collect())).collect(),
+            void_elements: void_elements.iter().map(|name| name.to_string()).collect(),
+            case_insensitive: true,
+        }
+    }
+}
+
+impl LenientOptions {
+    fn matches(&self, a: &str, b: &str) -> bool {
+        if self.case_insensitive { a.eq_ignore_ascii_case(b) } else { a == b }
+    }
+
+    // Whether opening `next` closes the currently open `open` element.
+    pub fn closes(&self, open: &str, next: &str) -> bool {
+        self.auto_close.iter().any(|(name, closers)| self.matches(name, open) && closers.iter().any(|closer| self.matches(closer, next)))
+    }
+
+    pub fn is_void(&self, name: &str) -> bool {
+        self.void_elements.iter().any(|void| self.matches(void, name))
+    }
+
+    pub fn has_optional_end_tag(&self, name: &str) -> bool {
+        self.auto_close.keys().any(|key| self.matches(key, name))
+    }
+}
+
+// Lenient counterpart of `parse_recovering`: every applied fix is reported as a warning,
+// `partial` is only set when errors had to be recovered as well.
+pub fn parse_lenient(input: &str, options: &LenientOptions) -> Recovered {
+    Scanner::new(input, Some(options)).recover()
+}
@@ -0,0 +1,307 @@
+// Single-pass converter over the input bytes that builds the JSON of `start_parser` without the pest pair tree.
+// It accepts exactly the language of grammar.pest and rejects anything else, `start_parser` then falls back
+// to pest so errors and their positions stay the same for documents of ordinary depth.
+use crate::{drop_value, insert_child, wrap};
+use memchr::{memchr, memchr3, memmem};
+use serde_json::{Map, Value};
+
+// Element that is open while its content is scanned.
+struct Frame<'a> {
+    name: &'a str,
+    json: Map<String, Value>,
+    text: String,
+    has_nested: bool,
+}
+
+impl Frame<'_> {
+    // Mirrors `parse_element`: nested elements win over text, text is a plain string without attributes.
+    fn into_value(mut self) -> Value {
+        if !self.has_nested && !self.text.is_empty() {
+            if self.json.is_empty() { return Value::String(self.text); }
+            self.json.insert("_text".to_string(), Value::String(self.text));
+        }
+        Value::Object(self.json)
+    }
+}
+
+// Why the lexer stopped: byte offset of the problem and the nesting depth of the whole input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejection {
+    pub offset: usize,
+    pub depth: usize,
+    pub mismatched_close_tag: bool,
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    bytes: &'a [u8],
+    position: usize,
+    mismatched_close_tag: bool,
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n')
+}
+
+fn is_name_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b':')
+}
+
+pub fn try_convert(input: &str) -> Option<Value> {
+    convert(input).ok()
+}
+
+pub fn convert(input: &str) -> Result<Value, Rejection> {
+    let mut lexer = Lexer { input, bytes: input.as_bytes(), position: 0, mismatched_close_tag: false };
+    lexer.document().ok_or_else(|| Rejection { offset: lexer.position, depth: nesting_depth(lexer.bytes), mismatched_close_tag: lexer.mismatched_close_tag })
+}
+
+// Deepest nesting of the tags, counted without checking them. Decides whether pest can parse the input,
+// so in a rejected input the part after the problem counts as well.
+pub(crate) fn nesting_depth(bytes: &[u8]) -> usize {
+    let (mut depth, mut max_depth) = (0usize, 0);
+    let mut position = 0;
+
+    'tags: while let Some(index) = memchr(b'<', &bytes[position..]) {
+        position += index + 1;
+        match bytes.get(position) {
+            Some(b'/') => depth = depth.saturating_sub(1),
+            Some(b'!') if bytes[position..].starts_with(b"!--") => match memmem::find(&bytes[position..], b"-->") {
+                Some(end) => position += end,
+                None => break,
+            },
+            Some(b'!') if bytes[position..].starts_with(b"!DOCTYPE") => match doctype_end(bytes, position - 1, true) {
+                Some(end) => position = end,
+                None => break,
+            },
+            Some(b'!' | b'?') => {}
+            _ => {
+                // Quoted attribute values may contain '>', like in `Lexer::attribute` a quote has to be closed.
+                loop {
+                    let Some(end) = memchr3(b'>', b'"', b'\'', &bytes[position..]) else { break 'tags };
+                    position += end;
+                    if bytes[position] == b'>' { break; }
+                    let Some(end) = memchr(bytes[position], &bytes[position + 1..]) else { break 'tags };
+                    position += end + 2;
+                }
+                depth += 1;
+                max_depth = max_depth.max(depth);
+                if bytes[position - 1] == b'/' { depth -= 1; }
+            }
+        }
+    }
+    max_depth
+}
+
+// Length of the quoted literal at `position`, the grammar reads an unclosed quote as an ordinary character.
+fn quoted(bytes: &[u8], position: usize) -> Option<usize> {
+    let quote = bytes.get(position).filter(|quote| **quote == b'"' || **quote == b'\'')?;
+    memchr(*quote, &bytes[position + 1..]).map(|end| end + 2)
+}
+
+// End of the document type declaration starting at `start`, skipped the way the `doctype` rule reads it:
+// an unclosed quote or internal subset is read as ordinary characters. When `bytes` may be only the beginning
+// of the input, `complete` is false and those cases return None to wait for the rest.
+pub(crate) fn doctype_end(bytes: &[u8], start: usize, complete: bool) -> Option<usize> {
+    let unclosed_quote = |position: usize| !complete && matches!(bytes.get(position), Some(b'"' | b'\''));
+    let mut position = start + "<!DOCTYPE".len();
+    loop {
+        if let Some(length) = quoted(bytes, position) { position += length; continue; }
+        if unclosed_quote(position) { return None; }
+        match bytes.get(position) {
+            None | Some(b'[' | b'>') => break,
+            Some(_) => position += 1,
+        }
+    }
+
+    if bytes.get(position) == Some(&b'[') {
+        let subset_start = position;
+        position += 1;
+        loop {
+            if bytes[position..].starts_with(b"<!--") && let Some(end) = memmem::find(&bytes[position + 4..], b"-->") {
+                position += 4 + end + 3;
+                continue;
+            }
+            if !complete && bytes[position..].starts_with(b"<!--") { return None; }
+            if let Some(length) = quoted(bytes, position) { position += length; continue; }
+            if unclosed_quote(position) { return None; }
+            match bytes.get(position) {
+                Some(b']') => {
+                    position += 1;
+                    break;
+                }
+                Some(_) => position += 1,
+                None if !complete => return None,
+                None => {
+                    position = subset_start;
+                    break;
+                }
+            }
+        }
+    }
+
+    Some(position + memchr(b'>', &bytes[position..])? + 1)
+}
+
+impl<'a> Lexer<'a> {
+    fn starts_with(&self, prefix: &[u8]) -> bool {
+        self.bytes[self.position..].starts_with(prefix)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(is_whitespace) { self.position += 1; }
+    }
+
+    fn expect(&mut self, prefix: &[u8]) -> Option<()> {
+        if !self.starts_with(prefix) { return None; }
+        self.position += prefix.len();
+        Some(())
+    }
+
+    // Moves past the first `terminator` found at or after `from`.
+    fn skip_past(&mut self, from: usize, terminator: &[u8]) -> Option<()> {
+        let end = memmem::find(&self.bytes[from..], terminator)?;
+        self.position = from + end + terminator.len();
+        Some(())
+    }
+
+    fn name(&mut self) -> Option<&'a str> {
+        let start = self.position;
+        if !self.peek()?.is_ascii_alphabetic() { return None; }
+        self.position += 1;
+        while self.peek().is_some_and(is_name_char) { self.position += 1; }
+        Some(&self.input[start..self.position])
+    }
+
+    fn comments(&mut self) -> Option<()> {
+        loop {
+            self.skip_whitespace();
+            if !self.starts_with(b"<!--") { return Some(()); }
+            self.skip_past(self.position + 4, b"-->")?;
+        }
+    }
+
+    fn document(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        if self.starts_with(b"<?xml") { self.skip_past(self.position + 5, b"?>")?; }
+        self.comments()?;
+        if self.starts_with(b"<!DOCTYPE") {
+            self.position = doctype_end(self.bytes, self.position, true)?;
+            self.comments()?;
+        }
+
+        let root = self.element()?;
+
+        if self.comments().is_none() || self.position != self.bytes.len() {
+            drop_value(root);
+            return None;
+        }
+        Some(root)
+    }
+
+    // Scans the tag after '<' and returns the frame of the element and whether the tag was `/>`.
+    fn start_tag(&mut self) -> Option<(Frame<'a>, bool)> {
+        self.skip_whitespace();
+        let name = self.name()?;
+        let mut frame = Frame { name, json: Map::new(), text: String::new(), has_nested: false };
+
+        loop {
+            self.skip_whitespace();
+            match self.peek()? {
+                b'>' => {
+                    self.position += 1;
+                    return Some((frame, false));
+                }
+                b'/' => {
+                    self.expect(b"/>")?;
+                    return Some((frame, true));
+                }
+                _ => {
+                    let (attribute_name, attribute_value) = self.attribute()?;
+                    frame.json.insert(format!("_{}", attribute_name), Value::String(attribute_value.to_string()));
+                }
+            }
+        }
+    }
+
+    fn attribute(&mut self) -> Option<(&'a str, &'a str)> {
+        let name = self.name()?;
+        self.skip_whitespace();
+        self.expect(b"=")?;
+        self.skip_whitespace();
+
+        let quote = self.peek().filter(|quote| *quote == b'"' || *quote == b'\'')?;
+        self.position += 1;
+        // Implicit whitespace of the grammar skips the start of the value, the end is kept.
+        self.skip_whitespace();
+        let start = self.position;
+        let end = start + memchr(quote, &self.bytes[start..])?;
+        self.position = end + 1;
+        Some((name, &self.input[start..end]))
+    }
+
+    fn close_tag(&mut self, name: &str) -> Option<()> {
+        self.skip_whitespace();
+        if self.name()? != name {
+            self.mismatched_close_tag = true;
+            return None;
+        }
+        self.skip_whitespace();
+        self.expect(b">")
+    }
+
+    // Elements are kept on an explicit stack, so nesting depth is not limited by the call stack.
+    fn element(&mut self) -> Option<Value> {
+        self.expect(b"<")?;
+        let (frame, is_empty) = self.start_tag()?;
+        if is_empty { return Some(wrap(frame.name, frame.into_value())); }
+
+        let mut stack = vec![frame];
+        let root = self.content(&mut stack);
+        for frame in stack { drop_value(Value::Object(frame.json)); }
+        root
+    }
+
+    // Content of the open elements on `stack` up to the close tag of the first one.
+    fn content(&mut self, stack: &mut Vec<Frame<'a>>) -> Option<Value> {
+        loop {
+            let text_end = self.position + memchr(b'<', &self.bytes[self.position..])?;
+            let text = self.input[self.position..text_end].trim();
+            if !text.is_empty() { stack.last_mut()?.text.push_str(text); }
+            self.position = text_end;
+
+            if self.starts_with(b"<!--") {
+                self.skip_past(self.position + 4, b"-->")?;
+            } else if self.starts_with(b"</") {
+                self.position += 2;
+                self.close_tag(stack.last()?.name)?;
+                let frame = stack.pop()?;
+
+                let name = frame.name;
+                let value = frame.into_value();
+                match stack.last_mut() {
+                    Some(parent) => {
+                        parent.has_nested = true;
+                        insert_child(&mut parent.json, name.to_string(), value);
+                    }
+                    None => return Some(wrap(name, value)),
+                }
+            } else {
+                self.position += 1;
+                let (frame, is_empty) = self.start_tag()?;
+                let parent = stack.last_mut()?;
+                parent.has_nested = true;
+                if is_empty {
+                    let name = frame.name;
+                    insert_child(&mut parent.json, name.to_string(), frame.into_value());
+                } else {
+                    stack.push(frame);
+                }
+            }
+        }
+    }
+}
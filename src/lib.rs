@@ -1,20 +1,170 @@
+#![allow(non_snake_case)]
+
 use pest::Parser;
 use anyhow::*;
-use pest::iterators::Pair;
-use serde_json::{json, Value};
+use pest::iterators::{Pair, Pairs};
+use serde_json::Value;
 use pest_derive::Parser;
 
+#[cfg(feature = "async")]
+pub mod async_reader;
+pub mod borrowed;
+pub mod c14n;
+pub mod check;
+pub mod codegen;
+pub mod de;
+pub mod diff;
+#[cfg(feature = "validation")]
+pub mod dtd;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+pub mod escape;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fmt;
+pub mod lenient;
+pub mod lexer;
+pub mod output;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod recover;
+#[cfg(feature = "validation")]
+pub mod rng;
+pub mod schema;
+pub mod ser;
+pub mod source_map;
+pub mod tree;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "validation")]
+pub mod xsd;
+
 #[derive(Parser)]
 #[grammar = "./grammar.pest"]
 pub struct Grammar;
 
 pub fn start_parser(input: &str) -> Result<Value> {
     if input.trim().is_empty() { return Err(anyhow!("XML is empty")); }
-    let parsed = Grammar::parse(Rule::xml, input)?.next().ok_or_else(|| anyhow!("Invalid XML"))?;
 
-    let json = parse_element(parsed.into_inner().next().unwrap());
+    // The lexer only rejects input that the grammar rejects too, pest reports the error in that case.
+    // Pest recurses for every nested element, errors in deeper documents are reported by the lexer.
+    lexer::convert(input).or_else(|rejection| {
+        if rejection.depth > MAX_PEST_DEPTH { Err(rejection_error(input, rejection)) } else { start_pest_parser(input) }
+    })
+}
+
+// Nesting that pest handles on small thread stacks in debug builds.
+pub(crate) const MAX_PEST_DEPTH: usize = 256;
+
+fn rejection_error(input: &str, rejection: lexer::Rejection) -> Error {
+    if rejection.mismatched_close_tag { return anyhow!("There are different open and close tags names"); }
+
+    let (line, column) = line_column(input, rejection.offset);
+    anyhow!("Invalid XML at {}:{}", line, column)
+}
+
+// Line and column of a byte offset, the column is counted in characters.
+pub(crate) fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before[before.rfind('\n').map_or(0, |index| index + 1)..].chars().count() + 1;
+    (line, column)
+}
 
-    return json;
+// Pair tree for everything that works on pest pairs. Pest recurses for every nested element,
+// deeper documents are refused instead of overflowing the stack.
+pub(crate) fn parse_pairs(input: &str) -> Result<Pair<'_, Rule>> {
+    if lexer::nesting_depth(input.as_bytes()) > MAX_PEST_DEPTH { return Err(anyhow!("Document is nested deeper than {} levels", MAX_PEST_DEPTH)); }
+    Grammar::parse(Rule::xml, input)?.next().ok_or_else(|| anyhow!("Invalid XML"))
+}
+
+// Object with a single key, `json!` would serialize `value` again and recurse through all of it.
+pub(crate) fn wrap(name: impl Into<String>, value: Value) -> Value {
+    let mut json = serde_json::Map::new();
+    json.insert(name.into(), value);
+    Value::Object(json)
+}
+
+// Repeated sibling elements become an array of their values in document order.
+pub(crate) fn insert_child(json: &mut serde_json::Map<String, Value>, name: String, value: Value) {
+    match json.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(first) => *first = Value::Array(vec![std::mem::take(first), value]),
+        None => { json.insert(name, value); }
+    }
+}
+
+// serde_json drops nested values recursively, very deep results are taken apart with an explicit stack instead.
+pub fn drop_value(value: Value) {
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Array(values) => stack.extend(values),
+            Value::Object(map) => stack.extend(map.into_iter().map(|(_, value)| value)),
+            _ => {}
+        }
+    }
+}
+
+// Deep copy with an explicit stack, `Value::clone` recurses like dropping does.
+pub(crate) fn clone_value(value: &Value) -> Value {
+    enum Copy<'a> {
+        Array(Vec<Value>, std::slice::Iter<'a, Value>),
+        Object(serde_json::Map<String, Value>, serde_json::map::Iter<'a>, String),
+    }
+
+    // Containers are copied once all their items are, scalars right away.
+    fn open(value: &Value) -> Option<Copy<'_>> {
+        match value {
+            Value::Array(values) => Some(Copy::Array(Vec::with_capacity(values.len()), values.iter())),
+            Value::Object(map) => Some(Copy::Object(serde_json::Map::new(), map.iter(), String::new())),
+            _ => None,
+        }
+    }
+
+    let mut stack = match open(value) {
+        Some(copy) => vec![copy],
+        None => return value.clone(),
+    };
+    loop {
+        let next = match stack.last_mut().unwrap() {
+            Copy::Array(_, items) => items.next(),
+            Copy::Object(_, entries, key) => entries.next().map(|(name, value)| {
+                *key = name.clone();
+                value
+            }),
+        };
+        let copied = match next {
+            Some(value) => match open(value) {
+                Some(copy) => {
+                    stack.push(copy);
+                    continue;
+                }
+                None => value.clone(),
+            },
+            None => match stack.pop().unwrap() {
+                Copy::Array(values, _) => Value::Array(values),
+                Copy::Object(map, _, _) => Value::Object(map),
+            },
+        };
+        match stack.last_mut() {
+            None => return copied,
+            Some(Copy::Array(values, _)) => values.push(copied),
+            Some(Copy::Object(map, _, key)) => {
+                map.insert(std::mem::take(key), copied);
+            }
+        }
+    }
+}
+
+// Conversion through the pest pair tree, kept for error reporting and as the reference for the lexer.
+pub fn start_pest_parser(input: &str) -> Result<Value> {
+    if input.trim().is_empty() { return Err(anyhow!("XML is empty")); }
+    let parsed = parse_pairs(input)?;
+
+    let root = parsed.into_inner().find(|pair| pair.as_rule() == Rule::element).unwrap();
+
+    parse_element(root)
 }
 
 pub fn parse_open_tag(pair: Pair<Rule>) -> (String, Vec<(String, String)>) {
@@ -35,51 +185,76 @@ pub fn parse_open_tag(pair: Pair<Rule>) -> (String, Vec<(String, String)>) {
     (name, attributes)
 }
 
-pub fn parse_element(pair: Pair<Rule>) -> Result<Value> {
-    let mut inner = pair.into_inner();
-    let open_tag = inner.next().unwrap();
-    let mut close_tag = None;
-    let (name, attributes) = parse_open_tag(open_tag);
-
-    let mut nested_content: Vec<Value> = vec![];
-    let mut text_content = String::new();
-
-    while let Some(next) = inner.next() {
-        match next.as_rule() {
-            Rule::element => nested_content.push(parse_element(next)?),
-            Rule::inner_text => {
-                let text = next.as_str().trim();
-                if !text.is_empty() { text_content.push_str(text); }
-            }
-            Rule::close_tag => {
-                close_tag = Some(next.into_inner().next().unwrap().as_str().to_string());
-            }
-            _ => {}
+// Element that is being converted by `parse_element`, `inner` holds the pairs that are not visited yet.
+struct ElementFrame<'a> {
+    name: String,
+    is_empty_element: bool,
+    json: serde_json::Map<String, Value>,
+    text_content: String,
+    has_nested: bool,
+    close_tag: Option<String>,
+    inner: Pairs<'a, Rule>,
+}
+
+impl<'a> ElementFrame<'a> {
+    fn new(pair: Pair<'a, Rule>) -> Self {
+        let mut inner = pair.into_inner();
+        let open_tag = inner.next().unwrap();
+        let is_empty_element = open_tag.as_rule() == Rule::empty_element;
+        let (name, attributes) = parse_open_tag(open_tag);
+
+        let mut json = serde_json::Map::new();
+        for (attribute_name, attribute_value) in attributes {
+            json.insert(format!("_{}", attribute_name), Value::String(attribute_value));
         }
+
+        ElementFrame { name, is_empty_element, json, text_content: String::new(), has_nested: false, close_tag: None, inner }
     }
 
-    if let Some(close_tag_name) = close_tag {
-        if close_tag_name != name { return Err(anyhow!("There are different open and close tags names")); }
-    } else { 
-        return Err(anyhow!("There are different open and close tags names"));
-     }
+    fn finish(mut self) -> Result<(String, Value)> {
+        if let Some(close_tag_name) = self.close_tag {
+            if close_tag_name != self.name { return Err(anyhow!("There are different open and close tags names")); }
+        } else if !self.is_empty_element {
+            return Err(anyhow!("There are different open and close tags names"));
+        }
 
-    let mut json = serde_json::Map::new();
+        if !self.has_nested && !self.text_content.is_empty() {
+            if self.json.is_empty() { return Ok((self.name, Value::String(self.text_content))); }
+            self.json.insert("_text".to_string(), Value::String(self.text_content));
+        }
 
-    for (attribute_name, attribute_value) in attributes {
-        json.insert(format!("_{}", attribute_name), Value::String(attribute_value));
+        Ok((self.name, Value::Object(self.json)))
     }
+}
+
+// Nested elements are kept on an explicit stack, so the depth of the document is only limited by memory.
+pub fn parse_element(pair: Pair<Rule>) -> Result<Value> {
+    let mut stack = vec![ElementFrame::new(pair)];
 
-    if !nested_content.is_empty() {
-        for content in nested_content {
-            let (object_name, object_content) = content.as_object().unwrap().iter().next().unwrap();
-            json.insert(object_name.clone(), object_content.clone());
+    loop {
+        let frame = stack.last_mut().unwrap();
+        match frame.inner.next() {
+            Some(next) => match next.as_rule() {
+                Rule::element => stack.push(ElementFrame::new(next)),
+                Rule::inner_text => {
+                    let text = next.as_str().trim();
+                    if !text.is_empty() { frame.text_content.push_str(text); }
+                }
+                Rule::close_tag => {
+                    frame.close_tag = Some(next.into_inner().next().unwrap().as_str().to_string());
+                }
+                _ => {}
+            },
+            None => {
+                let (name, value) = stack.pop().unwrap().finish()?;
+                match stack.last_mut() {
+                    Some(parent) => {
+                        parent.has_nested = true;
+                        insert_child(&mut parent.json, name, value);
+                    }
+                    None => return Ok(wrap(name, value)),
+                }
+            }
         }
-    } else if !text_content.is_empty() {
-        if json.is_empty() { return Ok(json!({ name: text_content })); }
-        else { json.insert("_text".to_string(), Value::String(text_content)); }
     }
-
-    let completed_json = json!({ name: json });
-    Ok(completed_json)
-}
\ No newline at end of file
+}
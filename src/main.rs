@@ -1,7 +1,24 @@
 use anyhow::*;
 use XML_to_JSON_Parser::*;
 use clap::*;
+use XML_to_JSON_Parser::c14n::*;
+use XML_to_JSON_Parser::check::*;
+use XML_to_JSON_Parser::codegen::*;
+use XML_to_JSON_Parser::diff::*;
+use XML_to_JSON_Parser::dtd::*;
+use XML_to_JSON_Parser::encoding::*;
+use XML_to_JSON_Parser::fmt::*;
+use XML_to_JSON_Parser::lenient::*;
+use XML_to_JSON_Parser::output::*;
+use XML_to_JSON_Parser::recover::*;
+use XML_to_JSON_Parser::rng::*;
+use XML_to_JSON_Parser::schema::*;
+use XML_to_JSON_Parser::source_map::*;
+use XML_to_JSON_Parser::tree::*;
+use XML_to_JSON_Parser::xsd::*;
 use std::fs;
+use std::path::Path;
+use std::process;
 
 #[derive(Parser)]
 struct Cli {
@@ -11,20 +28,304 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    Parse { file: String },
+    Parse {
+        file: String,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+        to: OutputFormat,
+        #[arg(long)]
+        recover: bool,
+        #[arg(long, conflicts_with = "recover")]
+        lenient: bool,
+        #[arg(long, value_name = "TAG=TAGS", requires = "lenient")]
+        auto_close: Vec<String>,
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["recover", "lenient"])]
+        source_map: Option<String>,
+        #[arg(long, value_name = "FILE")]
+        dtd: Option<String>,
+    },
+    Check {
+        #[arg(required = true)]
+        files: Vec<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    Validate {
+        #[arg(required = true)]
+        files: Vec<String>,
+        #[arg(long, value_name = "FILE")]
+        xsd: Option<String>,
+        #[arg(long, value_name = "FILE", conflicts_with = "xsd")]
+        dtd: Option<String>,
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["xsd", "dtd"])]
+        rng: Option<String>,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+    Fmt {
+        #[arg(required = true)]
+        files: Vec<String>,
+        #[arg(long)]
+        check: bool,
+    },
+    C14n {
+        file: String,
+        #[arg(long)]
+        with_comments: bool,
+    },
+    Diff {
+        old: String,
+        new: String,
+        #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+        format: DiffFormat,
+    },
+    InferSchema {
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    XsdToJsonschema {
+        xsd: String,
+    },
+    Codegen {
+        #[arg(value_enum)]
+        language: Language,
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
     Instruction,
     Credits,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DiffFormat {
+    Text,
+    JsonPatch,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Language {
+    Rust,
+    Ts,
+}
+
+fn read_xml(file: &str) -> anyhow::Result<String> {
+    read_xml_with_bytes(file).map(|(xml, _)| xml)
+}
+
+// Decoded text together with the bytes of the file, for commands that write the file back.
+fn read_xml_with_bytes(file: &str) -> anyhow::Result<(String, Vec<u8>)> {
+    let bytes = fs::read(file).with_context(|| format!("Error reading file {}", file))?;
+    let xml = decode_bytes(&bytes).with_context(|| format!("Error decoding file {}", file))?;
+    Ok((xml, bytes))
+}
+
+// Documents with the attribute defaults of their DTD, so the inferred types match the output of `parse`.
+fn read_documents(files: &[String]) -> anyhow::Result<Vec<Document>> {
+    files.iter().map(|file| {
+        let xml = read_xml(file)?;
+        let mut document = parse_document(&xml).with_context(|| format!("Error parsing file {}", file))?;
+        let dtd = Dtd::for_document(&xml, Some(Path::new(file)), None).with_context(|| format!("Error reading DTD of file {}", file))?;
+        dtd.apply_tree_defaults(&mut document.root);
+        Ok(document)
+    }).collect()
+}
+
+// Prints the differences and returns whether there are any.
+fn diff_files(old: &str, new: &str, format: DiffFormat) -> anyhow::Result<bool> {
+    let old_xml = read_xml(old)?;
+    let new_xml = read_xml(new)?;
+
+    Ok(match format {
+        DiffFormat::Text => {
+            let changes = diff_documents(&parse_document(&old_xml)?, &parse_document(&new_xml)?);
+            for change in &changes { println!("{}", change); }
+            !changes.is_empty()
+        }
+        DiffFormat::JsonPatch => {
+            let (old_json, new_json) = (start_parser(&old_xml)?, start_parser(&new_xml)?);
+            let patch = json_patch(&old_json, &new_json);
+            println!("{}", to_json_string(&patch, true));
+            let differ = patch.as_array().is_some_and(|operations| !operations.is_empty());
+            for value in [old_json, new_json, patch] { drop_value(value); }
+            differ
+        }
+    })
+}
+
 fn main() -> anyhow::Result<()> {
     
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Parse { file } => {
-            let xml = fs::read_to_string(&file).with_context(|| format!("Error reading file"))?;
-            let json = start_parser(&xml)?;
-            println!("{}", serde_json::to_string_pretty(&json)?);
+        Commands::Parse { file, to, recover, lenient, auto_close, source_map, dtd } => {
+            let xml = read_xml(&file)?;
+            let dtd = dtd.map(Dtd::from_file).transpose()?;
+            let mut code = 0;
+
+            let mut json = if recover || lenient {
+                let recovered = if lenient {
+                    let mut options = LenientOptions::default();
+                    for rule in auto_close {
+                        let (name, closers) = rule.split_once('=').ok_or_else(|| anyhow!("Auto-close rule {} must look like TAG=TAG,TAG", rule))?;
+                        options.auto_close.insert(name.to_string(), closers.split(',').map(|closer| closer.trim().to_string()).collect());
+                    }
+                    parse_lenient(&xml, &options)
+                } else {
+                    parse_recovering(&xml)
+                };
+
+                for diagnostic in &recovered.diagnostics { eprintln!("{}", diagnostic.to_text(&file)); }
+                if recovered.partial { eprintln!("{}: output is partial", file); }
+                code = exit_code(&recovered.diagnostics);
+                recovered.value
+            } else if let Some(source_map_file) = source_map {
+                let (json, source_map) = start_parser_with_source_map(&xml)?;
+                let source_map = serde_json::to_string_pretty(&source_map_to_json(&source_map))?;
+                fs::write(&source_map_file, source_map).with_context(|| format!("Error writing file {}", source_map_file))?;
+                json
+            } else {
+                start_parser(&xml)?
+            };
+
+            // Every mode gets the attribute defaults of the internal subset, the external subset and `--dtd`.
+            let defaults = Dtd::for_document(&xml, Some(Path::new(&file)), dtd.as_ref()).with_context(|| format!("Error reading DTD of file {}", file))?;
+            if !defaults.is_empty() { defaults.apply_defaults(&mut json); }
+
+            // Printed and dropped without recursion, so documents of any depth do not overflow the stack.
+            let output = match to {
+                OutputFormat::Json => Ok(to_json_string(&json, true) + "\n"),
+                OutputFormat::Yaml => to_yaml_string(&json),
+                OutputFormat::Toml => to_toml_string(&json),
+            };
+            drop_value(json);
+            print!("{}", output?);
+            if code != 0 { process::exit(code); }
+        }
+
+        Commands::Check { files, format } => {
+            let mut reports = vec![];
+            let mut all_diagnostics = vec![];
+
+            for file in files {
+                let xml = read_xml(&file)?;
+                let diagnostics = check(&xml);
+                for diagnostic in &diagnostics {
+                    match format {
+                        Format::Text => eprintln!("{}", diagnostic.to_text(&file)),
+                        Format::Json => reports.push(diagnostic.to_json(&file)),
+                    }
+                }
+                all_diagnostics.extend(diagnostics);
+            }
+
+            if let Format::Json = format { println!("{}", serde_json::to_string_pretty(&reports)?); }
+            let exit_code = exit_code(&all_diagnostics);
+            if exit_code != 0 { process::exit(exit_code); }
+        }
+
+        Commands::Validate { files, xsd, dtd, rng, format } => {
+            let schema = xsd.map(Schema::from_file).transpose()?;
+            let dtd = dtd.map(Dtd::from_file).transpose()?;
+            let relax_ng = rng.map(RelaxNg::from_file).transpose()?;
+            let mut reports = vec![];
+            let mut all_diagnostics = vec![];
+
+            for file in files {
+                let xml = read_xml(&file)?;
+                // Documents that are not well-formed are reported like `check` and not validated.
+                let mut diagnostics = check(&xml);
+                if exit_code(&diagnostics) == 0 {
+                    // Without a schema the document is validated against its internal subset and the DTD given with `--dtd`
+                    // or, without it, the DTD file named in the document type declaration.
+                    let validity = match (&schema, &relax_ng) {
+                        (Some(schema), _) => schema.validate(&xml)?,
+                        (_, Some(relax_ng)) => relax_ng.validate(&xml)?,
+                        _ if dtd.is_none() && find_doctype(&xml).is_none() => {
+                            return Err(anyhow!("File {} has no document type declaration, use --xsd, --rng or --dtd", file));
+                        }
+                        _ => Dtd::for_document(&xml, Some(Path::new(&file)), dtd.as_ref()).with_context(|| format!("Error reading DTD of file {}", file))?.validate(&xml)?,
+                    };
+                    diagnostics.extend(validity);
+                }
+                for diagnostic in &diagnostics {
+                    match format {
+                        Format::Text => eprintln!("{}", diagnostic.to_text(&file)),
+                        Format::Json => reports.push(diagnostic.to_json(&file)),
+                    }
+                }
+                all_diagnostics.extend(diagnostics);
+            }
+
+            if let Format::Json = format { println!("{}", serde_json::to_string_pretty(&reports)?); }
+            let exit_code = exit_code(&all_diagnostics);
+            if exit_code != 0 { process::exit(exit_code); }
+        }
+
+        Commands::Fmt { files, check } => {
+            let mut unformatted = false;
+
+            for file in files {
+                let (xml, bytes) = read_xml_with_bytes(&file)?;
+                let formatted = format_xml(&xml).with_context(|| format!("Error formatting file {}", file))?;
+                if formatted == xml { continue; }
+
+                if check {
+                    println!("{} is not formatted", file);
+                    unformatted = true;
+                } else {
+                    // Formatted text is written as UTF-8, files in other encodings are not rewritten.
+                    let utf8 = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+                    if std::str::from_utf8(utf8).is_err() { return Err(anyhow!("File {} is not UTF-8 and can not be rewritten", file)); }
+                    fs::write(&file, formatted).with_context(|| format!("Error writing file {}", file))?;
+                }
+            }
+
+            if unformatted { process::exit(1); }
+        }
+
+        Commands::C14n { file, with_comments } => {
+            let xml = read_xml(&file)?;
+            print!("{}", canonicalize(&xml, with_comments)?);
+        }
+
+        Commands::Diff { old, new, format } => {
+            // Like diff(1): 1 when the files differ, 2 when they can not be compared.
+            match diff_files(&old, &new, format) {
+                Result::Ok(true) => process::exit(1),
+                Result::Ok(false) => {}
+                Err(error) => {
+                    eprintln!("Error: {:?}", error);
+                    process::exit(2);
+                }
+            }
+        }
+
+        Commands::InferSchema { files } => {
+            println!("{}", serde_json::to_string_pretty(&infer_schema(&read_documents(&files)?))?);
+        }
+
+        Commands::XsdToJsonschema { xsd } => {
+            println!("{}", serde_json::to_string_pretty(&Schema::from_file(&xsd)?.to_json_schema())?);
+        }
+
+        Commands::Codegen { language, files } => {
+            let shape = Shape::from_documents(&read_documents(&files)?);
+            match language {
+                Language::Rust => print!("{}", rust_types(&shape)),
+                Language::Ts => print!("{}", typescript_types(&shape)),
+            }
         }
 
         Commands::Instruction => {
@@ -0,0 +1,121 @@
+#[cfg(feature = "toml")]
+use crate::escape::escape_pointer;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+use crate::start_parser;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+use anyhow::*;
+use serde_json::Value;
+
+// Nesting that the recursive YAML and TOML serializers handle on the main thread, deeper values are reported as an error.
+#[cfg(any(feature = "yaml", feature = "toml"))]
+const MAX_SERIALIZE_DEPTH: usize = 1024;
+
+// Same text as `serde_json::to_string` or `to_string_pretty`, written with an explicit stack so values of any depth can be printed.
+pub fn to_json_string(value: &Value, pretty: bool) -> String {
+    enum Item<'a> {
+        Value(&'a Value, usize),
+        Text(String),
+    }
+
+    let separator = |first: bool, indent: usize| match (pretty, first) {
+        (true, first) => format!("{}\n{}", if first { "" } else { "," }, "  ".repeat(indent)),
+        (false, true) => String::new(),
+        (false, false) => ",".to_string(),
+    };
+    let close = |bracket: char, indent: usize| if pretty { format!("\n{}{}", "  ".repeat(indent), bracket) } else { bracket.to_string() };
+
+    let mut output = String::new();
+    let mut stack = vec![Item::Value(value, 0)];
+    while let Some(item) = stack.pop() {
+        match item {
+            Item::Text(text) => output.push_str(&text),
+            Item::Value(Value::Array(values), indent) if !values.is_empty() => {
+                output.push('[');
+                stack.push(Item::Text(close(']', indent)));
+                for (index, value) in values.iter().enumerate().rev() {
+                    stack.push(Item::Value(value, indent + 1));
+                    stack.push(Item::Text(separator(index == 0, indent + 1)));
+                }
+            }
+            Item::Value(Value::Object(map), indent) if !map.is_empty() => {
+                output.push('{');
+                stack.push(Item::Text(close('}', indent)));
+                for (index, (key, value)) in map.iter().enumerate().rev() {
+                    stack.push(Item::Value(value, indent + 1));
+                    let key = Value::String(key.clone());
+                    stack.push(Item::Text(format!("{}{}:{}", separator(index == 0, indent + 1), key, if pretty { " " } else { "" })));
+                }
+            }
+            // Scalars and empty arrays or objects have no nested values.
+            Item::Value(value, _) => output.push_str(&value.to_string()),
+        }
+    }
+    output
+}
+
+#[cfg(feature = "yaml")]
+pub fn to_yaml_string(value: &Value) -> Result<String> {
+    check_depth(value, "YAML")?;
+    Ok(serde_norway::to_string(value)?)
+}
+
+// TOML has no null and its document root is always a table, those cases are reported with the JSON Pointer of the value.
+#[cfg(feature = "toml")]
+pub fn to_toml_string(value: &Value) -> Result<String> {
+    if !value.is_object() { return Err(anyhow!("TOML document root must be a table, got {}", type_name(value))); }
+    check_depth(value, "TOML")?;
+    check_toml_value(value, "")?;
+    Ok(toml::to_string_pretty(value)?)
+}
+
+#[cfg(feature = "yaml")]
+pub fn xml_to_yaml(input: &str) -> Result<String> {
+    to_yaml_string(&start_parser(input)?)
+}
+
+#[cfg(feature = "toml")]
+pub fn xml_to_toml(input: &str) -> Result<String> {
+    to_toml_string(&start_parser(input)?)
+}
+
+#[cfg(any(feature = "yaml", feature = "toml"))]
+fn check_depth(value: &Value, format: &str) -> Result<()> {
+    let mut stack = vec![(value, 1)];
+    while let Some((value, depth)) = stack.pop() {
+        if depth > MAX_SERIALIZE_DEPTH { return Err(anyhow!("{} output supports at most {} nested levels", format, MAX_SERIALIZE_DEPTH)); }
+        match value {
+            Value::Array(values) => stack.extend(values.iter().map(|value| (value, depth + 1))),
+            Value::Object(map) => stack.extend(map.values().map(|value| (value, depth + 1))),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "toml")]
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(feature = "toml")]
+fn check_toml_value(value: &Value, pointer: &str) -> Result<()> {
+    match value {
+        Value::Null => Err(anyhow!("TOML cannot represent null at {}", if pointer.is_empty() { "/" } else { pointer })),
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() { check_toml_value(item, &format!("{}/{}", pointer, index))?; }
+            Ok(())
+        }
+        Value::Object(object) => {
+            for (key, item) in object { check_toml_value(item, &format!("{}/{}", pointer, escape_pointer(key)))?; }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,113 @@
+use crate::check::{check, DiagnosticKind};
+use crate::encoding::decode_bytes;
+use crate::lenient::*;
+use crate::recover::*;
+use crate::start_parser;
+use pyo3::exceptions::{PyOSError, PyTypeError, PyUserWarning, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde_json::Value;
+use std::ffi::CString;
+use std::fs;
+
+pyo3::create_exception!(xml_to_json_parser, XmlParseError, PyValueError, "Raised when the XML can not be converted, `line` and `column` point at the problem or are `None` when it has no position.");
+pyo3::create_exception!(xml_to_json_parser, PartialResultWarning, PyUserWarning, "Warned by the recover and lenient modes when the result is partial.");
+
+#[derive(Default)]
+struct Options {
+    recover: bool,
+    lenient: bool,
+}
+
+impl Options {
+    fn extract(options: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut result = Options::default();
+        for (key, value) in options.into_iter().flatten() {
+            let key: String = key.extract()?;
+            match key.as_str() {
+                "recover" => result.recover = value.extract()?,
+                "lenient" => result.lenient = value.extract()?,
+                _ => return Err(PyTypeError::new_err(format!("Unknown option {}", key))),
+            }
+        }
+        if result.recover && result.lenient { return Err(PyTypeError::new_err("Options recover and lenient can not be used together")); }
+        Ok(result)
+    }
+}
+
+// Same pipeline as the `parse` command: strict `start_parser` by default, diagnostics of the other modes become Python warnings.
+fn convert(py: Python<'_>, xml: &str, source: &str, options: &Options) -> PyResult<Value> {
+    if !options.recover && !options.lenient {
+        return start_parser(xml).map_err(|error| parse_error(py, xml, &error.to_string()));
+    }
+
+    let recovered = if options.lenient { parse_lenient(xml, &LenientOptions::default()) } else { parse_recovering(xml) };
+    let category = py.get_type::<PyUserWarning>();
+    for diagnostic in &recovered.diagnostics {
+        let message = CString::new(diagnostic.to_text(source)).unwrap_or_default();
+        PyErr::warn(py, &category, &message, 1)?;
+    }
+    if recovered.partial {
+        let message = CString::new(format!("{}: output is partial", source)).unwrap_or_default();
+        PyErr::warn(py, &py.get_type::<PartialResultWarning>(), &message, 1)?;
+    }
+    Ok(recovered.value)
+}
+
+// The converter errors carry no position, the error is raised with the first error diagnostic of `check` for the same input.
+// When `check` accepts the input the converter message is raised as is, without a position.
+fn parse_error(py: Python<'_>, xml: &str, message: &str) -> PyErr {
+    let diagnostics = check(xml);
+    let (message, position) = match diagnostics.iter().find(|diagnostic| diagnostic.kind != DiagnosticKind::Warning) {
+        Some(diagnostic) => (format!("{} at {}:{}", diagnostic.message, diagnostic.line, diagnostic.column), Some((diagnostic.line, diagnostic.column))),
+        None => (message.trim().to_string(), None),
+    };
+    let error = XmlParseError::new_err(message);
+    let value = error.value(py);
+    let _ = value.setattr("line", position.map(|(line, _)| line));
+    let _ = value.setattr("column", position.map(|(_, column)| column));
+    error
+}
+
+fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(value) => value.into_pyobject(py)?.to_owned().into_any(),
+        Value::Number(number) => match number.as_i64() {
+            Some(number) => number.into_pyobject(py)?.into_any(),
+            None => number.as_f64().unwrap_or_default().into_pyobject(py)?.into_any(),
+        },
+        Value::String(value) => value.into_pyobject(py)?.into_any(),
+        Value::Array(values) => PyList::new(py, values.iter().map(|value| to_python(py, value)).collect::<PyResult<Vec<_>>>()?)?.into_any(),
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map { dict.set_item(key, to_python(py, value)?)?; }
+            dict.into_any()
+        }
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (xml, **options))]
+fn parse<'py>(py: Python<'py>, xml: &str, options: Option<&Bound<'py, PyDict>>) -> PyResult<Bound<'py, PyAny>> {
+    let options = Options::extract(options)?;
+    to_python(py, &convert(py, xml, "<string>", &options)?)
+}
+
+#[pyfunction]
+#[pyo3(signature = (path, **options))]
+fn parse_file<'py>(py: Python<'py>, path: &str, options: Option<&Bound<'py, PyDict>>) -> PyResult<Bound<'py, PyAny>> {
+    let options = Options::extract(options)?;
+    let bytes = fs::read(path).map_err(|error| PyOSError::new_err(format!("Error reading file {}: {}", path, error)))?;
+    let xml = decode_bytes(&bytes).map_err(|error| XmlParseError::new_err(format!("Error decoding file {}: {}", path, error)))?;
+    to_python(py, &convert(py, &xml, path, &options)?)
+}
+
+#[pymodule]
+pub fn xml_to_json_parser(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add("XmlParseError", module.py().get_type::<XmlParseError>())?;
+    module.add("PartialResultWarning", module.py().get_type::<PartialResultWarning>())?;
+    module.add_function(wrap_pyfunction!(parse, module)?)?;
+    module.add_function(wrap_pyfunction!(parse_file, module)?)?;
+    Ok(())
+}
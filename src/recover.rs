@@ -0,0 +1,327 @@
+use crate::check::*;
+use crate::lenient::LenientOptions;
+use crate::lexer::doctype_end;
+use crate::tree::*;
+use serde_json::Value;
+
+// Result of the recovering mode: `value` is the best-effort JSON, `partial` is set when something had to be fixed or skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovered {
+    pub value: Value,
+    pub diagnostics: Vec<Diagnostic>,
+    pub partial: bool,
+}
+
+pub fn parse_recovering(input: &str) -> Recovered {
+    Scanner::new(input, None).recover()
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic()
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':')
+}
+
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    name.chars().next().is_some_and(is_name_start) && name.chars().all(is_name_char)
+}
+
+pub(crate) struct Scanner<'a> {
+    input: &'a str,
+    lenient: Option<&'a LenientOptions>,
+    line_starts: Vec<usize>,
+    diagnostics: Vec<Diagnostic>,
+    stack: Vec<(Element, usize)>,
+    root: Option<Element>,
+}
+
+impl<'a> Scanner<'a> {
+    pub(crate) fn new(input: &'a str, lenient: Option<&'a LenientOptions>) -> Self {
+        let line_starts = std::iter::once(0).chain(input.match_indices('\n').map(|(index, _)| index + 1)).collect();
+        Scanner { input, lenient, line_starts, diagnostics: vec![], stack: vec![], root: None }
+    }
+
+    pub(crate) fn recover(mut self) -> Recovered {
+        let root = self.run();
+
+        let value = match &root {
+            Some(root) => root.to_json(),
+            None => Value::Null,
+        };
+        let partial = root.is_none() || self.diagnostics.iter().any(|diagnostic| diagnostic.kind != DiagnosticKind::Warning);
+        if let Some(root) = root { drop_element(root); }
+        Recovered { value, diagnostics: self.diagnostics, partial }
+    }
+
+    fn names_match(&self, open_name: &str, close_name: &str) -> bool {
+        match self.lenient {
+            Some(options) if options.case_insensitive => open_name.eq_ignore_ascii_case(close_name),
+            _ => open_name == close_name,
+        }
+    }
+
+    // Elements with an optional end tag are closed silently apart from a warning.
+    fn close_unclosed(&mut self, element: Element, offset: usize, reason: &str) {
+        let optional_end = self.lenient.is_some_and(|options| options.has_optional_end_tag(&element.name));
+        if optional_end {
+            self.report(DiagnosticKind::Warning, offset, format!("Element <{}> is closed {}", element.name, reason));
+        } else {
+            self.report(DiagnosticKind::Structure, offset, format!("Element <{}> is not closed", element.name));
+        }
+        self.attach(element, offset);
+    }
+
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let column = self.input[self.line_starts[line - 1]..offset].chars().count() + 1;
+        (line, column)
+    }
+
+    fn report(&mut self, kind: DiagnosticKind, offset: usize, message: String) {
+        let (line, column) = self.position(offset);
+        self.diagnostics.push(Diagnostic { kind, line, column, message });
+    }
+
+    fn run(&mut self) -> Option<Element> {
+        if self.input.trim().is_empty() {
+            self.report(DiagnosticKind::Syntax, 0, "XML is empty".to_string());
+            return None;
+        }
+
+        let mut cursor = 0;
+        while cursor < self.input.len() {
+            let next = self.input[cursor..].find('<').map(|index| cursor + index).unwrap_or(self.input.len());
+            if next > cursor { self.text(cursor, next); }
+            if next == self.input.len() { break; }
+            cursor = self.markup(next);
+        }
+
+        while let Some((element, offset)) = self.stack.pop() {
+            self.close_unclosed(element, offset, "at the end of the input");
+        }
+
+        if self.root.is_none() { self.report(DiagnosticKind::Syntax, 0, "There is no root element".to_string()); }
+        self.root.take()
+    }
+
+    fn text(&mut self, start: usize, end: usize) {
+        let text = &self.input[start..end];
+        match self.stack.last_mut() {
+            Some((parent, _)) => parent.children.push(Node::Text(text.to_string())),
+            None => {
+                if !text.trim().is_empty() {
+                    let offset = start + (text.len() - text.trim_start().len());
+                    self.report(DiagnosticKind::Syntax, offset, "Text outside of the root element is ignored".to_string());
+                }
+            }
+        }
+    }
+
+    fn attach(&mut self, element: Element, offset: usize) {
+        match self.stack.last_mut() {
+            Some((parent, _)) => parent.children.push(Node::Element(element)),
+            None if self.root.is_none() => self.root = Some(element),
+            None => {
+                self.report(DiagnosticKind::Structure, offset, format!("Element <{}> after the root element is ignored", element.name));
+                drop_element(element);
+            }
+        }
+    }
+
+    // Handles markup starting at `start` (a '<') and returns the offset right after it.
+    fn markup(&mut self, start: usize) -> usize {
+        let rest = &self.input[start..];
+
+        if rest.starts_with("<!--") {
+            return match rest.find("-->") {
+                Some(end) => {
+                    let comment = rest[4..end].to_string();
+                    if let Some((parent, _)) = self.stack.last_mut() { parent.children.push(Node::Comment(comment)); }
+                    start + end + 3
+                }
+                None => {
+                    self.report(DiagnosticKind::Syntax, start, "Comment is not closed".to_string());
+                    self.input.len()
+                }
+            };
+        }
+
+        if rest.starts_with("<?") {
+            return match rest.find("?>") {
+                Some(end) => start + end + 2,
+                None => {
+                    self.report(DiagnosticKind::Syntax, start, "Declaration is not closed".to_string());
+                    self.input.len()
+                }
+            };
+        }
+
+        // HTML writes the keyword in any case, the lenient mode accepts `<!doctype html>` as well.
+        let doctype = rest.get(.."<!DOCTYPE".len()).is_some_and(|keyword| keyword == "<!DOCTYPE" || self.lenient.is_some() && keyword.eq_ignore_ascii_case("<!DOCTYPE"));
+        if doctype {
+            let Some(end) = doctype_end(self.input.as_bytes(), start, true) else {
+                self.report(DiagnosticKind::Syntax, start, "Document type declaration is not closed".to_string());
+                return self.input.len();
+            };
+            if !self.stack.is_empty() || self.root.is_some() {
+                self.report(DiagnosticKind::Syntax, start, "Document type declaration after the start of the root element is ignored".to_string());
+            }
+            return end;
+        }
+
+        let end = tag_end(rest);
+        let body_end = match end {
+            Some(end) => end,
+            None => {
+                let body_end = rest[1..].find('<').map(|index| index + 1).unwrap_or(rest.len());
+                self.report(DiagnosticKind::Syntax, start, "Tag is not closed with >".to_string());
+                body_end
+            }
+        };
+        let next = start + end.map(|end| end + 1).unwrap_or(body_end);
+
+        if let Some(body) = rest[..body_end].strip_prefix("</") {
+            self.close_tag(body.trim(), start);
+        } else {
+            let body = &rest[1..body_end];
+            let (body, self_closing) = match body.strip_suffix('/') {
+                Some(body) => (body, true),
+                None => (body, false),
+            };
+            self.open_tag(body, start + 1, self_closing);
+        }
+
+        next
+    }
+
+    fn parse_tag(&mut self, body: &str, offset: usize) -> Option<Element> {
+        let name_end = body.find(|c: char| c.is_whitespace()).unwrap_or(body.len());
+        let name = &body[..name_end];
+        if !is_valid_name(name) {
+            self.report(DiagnosticKind::Syntax, offset, format!("Invalid tag name {:?}, tag is skipped", name));
+            return None;
+        }
+
+        let mut element = Element { name: name.to_string(), attributes: vec![], children: vec![] };
+        let mut cursor = name_end;
+
+        loop {
+            cursor += body[cursor..].len() - body[cursor..].trim_start().len();
+            if cursor >= body.len() { break; }
+
+            match parse_attribute(&body[cursor..], self.lenient.is_some()) {
+                Ok((attribute_name, attribute_value, length, fix)) => {
+                    if let Some(fix) = fix { self.report(DiagnosticKind::Warning, offset + cursor, fix); }
+                    element.attributes.push((attribute_name, attribute_value));
+                    cursor += length;
+                }
+                Err(message) => {
+                    self.report(DiagnosticKind::Syntax, offset + cursor, format!("{}, the rest of the tag is skipped", message));
+                    break;
+                }
+            }
+        }
+
+        Some(element)
+    }
+
+    fn open_tag(&mut self, body: &str, offset: usize, self_closing: bool) {
+        let Some(element) = self.parse_tag(body, offset) else { return };
+
+        if let Some(options) = self.lenient {
+            while self.stack.last().is_some_and(|(open, _)| options.closes(&open.name, &element.name)) {
+                let (open, open_offset) = self.stack.pop().unwrap();
+                self.close_unclosed(open, open_offset, &format!("before <{}>", element.name));
+            }
+
+            if !self_closing && options.is_void(&element.name) {
+                self.report(DiagnosticKind::Warning, offset - 1, format!("Element <{}> is closed automatically", element.name));
+                self.attach(element, offset - 1);
+                return;
+            }
+        }
+
+        if self_closing { self.attach(element, offset - 1); } else { self.stack.push((element, offset - 1)); }
+    }
+
+    fn close_tag(&mut self, name: &str, offset: usize) {
+        if !is_valid_name(name) {
+            self.report(DiagnosticKind::Syntax, offset, format!("Invalid tag name {:?}, tag is skipped", name));
+            return;
+        }
+
+        let Some((current, open_offset)) = self.stack.last() else {
+            self.report(DiagnosticKind::Structure, offset, format!("Close tag </{}> has no open tag and is ignored", name));
+            return;
+        };
+
+        if current.name != name {
+            if self.names_match(&current.name, name) {
+                let message = format!("Close tag </{}> matches <{}> ignoring case", name, current.name);
+                self.report(DiagnosticKind::Warning, offset, message);
+            } else if self.stack.iter().any(|(element, _)| self.names_match(&element.name, name)) {
+                // Elements opened after the matching one are closed here.
+                while self.stack.last().is_some_and(|(element, _)| !self.names_match(&element.name, name)) {
+                    let (element, open_offset) = self.stack.pop().unwrap();
+                    self.close_unclosed(element, open_offset, &format!("by </{}>", name));
+                }
+                let (current, _) = self.stack.last().unwrap();
+                if current.name != name {
+                    let message = format!("Close tag </{}> matches <{}> ignoring case", name, current.name);
+                    self.report(DiagnosticKind::Warning, offset, message);
+                }
+            } else {
+                let (line, column) = self.position(*open_offset);
+                let message = format!("Close tag </{}> does not match open tag <{}> at {}:{}", name, current.name, line, column);
+                self.report(DiagnosticKind::Structure, offset, message);
+            }
+        }
+
+        let (element, open_offset) = self.stack.pop().unwrap();
+        self.attach(element, open_offset);
+    }
+}
+
+// Offset of the '>' that ends the tag, quotes are skipped so values may contain '>'. Stops at the next '<'.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in tag.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(index),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+// Returns the attribute name, value, consumed length and, in lenient mode, the description of the fix that was applied.
+fn parse_attribute(text: &str, lenient: bool) -> Result<(String, String, usize, Option<String>), String> {
+    let name_end = text.find(|c: char| !is_name_char(c)).unwrap_or(text.len());
+    let name = &text[..name_end];
+    if !is_valid_name(name) { return Err(format!("Invalid attribute name {:?}", name)); }
+
+    let rest = &text[name_end..];
+    let Some(after_equals) = rest.trim_start().strip_prefix('=') else {
+        if lenient { return Ok((name.to_string(), String::new(), name_end, Some(format!("Attribute {} has no value, empty value is used", name)))); }
+        return Err(format!("Attribute {} has no value", name));
+    };
+    let value_part = after_equals.trim_start();
+
+    let Some(quote) = value_part.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+        let value_end = value_part.find(char::is_whitespace).unwrap_or(value_part.len());
+        if !lenient || value_end == 0 { return Err(format!("Value of attribute {} is not quoted", name)); }
+        let consumed = text.len() - value_part.len() + value_end;
+        return Ok((name.to_string(), value_part[..value_end].to_string(), consumed, Some(format!("Value of attribute {} is not quoted", name))));
+    };
+    let value_end = value_part[1..].find(quote).ok_or_else(|| format!("Value of attribute {} is not closed", name))?;
+    let value = value_part[1..value_end + 1].to_string();
+
+    let consumed = text.len() - value_part.len() + value_end + 2;
+    Ok((name.to_string(), value, consumed, None))
+}
@@ -0,0 +1,1224 @@
+use crate::borrowed::{self, parse_borrowed};
+use crate::check::Diagnostic;
+use crate::encoding::decode_bytes;
+use crate::tree::{parse_document, Element, Node};
+use crate::xsd::{local_name, restricted_type, SimpleType};
+use anyhow::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// RELAX NG validation with schemas in XML or compact syntax, by derivatives of the patterns.
+// Names are compared without namespaces, so `nsName` matches any name. Datatypes are the built-in `string` and `token`
+// and the XML Schema datatypes, with their facets as parameters.
+#[derive(Debug, Clone)]
+pub struct RelaxNg {
+    start: Arc<Pattern>,
+    defines: Vec<Arc<Pattern>>,
+}
+
+const RNG_NAMESPACE: &str = "http://relaxng.org/ns/structure/1.0";
+const XSD_DATATYPES: &str = "http://www.w3.org/2001/XMLSchema-datatypes";
+
+#[derive(Debug)]
+enum Pattern {
+    Empty,
+    NotAllowed,
+    Text,
+    Choice(Arc<Pattern>, Arc<Pattern>),
+    Interleave(Arc<Pattern>, Arc<Pattern>),
+    Group(Arc<Pattern>, Arc<Pattern>),
+    OneOrMore(Arc<Pattern>),
+    List(Arc<Pattern>),
+    Data { name: String, simple: SimpleType, except: Option<Arc<Pattern>> },
+    Value { simple: SimpleType, value: String },
+    Attribute(NameClass, Arc<Pattern>),
+    Element(NameClass, Arc<Pattern>),
+    Ref(usize),
+    // Content of an open element followed by the pattern after the element, only in derivatives.
+    After(Arc<Pattern>, Arc<Pattern>),
+}
+
+#[derive(Debug)]
+enum NameClass {
+    Name(String),
+    AnyName(Option<Box<NameClass>>),
+    Choice(Box<NameClass>, Box<NameClass>),
+}
+
+impl NameClass {
+    fn contains(&self, name: &str) -> bool {
+        match self {
+            NameClass::Name(expected) => expected == name,
+            NameClass::AnyName(except) => except.as_ref().is_none_or(|except| !except.contains(name)),
+            NameClass::Choice(first, second) => first.contains(name) || second.contains(name),
+        }
+    }
+
+    fn describe(&self, names: &mut BTreeSet<String>) {
+        match self {
+            NameClass::Name(name) => { names.insert(format!("<{}>", name)); }
+            NameClass::AnyName(_) => { names.insert("any element".to_string()); }
+            NameClass::Choice(first, second) => {
+                first.describe(names);
+                second.describe(names);
+            }
+        }
+    }
+}
+
+fn empty() -> Arc<Pattern> {
+    Arc::new(Pattern::Empty)
+}
+
+fn not_allowed() -> Arc<Pattern> {
+    Arc::new(Pattern::NotAllowed)
+}
+
+fn is_not_allowed(pattern: &Pattern) -> bool {
+    matches!(pattern, Pattern::NotAllowed)
+}
+
+// Structural equality of derivatives, schema patterns are shared and compared by address.
+fn same(first: &Pattern, second: &Pattern) -> bool {
+    if std::ptr::eq(first, second) { return true; }
+    match (first, second) {
+        (Pattern::Empty, Pattern::Empty) | (Pattern::NotAllowed, Pattern::NotAllowed) | (Pattern::Text, Pattern::Text) => true,
+        (Pattern::Choice(first_a, first_b), Pattern::Choice(second_a, second_b))
+        | (Pattern::Interleave(first_a, first_b), Pattern::Interleave(second_a, second_b))
+        | (Pattern::Group(first_a, first_b), Pattern::Group(second_a, second_b))
+        | (Pattern::After(first_a, first_b), Pattern::After(second_a, second_b)) => same(first_a, second_a) && same(first_b, second_b),
+        (Pattern::OneOrMore(first), Pattern::OneOrMore(second)) | (Pattern::List(first), Pattern::List(second)) => same(first, second),
+        (Pattern::Ref(first), Pattern::Ref(second)) => first == second,
+        _ => false,
+    }
+}
+
+fn alternatives<'p>(pattern: &'p Pattern, found: &mut Vec<&'p Pattern>) {
+    match pattern {
+        Pattern::Choice(first, second) => {
+            alternatives(first, found);
+            alternatives(second, found);
+        }
+        _ => found.push(pattern),
+    }
+}
+
+// Alternatives that are already there are dropped, otherwise ambiguous content doubles the derivative at every step.
+fn choice(first: Arc<Pattern>, second: Arc<Pattern>) -> Arc<Pattern> {
+    if is_not_allowed(&first) { return second; }
+    if is_not_allowed(&second) { return first; }
+    let mut found = vec![];
+    alternatives(&first, &mut found);
+    if found.iter().any(|alternative| same(alternative, &second)) { return first; }
+    Arc::new(Pattern::Choice(first, second))
+}
+
+fn group(first: Arc<Pattern>, second: Arc<Pattern>) -> Arc<Pattern> {
+    match (&*first, &*second) {
+        (Pattern::NotAllowed, _) | (_, Pattern::Empty) => first,
+        (_, Pattern::NotAllowed) | (Pattern::Empty, _) => second,
+        _ => Arc::new(Pattern::Group(first, second)),
+    }
+}
+
+fn interleave(first: Arc<Pattern>, second: Arc<Pattern>) -> Arc<Pattern> {
+    match (&*first, &*second) {
+        (Pattern::NotAllowed, _) | (_, Pattern::Empty) => first,
+        (_, Pattern::NotAllowed) | (Pattern::Empty, _) => second,
+        _ => Arc::new(Pattern::Interleave(first, second)),
+    }
+}
+
+fn after(first: Arc<Pattern>, second: Arc<Pattern>) -> Arc<Pattern> {
+    match (&*first, &*second) {
+        (Pattern::NotAllowed, _) => first,
+        (_, Pattern::NotAllowed) => second,
+        _ => Arc::new(Pattern::After(first, second)),
+    }
+}
+
+fn one_or_more(pattern: Arc<Pattern>) -> Arc<Pattern> {
+    if matches!(*pattern, Pattern::NotAllowed | Pattern::Empty) { return pattern; }
+    Arc::new(Pattern::OneOrMore(pattern))
+}
+
+fn apply_after(pattern: &Pattern, apply: &dyn Fn(Arc<Pattern>) -> Arc<Pattern>) -> Arc<Pattern> {
+    match pattern {
+        Pattern::After(first, second) => after(first.clone(), apply(second.clone())),
+        Pattern::Choice(first, second) => choice(apply_after(first, apply), apply_after(second, apply)),
+        _ => not_allowed(),
+    }
+}
+
+fn required_attribute<'e>(element: &'e Element, name: &str) -> Result<&'e str> {
+    element.attribute(name).ok_or_else(|| anyhow!("<{}> has no {} attribute", element.name, name))
+}
+
+fn datatype(library: &str, name: &str, params: Vec<(&str, &str)>) -> Result<SimpleType> {
+    match library {
+        "" if matches!(name, "string" | "token") && params.is_empty() => restricted_type(name, params),
+        "" => Err(anyhow!("Unknown datatype {}", name)),
+        XSD_DATATYPES => restricted_type(name, params),
+        _ => Err(anyhow!("Unsupported datatype library {}", library)),
+    }
+}
+
+fn name_class(element: &Element) -> Result<NameClass> {
+    match element.name.as_str() {
+        "name" => Ok(NameClass::Name(local_name(element.text().trim()).to_string())),
+        "anyName" | "nsName" => {
+            let except = element.elements().find(|child| child.name == "except").map(name_classes).transpose()?;
+            Ok(NameClass::AnyName(except.map(Box::new)))
+        }
+        "choice" => name_classes(element),
+        other => Err(anyhow!("Unsupported name class <{}>", other)),
+    }
+}
+
+fn name_classes(element: &Element) -> Result<NameClass> {
+    let mut combined = None;
+    for child in element.elements() {
+        let name_class = name_class(child)?;
+        combined = Some(match combined {
+            None => name_class,
+            Some(combined) => NameClass::Choice(Box::new(combined), Box::new(name_class)),
+        });
+    }
+    combined.ok_or_else(|| anyhow!("<{}> has no name classes", element.name))
+}
+
+// Prefixes bound to the RELAX NG namespace, the default namespace is assumed when it is not declared.
+fn declared_prefixes(element: &Element, inherited: &HashSet<String>) -> HashSet<String> {
+    let mut prefixes = inherited.clone();
+    for (name, value) in &element.attributes {
+        let prefix = if name == "xmlns" { "" } else if let Some(prefix) = name.strip_prefix("xmlns:") { prefix } else { continue };
+        if value == RNG_NAMESPACE { prefixes.insert(prefix.to_string()); } else { prefixes.remove(prefix); }
+    }
+    prefixes
+}
+
+// Keeps the RELAX NG elements without prefixes and drops annotations, resolves `href` against `base`
+// and gives `data` and `value` the inherited datatype library.
+fn normalize(element: &Element, prefixes: &HashSet<String>, library: &str, base: Option<&Path>) -> Option<Element> {
+    let prefixes = declared_prefixes(element, prefixes);
+    let (prefix, name) = element.name.rsplit_once(':').unwrap_or(("", &element.name));
+    if !prefixes.contains(prefix) { return None; }
+
+    let library = element.attribute("datatypeLibrary").unwrap_or(library);
+    let mut attributes: Vec<_> = element.attributes.iter().filter(|(name, _)| name != "xmlns" && !name.contains(':')).cloned().collect();
+    if matches!(name, "data" | "value") && element.attribute("type").is_some() && element.attribute("datatypeLibrary").is_none() {
+        attributes.push(("datatypeLibrary".to_string(), library.to_string()));
+    }
+    if let Some(base) = base && let Some((_, href)) = attributes.iter_mut().find(|(name, _)| name == "href") {
+        *href = base.join(&*href).to_string_lossy().into_owned();
+    }
+
+    let children = element.children.iter().filter_map(|child| match child {
+        Node::Element(child) => normalize(child, &prefixes, library, base).map(Node::Element),
+        Node::Text(text) => Some(Node::Text(text.clone())),
+        Node::Comment(_) => None,
+    }).collect();
+    Some(Element { name: name.to_string(), attributes, children })
+}
+
+fn parse_schema(schema: &str, compact: bool, base: Option<&Path>) -> Result<Element> {
+    let root = if compact { parse_compact(schema)? } else { parse_document(schema)?.root };
+    normalize(&root, &HashSet::from([String::new()]), "", base).ok_or_else(|| anyhow!("Root element <{}> is not in the RELAX NG namespace", root.name))
+}
+
+// Grammar content by kind, a name can have several definitions that are combined.
+#[derive(Default)]
+struct GrammarContent {
+    start: Vec<Element>,
+    defines: BTreeMap<String, Vec<Element>>,
+}
+
+impl GrammarContent {
+    fn append(&mut self, other: GrammarContent) {
+        self.start.extend(other.start);
+        for (name, definitions) in other.defines { self.defines.entry(name).or_default().extend(definitions); }
+    }
+}
+
+// Compiles normalized schema elements to patterns. Definitions are numbered, references are indexes,
+// `scopes` are the names of the enclosing grammars.
+#[derive(Default)]
+struct Loader {
+    defines: Vec<Arc<Pattern>>,
+    names: Vec<String>,
+    scopes: Vec<HashMap<String, usize>>,
+    files: Vec<PathBuf>,
+}
+
+impl Loader {
+    fn with_file<T>(&mut self, path: &Path, load: impl FnOnce(&mut Loader, Element) -> Result<T>) -> Result<T> {
+        if path.to_string_lossy().contains("://") { return Err(anyhow!("Only local schema files are supported, got {}", path.display())); }
+        let path = fs::canonicalize(path).with_context(|| format!("Error reading schema {}", path.display()))?;
+        if self.files.contains(&path) { return Err(anyhow!("Schema {} includes itself", path.display())); }
+
+        let bytes = fs::read(&path).with_context(|| format!("Error reading schema {}", path.display()))?;
+        let compact = path.extension().is_some_and(|extension| extension == "rnc");
+        let root = parse_schema(&decode_bytes(&bytes)?, compact, path.parent()).with_context(|| format!("Error parsing schema {}", path.display()))?;
+
+        self.files.push(path);
+        let result = load(self, root);
+        self.files.pop();
+        result
+    }
+
+    fn collect(&mut self, grammar: &Element, content: &mut GrammarContent) -> Result<()> {
+        for child in grammar.elements() {
+            match child.name.as_str() {
+                "start" => content.start.push(child.clone()),
+                "define" => content.defines.entry(required_attribute(child, "name")?.to_string()).or_default().push(child.clone()),
+                "div" => self.collect(child, content)?,
+                "include" => {
+                    let mut overrides = GrammarContent::default();
+                    self.collect(child, &mut overrides)?;
+                    let mut included = self.with_file(Path::new(required_attribute(child, "href")?), |loader, root| {
+                        if root.name != "grammar" { return Err(anyhow!("Included schema must be a <grammar>, got <{}>", root.name)); }
+                        let mut included = GrammarContent::default();
+                        loader.collect(&root, &mut included)?;
+                        Ok(included)
+                    })?;
+
+                    // Definitions inside `include` replace the included ones.
+                    if !overrides.start.is_empty() { included.start.clear(); }
+                    included.defines.retain(|name, _| !overrides.defines.contains_key(name));
+                    content.append(included);
+                    content.append(overrides);
+                }
+                other => return Err(anyhow!("Unexpected <{}> in <{}>", other, grammar.name)),
+            }
+        }
+        Ok(())
+    }
+
+    fn grammar(&mut self, grammar: &Element) -> Result<Arc<Pattern>> {
+        let mut content = GrammarContent::default();
+        self.collect(grammar, &mut content)?;
+
+        let mut scope = HashMap::new();
+        for name in content.defines.keys() {
+            scope.insert(name.clone(), self.defines.len());
+            self.defines.push(not_allowed());
+            self.names.push(name.clone());
+        }
+        self.scopes.push(scope);
+        let start = self.grammar_content(content);
+        self.scopes.pop();
+        start
+    }
+
+    fn grammar_content(&mut self, content: GrammarContent) -> Result<Arc<Pattern>> {
+        for (name, definitions) in &content.defines {
+            let index = self.scopes.last().unwrap()[name];
+            self.defines[index] = self.combine(name, definitions)?;
+        }
+        if content.start.is_empty() { return Err(anyhow!("Grammar has no <start>")); }
+        self.combine("start", &content.start)
+    }
+
+    // Definitions with the same name are combined by their `combine` attribute, at most one of them may omit it.
+    fn combine(&mut self, name: &str, definitions: &[Element]) -> Result<Arc<Pattern>> {
+        if definitions.iter().filter(|definition| definition.attribute("combine").is_none()).count() > 1 {
+            return Err(anyhow!("Pattern {} is defined twice without a combine attribute", name));
+        }
+        let interleaved = definitions.iter().any(|definition| definition.attribute("combine") == Some("interleave"));
+
+        let mut combined: Option<Arc<Pattern>> = None;
+        for definition in definitions {
+            let pattern = self.sequence(definition.elements())?;
+            combined = Some(match combined {
+                None => pattern,
+                Some(combined) if interleaved => interleave(combined, pattern),
+                Some(combined) => choice(combined, pattern),
+            });
+        }
+        Ok(combined.unwrap_or_else(not_allowed))
+    }
+
+    fn sequence<'e>(&mut self, elements: impl Iterator<Item = &'e Element>) -> Result<Arc<Pattern>> {
+        let mut sequence = empty();
+        for element in elements { sequence = group(sequence, self.pattern(element)?); }
+        Ok(sequence)
+    }
+
+    fn alternatives(&mut self, element: &Element, combine: fn(Arc<Pattern>, Arc<Pattern>) -> Arc<Pattern>) -> Result<Arc<Pattern>> {
+        let mut combined = None;
+        for child in element.elements() {
+            let pattern = self.pattern(child)?;
+            combined = Some(match combined {
+                None => pattern,
+                Some(combined) => combine(combined, pattern),
+            });
+        }
+        combined.ok_or_else(|| anyhow!("<{}> has no patterns", element.name))
+    }
+
+    fn pattern(&mut self, element: &Element) -> Result<Arc<Pattern>> {
+        Ok(match element.name.as_str() {
+            "element" | "attribute" => {
+                let mut children = element.elements();
+                let name_class = match element.attribute("name") {
+                    Some(name) => NameClass::Name(local_name(name.trim()).to_string()),
+                    None => name_class(children.next().ok_or_else(|| anyhow!("<{}> has no name", element.name))?)?,
+                };
+                let content: Vec<_> = children.collect();
+                if element.name == "element" { Arc::new(Pattern::Element(name_class, self.sequence(content.into_iter())?)) }
+                else if content.is_empty() { Arc::new(Pattern::Attribute(name_class, Arc::new(Pattern::Text))) }
+                else { Arc::new(Pattern::Attribute(name_class, self.sequence(content.into_iter())?)) }
+            }
+            "group" => self.sequence(element.elements())?,
+            "choice" => self.alternatives(element, choice)?,
+            "interleave" => self.alternatives(element, interleave)?,
+            "optional" => choice(self.sequence(element.elements())?, empty()),
+            "zeroOrMore" => choice(one_or_more(self.sequence(element.elements())?), empty()),
+            "oneOrMore" => one_or_more(self.sequence(element.elements())?),
+            "mixed" => interleave(self.sequence(element.elements())?, Arc::new(Pattern::Text)),
+            "list" => Arc::new(Pattern::List(self.sequence(element.elements())?)),
+            "empty" => empty(),
+            "text" => Arc::new(Pattern::Text),
+            "notAllowed" => not_allowed(),
+            "ref" | "parentRef" => {
+                let name = required_attribute(element, "name")?;
+                let depth = if element.name == "ref" { 1 } else { 2 };
+                let scope = self.scopes.len().checked_sub(depth).map(|index| &self.scopes[index]);
+                let index = scope.and_then(|scope| scope.get(name)).ok_or_else(|| anyhow!("Reference to undefined pattern {}", name))?;
+                Arc::new(Pattern::Ref(*index))
+            }
+            "data" => {
+                let name = required_attribute(element, "type")?;
+                let params = element.elements().filter(|child| child.name == "param")
+                    .map(|param| Ok((required_attribute(param, "name")?, param.text())))
+                    .collect::<Result<Vec<_>>>()?;
+                let params = params.iter().map(|(name, value)| (*name, value.as_str())).collect();
+                let simple = datatype(element.attribute("datatypeLibrary").unwrap_or(""), name, params)?;
+                let except = element.elements().find(|child| child.name == "except").map(|except| self.alternatives(except, choice)).transpose()?;
+                Arc::new(Pattern::Data { name: name.to_string(), simple, except })
+            }
+            "value" => {
+                let simple = match element.attribute("type") {
+                    Some(name) => datatype(element.attribute("datatypeLibrary").unwrap_or(""), name, vec![])?,
+                    None => datatype("", "token", vec![])?,
+                };
+                Arc::new(Pattern::Value { simple, value: element.text() })
+            }
+            "grammar" => self.grammar(element)?,
+            "externalRef" => self.with_file(Path::new(required_attribute(element, "href")?), |loader, root| loader.pattern(&root))?,
+            other => return Err(anyhow!("Unsupported pattern <{}>", other)),
+        })
+    }
+
+    // A reference outside of an element must not lead back to its own definition.
+    fn finish(self, start: Arc<Pattern>) -> Result<RelaxNg> {
+        for index in 0..self.defines.len() {
+            let mut stack = vec![index];
+            let mut seen = HashSet::new();
+            while let Some(current) = stack.pop() {
+                let mut references = vec![];
+                unguarded_references(&self.defines[current], &mut references);
+                for reference in references {
+                    if reference == index { return Err(anyhow!("Pattern {} refers to itself outside of an element", self.names[index])); }
+                    if seen.insert(reference) { stack.push(reference); }
+                }
+            }
+        }
+        Ok(RelaxNg { start, defines: self.defines })
+    }
+}
+
+fn unguarded_references(pattern: &Pattern, references: &mut Vec<usize>) {
+    match pattern {
+        Pattern::Choice(first, second) | Pattern::Interleave(first, second) | Pattern::Group(first, second) | Pattern::After(first, second) => {
+            unguarded_references(first, references);
+            unguarded_references(second, references);
+        }
+        Pattern::OneOrMore(pattern) | Pattern::List(pattern) | Pattern::Attribute(_, pattern) | Pattern::Data { except: Some(pattern), .. } => {
+            unguarded_references(pattern, references);
+        }
+        Pattern::Ref(index) => references.push(*index),
+        _ => {}
+    }
+}
+
+// Tokens of the compact syntax, comments and annotations are skipped.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    // Identifier or keyword.
+    Name(String),
+    // Identifier written with a backslash, never a keyword.
+    Escaped(String),
+    PrefixedName(String),
+    // `prefix:*`
+    NsName(String),
+    Literal(String),
+    Symbol(&'static str),
+    End,
+}
+
+const KEYWORDS: &[&str] = &[
+    "attribute", "default", "datatypes", "div", "element", "empty", "external", "grammar", "include",
+    "inherit", "list", "mixed", "namespace", "notAllowed", "parent", "start", "string", "text", "token",
+];
+const SYMBOLS: &[&str] = &["|=", "&=", "=", "{", "}", "(", ")", ",", "&", "|", "?", "*", "+", "-", "~"];
+
+fn syntax_error(input: &str, offset: usize, message: String) -> Error {
+    anyhow!("Syntax error at line {}: {}", input[..offset].matches('\n').count() + 1, message)
+}
+
+fn name_length(text: &str) -> usize {
+    let is_name_char = |(index, c): &(usize, char)| c.is_alphabetic() || *c == '_' || (*index > 0 && (c.is_numeric() || matches!(c, '-' | '.')));
+    text.char_indices().find(|item| !is_name_char(item)).map_or(text.len(), |(index, _)| index)
+}
+
+// Length of an annotation in brackets, brackets in literals do not count.
+fn annotation_length(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => {
+                depth -= 1;
+                if depth == 0 { return Some(index + 1); }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens: Vec<(Token, usize)> = vec![];
+    let mut offset = 0;
+
+    while let Some(c) = input[offset..].chars().next() {
+        let rest = &input[offset..];
+        if c.is_whitespace() {
+            offset += c.len_utf8();
+            continue;
+        }
+        if c == '#' {
+            offset += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        // Follow annotations are `>>` with an element name and an annotation.
+        let annotation = match rest.strip_prefix(">>") {
+            Some(follow) => {
+                let follow = follow.trim_start();
+                let name = name_length(follow);
+                let name = name + follow[name..].strip_prefix(':').map_or(0, |local| 1 + name_length(local));
+                follow[name..].trim_start()
+            }
+            None => rest,
+        };
+        if annotation.starts_with('[') {
+            let length = annotation_length(annotation).ok_or_else(|| syntax_error(input, offset, "unclosed annotation".to_string()))?;
+            offset = input.len() - annotation.len() + length;
+            continue;
+        }
+        if annotation.len() != rest.len() { return Err(syntax_error(input, offset, "expected an annotation after >>".to_string())); }
+
+        let (token, length) = if c == '"' || c == '\'' {
+            let triple = c.to_string().repeat(3);
+            let quote = if rest.starts_with(&triple) { triple } else { c.to_string() };
+            let end = rest[quote.len()..].find(&quote).ok_or_else(|| syntax_error(input, offset, "unclosed literal".to_string()))?;
+            (Token::Literal(rest[quote.len()..quote.len() + end].to_string()), end + 2 * quote.len())
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            (Token::Symbol(symbol), symbol.len())
+        } else {
+            let escaped = c == '\\';
+            let start = usize::from(escaped);
+            let length = name_length(&rest[start..]);
+            if length == 0 { return Err(syntax_error(input, offset, format!("unexpected character {:?}", c))); }
+            let name = rest[start..start + length].to_string();
+            let after = &rest[start + length..];
+            let local = after.strip_prefix(':').map_or(0, name_length);
+
+            if escaped { (Token::Escaped(name), start + length) }
+            else if after.starts_with(":*") { (Token::NsName(name), length + 2) }
+            else if local > 0 { (Token::PrefixedName(rest[..length + 1 + local].to_string()), length + 1 + local) }
+            else { (Token::Name(name), length) }
+        };
+
+        // Literals joined with `~` are one literal.
+        if let Token::Literal(value) = &token && let [.., (Token::Literal(previous), _), (Token::Symbol("~"), _)] = tokens.as_mut_slice() {
+            previous.push_str(value);
+            tokens.pop();
+        } else {
+            tokens.push((token, offset));
+        }
+        offset += length;
+    }
+
+    tokens.push((Token::End, input.len()));
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Name(name) | Token::PrefixedName(name) => name.clone(),
+        Token::Escaped(name) => format!("\\{}", name),
+        Token::NsName(prefix) => format!("{}:*", prefix),
+        Token::Literal(value) => format!("{:?}", value),
+        Token::Symbol(symbol) => format!("`{}`", symbol),
+        Token::End => "end of schema".to_string(),
+    }
+}
+
+fn schema_element(name: &str, attributes: &[(&str, &str)], children: Vec<Element>) -> Element {
+    Element {
+        name: name.to_string(),
+        attributes: attributes.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+        children: children.into_iter().map(Node::Element).collect(),
+    }
+}
+
+fn text_element(name: &str, attributes: &[(&str, &str)], text: &str) -> Element {
+    let mut element = schema_element(name, attributes, vec![]);
+    element.children.push(Node::Text(text.to_string()));
+    element
+}
+
+// Translates the compact syntax to the elements of the XML syntax. Namespace declarations are read and ignored.
+struct Compact<'s> {
+    input: &'s str,
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    datatypes: HashMap<String, String>,
+}
+
+fn parse_compact(input: &str) -> Result<Element> {
+    let datatypes = HashMap::from([("xsd".to_string(), XSD_DATATYPES.to_string())]);
+    let mut compact = Compact { input, tokens: tokenize(input)?, position: 0, datatypes };
+    let mut root = compact.top_level()?;
+    root.attributes.push(("xmlns".to_string(), RNG_NAMESPACE.to_string()));
+    Ok(root)
+}
+
+impl Compact<'_> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].0
+    }
+
+    fn peek_second(&self) -> &Token {
+        &self.tokens[(self.position + 1).min(self.tokens.len() - 1)].0
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        if self.position < self.tokens.len() - 1 { self.position += 1; }
+        token
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Token::Symbol(found) if *found == symbol)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Name(name) if name == keyword)
+    }
+
+    fn error(&self, expected: &str) -> Error {
+        syntax_error(self.input, self.tokens[self.position].1, format!("expected {}, found {}", expected, describe(self.peek())))
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<()> {
+        if !self.is_symbol(symbol) { return Err(self.error(&format!("`{}`", symbol))); }
+        self.next();
+        Ok(())
+    }
+
+    fn literal(&mut self) -> Result<String> {
+        match self.peek() {
+            Token::Literal(_) => match self.next() { Token::Literal(value) => Ok(value), _ => unreachable!() },
+            _ => Err(self.error("a literal")),
+        }
+    }
+
+    // Identifier or keyword, where any name is allowed.
+    fn name(&mut self) -> Result<String> {
+        match self.peek() {
+            Token::Name(_) | Token::Escaped(_) => match self.next() { Token::Name(name) | Token::Escaped(name) => Ok(name), _ => unreachable!() },
+            _ => Err(self.error("a name")),
+        }
+    }
+
+    fn skip_inherit(&mut self) -> Result<()> {
+        if self.is_keyword("inherit") {
+            self.next();
+            self.expect("=")?;
+            self.name()?;
+        }
+        Ok(())
+    }
+
+    fn top_level(&mut self) -> Result<Element> {
+        while self.declaration()? {}
+        let is_grammar = self.is_keyword("div") || self.is_keyword("include") || *self.peek() == Token::End
+            || matches!(self.peek_second(), Token::Symbol("=" | "|=" | "&="));
+        let root = if is_grammar { schema_element("grammar", &[], self.grammar_content()?) } else { self.pattern()? };
+        if *self.peek() != Token::End { return Err(self.error("end of schema")); }
+        Ok(root)
+    }
+
+    fn declaration(&mut self) -> Result<bool> {
+        if self.is_keyword("default") {
+            self.next();
+            if !self.is_keyword("namespace") { return Err(self.error("namespace")); }
+        } else if !self.is_keyword("namespace") && !self.is_keyword("datatypes") {
+            return Ok(false);
+        }
+
+        let datatypes = self.next() == Token::Name("datatypes".to_string());
+        let prefix = if self.is_symbol("=") { String::new() } else { self.name()? };
+        self.expect("=")?;
+        let uri = if self.is_keyword("inherit") { self.next(); String::new() } else { self.literal()? };
+        if datatypes { self.datatypes.insert(prefix, uri); }
+        Ok(true)
+    }
+
+    fn grammar_content(&mut self) -> Result<Vec<Element>> {
+        let mut content = vec![];
+        loop {
+            if self.is_keyword("div") {
+                self.next();
+                self.expect("{")?;
+                let children = self.grammar_content()?;
+                self.expect("}")?;
+                content.push(schema_element("div", &[], children));
+            } else if self.is_keyword("include") {
+                self.next();
+                let href = self.literal()?;
+                self.skip_inherit()?;
+                let mut overrides = vec![];
+                if self.is_symbol("{") {
+                    self.next();
+                    overrides = self.grammar_content()?;
+                    self.expect("}")?;
+                }
+                content.push(schema_element("include", &[("href", &href)], overrides));
+            } else if matches!(self.peek(), Token::Name(_) | Token::Escaped(_)) && matches!(self.peek_second(), Token::Symbol("=" | "|=" | "&=")) {
+                let start = self.is_keyword("start");
+                let name = self.name()?;
+                let combine = match self.next() {
+                    Token::Symbol("|=") => Some("choice"),
+                    Token::Symbol("&=") => Some("interleave"),
+                    _ => None,
+                };
+                let pattern = self.pattern()?;
+
+                let mut attributes = vec![];
+                if !start { attributes.push(("name", name.as_str())); }
+                if let Some(combine) = combine { attributes.push(("combine", combine)); }
+                content.push(schema_element(if start { "start" } else { "define" }, &attributes, vec![pattern]));
+            } else {
+                return Ok(content);
+            }
+        }
+    }
+
+    // Operators can not be mixed without parentheses.
+    fn pattern(&mut self) -> Result<Element> {
+        let first = self.particle()?;
+        let operator = match self.peek() {
+            Token::Symbol(operator @ ("," | "&" | "|")) => *operator,
+            _ => return Ok(first),
+        };
+
+        let mut children = vec![first];
+        while self.is_symbol(operator) {
+            self.next();
+            children.push(self.particle()?);
+        }
+        if matches!(self.peek(), Token::Symbol("," | "&" | "|")) { return Err(self.error(&format!("`{}`", operator))); }
+        let name = match operator {
+            "," => "group",
+            "&" => "interleave",
+            _ => "choice",
+        };
+        Ok(schema_element(name, &[], children))
+    }
+
+    fn particle(&mut self) -> Result<Element> {
+        let primary = self.primary()?;
+        let name = match self.peek() {
+            Token::Symbol("?") => "optional",
+            Token::Symbol("*") => "zeroOrMore",
+            Token::Symbol("+") => "oneOrMore",
+            _ => return Ok(primary),
+        };
+        self.next();
+        Ok(schema_element(name, &[], vec![primary]))
+    }
+
+    fn primary(&mut self) -> Result<Element> {
+        let keyword = match self.peek() {
+            Token::Name(name) if KEYWORDS.contains(&name.as_str()) => name.clone(),
+            Token::Name(_) | Token::Escaped(_) => return Ok(schema_element("ref", &[("name", &self.name()?)], vec![])),
+            Token::PrefixedName(name) => {
+                let (prefix, local) = name.split_once(':').unwrap();
+                let library = self.datatypes.get(prefix).ok_or_else(|| self.error("a declared datatypes prefix"))?.clone();
+                let local = local.to_string();
+                self.next();
+                return self.datatype(&library, &local);
+            }
+            Token::Literal(_) => return Ok(text_element("value", &[], &self.literal()?)),
+            Token::Symbol("(") => {
+                self.next();
+                let pattern = self.pattern()?;
+                self.expect(")")?;
+                return Ok(pattern);
+            }
+            _ => return Err(self.error("a pattern")),
+        };
+
+        match keyword.as_str() {
+            "element" | "attribute" => {
+                self.next();
+                let name_class = self.name_class()?;
+                self.expect("{")?;
+                let content = self.pattern()?;
+                self.expect("}")?;
+                Ok(schema_element(&keyword, &[], vec![name_class, content]))
+            }
+            "list" | "mixed" => {
+                self.next();
+                self.expect("{")?;
+                let content = self.pattern()?;
+                self.expect("}")?;
+                Ok(schema_element(&keyword, &[], vec![content]))
+            }
+            "grammar" => {
+                self.next();
+                self.expect("{")?;
+                let content = self.grammar_content()?;
+                self.expect("}")?;
+                Ok(schema_element("grammar", &[], content))
+            }
+            "empty" | "text" | "notAllowed" => {
+                self.next();
+                Ok(schema_element(&keyword, &[], vec![]))
+            }
+            "parent" => {
+                self.next();
+                Ok(schema_element("parentRef", &[("name", &self.name()?)], vec![]))
+            }
+            "external" => {
+                self.next();
+                let href = self.literal()?;
+                self.skip_inherit()?;
+                Ok(schema_element("externalRef", &[("href", &href)], vec![]))
+            }
+            "string" | "token" => {
+                self.next();
+                self.datatype("", &keyword)
+            }
+            _ => Err(self.error("a pattern")),
+        }
+    }
+
+    // A datatype name followed by a literal is a value, otherwise data with optional parameters and except pattern.
+    fn datatype(&mut self, library: &str, name: &str) -> Result<Element> {
+        let attributes = [("type", name), ("datatypeLibrary", library)];
+        if let Token::Literal(_) = self.peek() { return Ok(text_element("value", &attributes, &self.literal()?)); }
+
+        let mut children = vec![];
+        if self.is_symbol("{") {
+            self.next();
+            while !self.is_symbol("}") {
+                let param = self.name()?;
+                self.expect("=")?;
+                children.push(text_element("param", &[("name", &param)], &self.literal()?));
+            }
+            self.next();
+        }
+        if self.is_symbol("-") {
+            self.next();
+            children.push(schema_element("except", &[], vec![self.primary()?]));
+        }
+        Ok(schema_element("data", &attributes, children))
+    }
+
+    fn name_class(&mut self) -> Result<Element> {
+        let first = self.name_class_primary()?;
+        if !self.is_symbol("|") { return Ok(first); }
+
+        let mut children = vec![first];
+        while self.is_symbol("|") {
+            self.next();
+            children.push(self.name_class_primary()?);
+        }
+        Ok(schema_element("choice", &[], children))
+    }
+
+    fn name_class_primary(&mut self) -> Result<Element> {
+        let name = match self.peek() {
+            Token::Name(_) | Token::Escaped(_) => self.name()?,
+            Token::PrefixedName(name) => {
+                let name = name.clone();
+                self.next();
+                name
+            }
+            Token::NsName(_) | Token::Symbol("*") => {
+                let name = if matches!(self.next(), Token::NsName(_)) { "nsName" } else { "anyName" };
+                if !self.is_symbol("-") { return Ok(schema_element(name, &[], vec![])); }
+                self.next();
+                let except = schema_element("except", &[], vec![self.name_class_primary()?]);
+                return Ok(schema_element(name, &[], vec![except]));
+            }
+            Token::Symbol("(") => {
+                self.next();
+                let name_class = self.name_class()?;
+                self.expect(")")?;
+                return Ok(name_class);
+            }
+            _ => return Err(self.error("a name")),
+        };
+        Ok(text_element("name", &[], &name))
+    }
+}
+
+// What a pattern accepts next: element names and text or datatypes, with the messages of datatypes that reject a value.
+#[derive(Default)]
+struct Expected {
+    elements: BTreeSet<String>,
+    values: BTreeSet<String>,
+    errors: BTreeSet<String>,
+}
+
+impl Expected {
+    // The message of the datatype when it is the only value that was expected, like the facet that failed.
+    fn invalid_value(self, value: &str) -> String {
+        match (self.values.len(), self.errors.len()) {
+            (1, 1) => self.errors.into_iter().next().unwrap(),
+            _ => format!("{:?} is not valid, expected {}", value, join(self.values)),
+        }
+    }
+}
+
+fn join(items: BTreeSet<String>) -> String {
+    items.into_iter().collect::<Vec<_>>().join(", ")
+}
+
+impl RelaxNg {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<RelaxNg> {
+        let mut loader = Loader::default();
+        let start = loader.with_file(path.as_ref(), |loader, root| loader.pattern(&root))?;
+        loader.finish(start)
+    }
+
+    // Schemas that start with `<` are in XML syntax, others in compact syntax. Included schemas are resolved from the current directory.
+    pub fn parse(schema: &str) -> Result<RelaxNg> {
+        let root = parse_schema(schema, !schema.trim_start().starts_with('<'), None)?;
+        let mut loader = Loader::default();
+        let start = loader.pattern(&root)?;
+        loader.finish(start)
+    }
+
+    pub fn validate(&self, input: &str) -> Result<Vec<Diagnostic>> {
+        Ok(self.validate_document(&parse_borrowed(input)?, input))
+    }
+
+    // Locations are the offsets in `document`, which must be parsed from `input`.
+    pub fn validate_document(&self, document: &borrowed::Document, input: &str) -> Vec<Diagnostic> {
+        let mut validator = Validator { schema: self, input, diagnostics: vec![] };
+        validator.element(&self.start, &document.root);
+        validator.diagnostics
+    }
+
+    fn nullable(&self, pattern: &Pattern) -> bool {
+        match pattern {
+            Pattern::Empty | Pattern::Text => true,
+            Pattern::Group(first, second) | Pattern::Interleave(first, second) => self.nullable(first) && self.nullable(second),
+            Pattern::Choice(first, second) => self.nullable(first) || self.nullable(second),
+            Pattern::OneOrMore(pattern) => self.nullable(pattern),
+            Pattern::Ref(index) => self.nullable(&self.defines[*index]),
+            _ => false,
+        }
+    }
+
+    fn start_tag_open(&self, pattern: &Arc<Pattern>, name: &str) -> Arc<Pattern> {
+        match &**pattern {
+            Pattern::Choice(first, second) => choice(self.start_tag_open(first, name), self.start_tag_open(second, name)),
+            Pattern::Element(name_class, content) if name_class.contains(name) => after(content.clone(), empty()),
+            Pattern::Interleave(first, second) => choice(
+                apply_after(&self.start_tag_open(first, name), &|open| interleave(open, second.clone())),
+                apply_after(&self.start_tag_open(second, name), &|open| interleave(first.clone(), open)),
+            ),
+            Pattern::OneOrMore(repeated) => {
+                apply_after(&self.start_tag_open(repeated, name), &|open| group(open, choice(pattern.clone(), empty())))
+            }
+            Pattern::Group(first, second) => {
+                let open = apply_after(&self.start_tag_open(first, name), &|open| group(open, second.clone()));
+                if self.nullable(first) { choice(open, self.start_tag_open(second, name)) } else { open }
+            }
+            Pattern::After(first, second) => apply_after(&self.start_tag_open(first, name), &|open| after(open, second.clone())),
+            Pattern::Ref(index) => self.start_tag_open(&self.defines[*index], name),
+            _ => not_allowed(),
+        }
+    }
+
+    // Without a value any value of the attribute is accepted.
+    fn attribute(&self, pattern: &Arc<Pattern>, name: &str, value: Option<&str>) -> Arc<Pattern> {
+        match &**pattern {
+            Pattern::After(first, second) => after(self.attribute(first, name, value), second.clone()),
+            Pattern::Choice(first, second) => choice(self.attribute(first, name, value), self.attribute(second, name, value)),
+            Pattern::Group(first, second) => choice(
+                group(self.attribute(first, name, value), second.clone()),
+                group(first.clone(), self.attribute(second, name, value)),
+            ),
+            Pattern::Interleave(first, second) => choice(
+                interleave(self.attribute(first, name, value), second.clone()),
+                interleave(first.clone(), self.attribute(second, name, value)),
+            ),
+            Pattern::OneOrMore(repeated) => group(self.attribute(repeated, name, value), choice(pattern.clone(), empty())),
+            Pattern::Attribute(name_class, content) if name_class.contains(name) => {
+                let matches = value.is_none_or(|value| (self.nullable(content) && value.trim().is_empty()) || self.nullable(&self.text(content, Some(value))));
+                if matches { empty() } else { not_allowed() }
+            }
+            Pattern::Ref(index) => self.attribute(&self.defines[*index], name, value),
+            _ => not_allowed(),
+        }
+    }
+
+    // Attributes that are still required are not allowed, or with `lenient` taken as missing.
+    fn start_tag_close(&self, pattern: &Arc<Pattern>, lenient: bool) -> Arc<Pattern> {
+        match &**pattern {
+            Pattern::After(first, second) => after(self.start_tag_close(first, lenient), second.clone()),
+            Pattern::Choice(first, second) => choice(self.start_tag_close(first, lenient), self.start_tag_close(second, lenient)),
+            Pattern::Group(first, second) => group(self.start_tag_close(first, lenient), self.start_tag_close(second, lenient)),
+            Pattern::Interleave(first, second) => interleave(self.start_tag_close(first, lenient), self.start_tag_close(second, lenient)),
+            Pattern::OneOrMore(repeated) => one_or_more(self.start_tag_close(repeated, lenient)),
+            Pattern::Attribute(..) if lenient => empty(),
+            Pattern::Attribute(..) => not_allowed(),
+            Pattern::Ref(index) => self.start_tag_close(&self.defines[*index], lenient),
+            _ => pattern.clone(),
+        }
+    }
+
+    // Without a text any value of data is accepted.
+    fn text(&self, pattern: &Arc<Pattern>, text: Option<&str>) -> Arc<Pattern> {
+        match &**pattern {
+            Pattern::Choice(first, second) => choice(self.text(first, text), self.text(second, text)),
+            Pattern::Interleave(first, second) => choice(
+                interleave(self.text(first, text), second.clone()),
+                interleave(first.clone(), self.text(second, text)),
+            ),
+            Pattern::Group(first, second) => {
+                let matched = group(self.text(first, text), second.clone());
+                if self.nullable(first) { choice(matched, self.text(second, text)) } else { matched }
+            }
+            Pattern::After(first, second) => after(self.text(first, text), second.clone()),
+            Pattern::OneOrMore(repeated) => group(self.text(repeated, text), choice(pattern.clone(), empty())),
+            Pattern::Text => pattern.clone(),
+            Pattern::Value { .. } | Pattern::Data { .. } | Pattern::List(_) if text.is_none() => empty(),
+            Pattern::Value { simple, value } if text.is_some_and(|text| simple.normalize(value) == simple.normalize(text)) => empty(),
+            Pattern::Data { simple, except, .. } => {
+                let text = text.unwrap();
+                let excluded = except.as_ref().is_some_and(|except| self.nullable(&self.text(except, Some(text))));
+                if simple.check(text).is_ok() && !excluded { empty() } else { not_allowed() }
+            }
+            Pattern::List(items) => {
+                let rest = text.unwrap().split_whitespace().fold(items.clone(), |rest, item| self.text(&rest, Some(item)));
+                if self.nullable(&rest) { empty() } else { not_allowed() }
+            }
+            Pattern::Ref(index) => self.text(&self.defines[*index], text),
+            _ => not_allowed(),
+        }
+    }
+
+    // With `lenient` an element that is not complete is closed anyway.
+    fn end_tag(&self, pattern: &Arc<Pattern>, lenient: bool) -> Arc<Pattern> {
+        match &**pattern {
+            Pattern::Choice(first, second) => choice(self.end_tag(first, lenient), self.end_tag(second, lenient)),
+            Pattern::After(first, second) if lenient || self.nullable(first) => second.clone(),
+            _ => not_allowed(),
+        }
+    }
+
+    fn expected(&self, pattern: &Pattern, expected: &mut Expected) {
+        match pattern {
+            Pattern::Choice(first, second) | Pattern::Interleave(first, second) => {
+                self.expected(first, expected);
+                self.expected(second, expected);
+            }
+            Pattern::Group(first, second) => {
+                self.expected(first, expected);
+                if self.nullable(first) { self.expected(second, expected); }
+            }
+            Pattern::OneOrMore(pattern) | Pattern::List(pattern) | Pattern::After(pattern, _) => self.expected(pattern, expected),
+            Pattern::Element(name_class, _) => name_class.describe(&mut expected.elements),
+            Pattern::Text => { expected.values.insert("text".to_string()); }
+            Pattern::Data { name, .. } => { expected.values.insert(name.clone()); }
+            Pattern::Value { value, .. } => { expected.values.insert(format!("{:?}", value)); }
+            Pattern::Ref(index) => self.expected(&self.defines[*index], expected),
+            _ => {}
+        }
+    }
+
+    fn attribute_values(&self, pattern: &Pattern, name: &str, value: &str, expected: &mut Expected) {
+        match pattern {
+            Pattern::Choice(first, second) | Pattern::Group(first, second) | Pattern::Interleave(first, second) => {
+                self.attribute_values(first, name, value, expected);
+                self.attribute_values(second, name, value, expected);
+            }
+            Pattern::OneOrMore(pattern) | Pattern::After(pattern, _) => self.attribute_values(pattern, name, value, expected),
+            Pattern::Attribute(name_class, content) if name_class.contains(name) => {
+                self.expected(content, expected);
+                self.data_errors(content, value, &mut expected.errors);
+            }
+            Pattern::Ref(index) => self.attribute_values(&self.defines[*index], name, value, expected),
+            _ => {}
+        }
+    }
+
+    // Messages of the datatypes that reject `text` where `expected` looks for values. Items of lists are not checked one by one.
+    fn data_errors(&self, pattern: &Pattern, text: &str, errors: &mut BTreeSet<String>) {
+        match pattern {
+            Pattern::Choice(first, second) | Pattern::Interleave(first, second) => {
+                self.data_errors(first, text, errors);
+                self.data_errors(second, text, errors);
+            }
+            Pattern::Group(first, second) => {
+                self.data_errors(first, text, errors);
+                if self.nullable(first) { self.data_errors(second, text, errors); }
+            }
+            Pattern::OneOrMore(pattern) | Pattern::After(pattern, _) => self.data_errors(pattern, text, errors),
+            Pattern::Data { simple, .. } => { if let Err(error) = simple.check(text) { errors.insert(error.to_string()); } }
+            Pattern::Ref(index) => self.data_errors(&self.defines[*index], text, errors),
+            _ => {}
+        }
+    }
+
+    // Names of attributes that every alternative requires.
+    fn required_attributes(&self, pattern: &Pattern) -> BTreeSet<String> {
+        match pattern {
+            Pattern::Group(first, second) | Pattern::Interleave(first, second) => &self.required_attributes(first) | &self.required_attributes(second),
+            Pattern::Choice(first, second) => &self.required_attributes(first) & &self.required_attributes(second),
+            Pattern::OneOrMore(pattern) | Pattern::After(pattern, _) => self.required_attributes(pattern),
+            Pattern::Attribute(NameClass::Name(name), _) => BTreeSet::from([name.clone()]),
+            Pattern::Ref(index) => self.required_attributes(&self.defines[*index]),
+            _ => BTreeSet::new(),
+        }
+    }
+}
+
+struct Validator<'s, 'i> {
+    schema: &'s RelaxNg,
+    input: &'i str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_, '_> {
+    fn report(&mut self, offset: usize, message: String) {
+        self.diagnostics.push(Diagnostic::validity(self.input, offset, message));
+    }
+
+    // Returns the pattern after `element`. An element that is not allowed is reported and skipped with its content.
+    fn element(&mut self, pattern: &Arc<Pattern>, element: &borrowed::Element) -> Arc<Pattern> {
+        let schema = self.schema;
+        let mut current = schema.start_tag_open(pattern, local_name(&element.name));
+        if is_not_allowed(&current) {
+            let mut expected = Expected::default();
+            schema.expected(pattern, &mut expected);
+            let message = match expected.elements.is_empty() {
+                true => format!("Element <{}> is not expected here", element.name),
+                false => format!("Element <{}> is not expected here, expected {}", element.name, join(expected.elements)),
+            };
+            self.report(element.offset, message);
+            return pattern.clone();
+        }
+
+        for (name, value, offset) in element.attributes_with_offsets() {
+            if name == "xmlns" || name.starts_with("xmlns:") { continue; }
+            let next = schema.attribute(&current, local_name(name), Some(value));
+            if !is_not_allowed(&next) {
+                current = next;
+                continue;
+            }
+
+            let any_value = schema.attribute(&current, local_name(name), None);
+            if is_not_allowed(&any_value) {
+                self.report(offset, format!("Attribute {} is not allowed on element <{}>", name, element.name));
+                continue;
+            }
+            let mut expected = Expected::default();
+            schema.attribute_values(&current, local_name(name), value, &mut expected);
+            self.report(offset, format!("Attribute {}: {}", name, expected.invalid_value(value)));
+            current = any_value;
+        }
+
+        let mut content = schema.start_tag_close(&current, false);
+        if is_not_allowed(&content) {
+            let missing = schema.required_attributes(&current);
+            let message = match missing.is_empty() {
+                true => format!("Element <{}> is missing required attributes", element.name),
+                false => format!("Element <{}> is missing required attribute {}", element.name, join(missing)),
+            };
+            self.report(element.offset, message);
+            content = schema.start_tag_close(&current, true);
+        }
+
+        let content = self.children(content, element);
+        let mut next = schema.end_tag(&content, false);
+        if is_not_allowed(&next) {
+            let mut expected = Expected::default();
+            schema.expected(&content, &mut expected);
+            let expected = &expected.elements | &expected.values;
+            let message = match expected.is_empty() {
+                true => format!("Element <{}> is incomplete", element.name),
+                false => format!("Element <{}> is incomplete, expected {}", element.name, join(expected)),
+            };
+            self.report(element.offset, message);
+            next = schema.end_tag(&content, true);
+        }
+        next
+    }
+
+    // Comments are ignored, so text around them is one piece. Whitespace between elements is ignored.
+    fn children(&mut self, pattern: Arc<Pattern>, element: &borrowed::Element) -> Arc<Pattern> {
+        if element.elements().next().is_none() {
+            let text = element.text();
+            if text.trim().is_empty() { return choice(pattern.clone(), self.schema.text(&pattern, Some(&text))); }
+            return self.text(pattern, element, &text);
+        }
+
+        let mut current = pattern;
+        let mut text = String::new();
+        for child in &element.children {
+            match child {
+                borrowed::Node::Text(piece) => text.push_str(piece),
+                borrowed::Node::Comment(_) => {}
+                borrowed::Node::Element(child) => {
+                    current = self.text(current, element, &std::mem::take(&mut text));
+                    current = self.element(&current, child);
+                }
+            }
+        }
+        self.text(current, element, &text)
+    }
+
+    fn text(&mut self, pattern: Arc<Pattern>, element: &borrowed::Element, text: &str) -> Arc<Pattern> {
+        if text.trim().is_empty() { return pattern; }
+        let next = self.schema.text(&pattern, Some(text));
+        if !is_not_allowed(&next) { return next; }
+
+        let mut expected = Expected::default();
+        self.schema.expected(&pattern, &mut expected);
+        if expected.values.is_empty() {
+            self.report(element.offset, format!("Text is not allowed in element <{}>", element.name));
+            return pattern;
+        }
+        self.schema.data_errors(&pattern, text, &mut expected.errors);
+        self.report(element.offset, format!("Element <{}>: {}", element.name, expected.invalid_value(text)));
+        // Invalid data is taken as given, so the element is not also reported as incomplete.
+        self.schema.text(&pattern, None)
+    }
+}
@@ -0,0 +1,218 @@
+use crate::tree::{Document, Element};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+// Structure of all occurrences of an element at one place in the documents, merged the way `start_parser` converts them:
+// an element with only text is a plain string, anything else is an object with `_`-prefixed attributes, `_text` and children.
+// Repeated siblings are merged into one shape, the converter puts them into an array.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Shape {
+    // Parent objects that contain the element and the most times it was repeated in one of them.
+    pub parents: usize,
+    pub max_occurs: usize,
+    pub string: Option<ScalarType>,
+    pub objects: usize,
+    pub properties: BTreeMap<String, Property>,
+    pub children: BTreeMap<String, Shape>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Property {
+    pub count: usize,
+    pub scalar: ScalarType,
+}
+
+// Type of the text in string values, the JSON value itself is always a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarType {
+    Boolean,
+    Integer,
+    Number,
+    Date,
+    DateTime,
+    String,
+}
+
+impl ScalarType {
+    pub fn of(text: &str) -> ScalarType {
+        if text == "true" || text == "false" { ScalarType::Boolean }
+        else if is_integer(text) { ScalarType::Integer }
+        else if is_number(text) { ScalarType::Number }
+        else if is_date(text) { ScalarType::Date }
+        else if is_date_time(text) { ScalarType::DateTime }
+        else { ScalarType::String }
+    }
+
+    pub fn merge(self, other: ScalarType) -> ScalarType {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ScalarType::Integer | ScalarType::Number, ScalarType::Integer | ScalarType::Number) => ScalarType::Number,
+            _ => ScalarType::String,
+        }
+    }
+
+    pub fn to_json_schema(self) -> Value {
+        match self {
+            ScalarType::Boolean => json!({ "type": "string", "enum": ["true", "false"] }),
+            ScalarType::Integer => json!({ "type": "string", "pattern": INTEGER_PATTERN }),
+            ScalarType::Number => json!({ "type": "string", "pattern": NUMBER_PATTERN }),
+            ScalarType::Date => json!({ "type": "string", "format": "date" }),
+            ScalarType::DateTime => json!({ "type": "string", "format": "date-time" }),
+            ScalarType::String => json!({ "type": "string" }),
+        }
+    }
+}
+
+const INTEGER_PATTERN: &str = "^[-+]?[0-9]+$";
+const NUMBER_PATTERN: &str = "^[-+]?([0-9]+\\.?[0-9]*|\\.[0-9]+)([eE][-+]?[0-9]+)?$";
+
+fn digits(text: &str) -> usize {
+    text.bytes().take_while(u8::is_ascii_digit).count()
+}
+
+fn is_integer(text: &str) -> bool {
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    !unsigned.is_empty() && digits(unsigned) == unsigned.len()
+}
+
+// Same language as NUMBER_PATTERN.
+fn is_number(text: &str) -> bool {
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    let integer = digits(unsigned);
+    let rest = &unsigned[integer..];
+    let (fraction, rest) = match rest.strip_prefix('.') {
+        Some(rest) => (Some(digits(rest)), &rest[digits(rest)..]),
+        None => (None, rest),
+    };
+    if integer == 0 && fraction.is_none_or(|fraction| fraction == 0) { return false; }
+
+    match rest.strip_prefix(['e', 'E']) {
+        Some(exponent) => is_integer(exponent),
+        None => rest.is_empty(),
+    }
+}
+
+fn is_digits(text: &str, positions: &[usize]) -> bool {
+    positions.iter().all(|&position| text.as_bytes().get(position).is_some_and(u8::is_ascii_digit))
+}
+
+fn is_byte(text: &str, position: usize, bytes: &[u8]) -> bool {
+    text.as_bytes().get(position).is_some_and(|byte| bytes.contains(byte))
+}
+
+// Two digit number at `position`, the digits are checked before.
+fn two_digits(text: &str, position: usize) -> u32 {
+    text.as_bytes()[position..position + 2].iter().fold(0, |number, digit| number * 10 + u32::from(digit - b'0'))
+}
+
+// YYYY-MM-DD as in RFC 3339.
+fn is_date(text: &str) -> bool {
+    text.len() == 10 && is_digits(text, &[0, 1, 2, 3, 5, 6, 8, 9]) && is_byte(text, 4, b"-") && is_byte(text, 7, b"-")
+        && (1..=12).contains(&two_digits(text, 5)) && (1..=31).contains(&two_digits(text, 8))
+}
+
+// YYYY-MM-DDThh:mm:ss with optional fraction and a required offset, as in RFC 3339.
+fn is_date_time(text: &str) -> bool {
+    if text.len() < 20 || !text.is_char_boundary(10) || !is_date(&text[..10]) || !is_byte(text, 10, b"Tt") { return false; }
+    let time = &text[11..];
+    if !(is_digits(time, &[0, 1, 3, 4, 6, 7]) && is_byte(time, 2, b":") && is_byte(time, 5, b":")) { return false; }
+    if two_digits(time, 0) > 23 || two_digits(time, 3) > 59 || two_digits(time, 6) > 60 { return false; }
+
+    let mut offset = &time[8..];
+    if let Some(fraction) = offset.strip_prefix('.') {
+        if digits(fraction) == 0 { return false; }
+        offset = &fraction[digits(fraction)..];
+    }
+    matches!(offset, "Z" | "z") || (offset.len() == 6 && is_byte(offset, 0, b"+-") && is_digits(offset, &[1, 2, 4, 5]) && is_byte(offset, 3, b":"))
+}
+
+impl Shape {
+    // Shape of the document object, its children are the root elements.
+    pub fn from_documents<'a>(documents: impl IntoIterator<Item = &'a Document>) -> Shape {
+        let mut shape = Shape::default();
+        for document in documents {
+            shape.objects += 1;
+            shape.add_children(std::slice::from_ref(&document.root).iter());
+        }
+        shape
+    }
+
+    fn add(&mut self, element: &Element) {
+        let text = element.trimmed_text();
+        let has_elements = element.elements().next().is_some();
+        if !has_elements && element.attributes.is_empty() && !text.is_empty() {
+            let scalar = ScalarType::of(&text);
+            self.string = Some(self.string.map_or(scalar, |string| string.merge(scalar)));
+            return;
+        }
+
+        // Later attributes and `_text` replace earlier values with the same key, as in the converter.
+        self.objects += 1;
+        let mut values = BTreeMap::new();
+        for (name, value) in &element.attributes { values.insert(format!("_{}", name), value.as_str()); }
+        if !has_elements && !text.is_empty() { values.insert("_text".to_string(), &text); }
+
+        for (key, value) in values {
+            let scalar = ScalarType::of(value);
+            self.properties.entry(key)
+                .and_modify(|property| { property.count += 1; property.scalar = property.scalar.merge(scalar); })
+                .or_insert(Property { count: 1, scalar });
+        }
+        self.add_children(element.elements());
+    }
+
+    fn add_children<'a>(&mut self, elements: impl Iterator<Item = &'a Element>) {
+        let mut groups: BTreeMap<&str, Vec<&Element>> = BTreeMap::new();
+        for element in elements { groups.entry(element.name.as_str()).or_default().push(element); }
+
+        for (name, group) in groups {
+            let child = self.children.entry(name.to_string()).or_default();
+            child.parents += 1;
+            child.max_occurs = child.max_occurs.max(group.len());
+            for element in group { child.add(element); }
+        }
+    }
+
+    pub fn is_required_in(&self, parent: &Shape) -> bool {
+        self.parents == parent.objects
+    }
+
+    pub fn to_json_schema(&self) -> Value {
+        match (self.string, self.objects) {
+            (Some(string), 0) => string.to_json_schema(),
+            (None, _) => self.object_schema(),
+            (Some(string), _) => json!({ "anyOf": [string.to_json_schema(), self.object_schema()] }),
+        }
+    }
+
+    fn object_schema(&self) -> Value {
+        let mut properties = Map::new();
+        let mut required = vec![];
+
+        for (key, property) in &self.properties {
+            properties.insert(key.clone(), property.scalar.to_json_schema());
+            if property.count == self.objects { required.push(key.clone()); }
+        }
+        for (name, child) in &self.children {
+            let schema = child.to_json_schema();
+            properties.insert(name.clone(), if child.max_occurs > 1 { repeated_schema(schema) } else { schema });
+            if child.is_required_in(self) { required.push(name.clone()); }
+        }
+
+        let mut schema = json!({ "type": "object", "properties": properties, "additionalProperties": false });
+        if !required.is_empty() { schema["required"] = json!(required); }
+        schema
+    }
+}
+
+// A single element is its own value, repeated siblings are an array of their values.
+pub(crate) fn repeated_schema(item: Value) -> Value {
+    json!({ "anyOf": [item.clone(), { "type": "array", "items": item, "minItems": 2 }] })
+}
+
+// JSON Schema of the output of `start_parser` for the sample documents and documents like them.
+pub fn infer_schema<'a>(documents: impl IntoIterator<Item = &'a Document>) -> Value {
+    let mut schema = Shape::from_documents(documents).object_schema();
+    schema["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+    schema
+}
@@ -0,0 +1,354 @@
+use crate::escape::*;
+use crate::recover::is_valid_name;
+use crate::tree::*;
+use serde::ser::{self, Serialize};
+use std::fmt;
+
+// Writes Rust values as XML with the same conventions as `de::from_str`: fields named `_attribute` become attributes,
+// `_text` becomes text, sequences become repeated elements and enum variants become elements named after them.
+// The root element is named after the struct or enum variant.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> anyhow::Result<String> {
+    let content = value.serialize(ContentSerializer)?;
+    let root = match content {
+        Content::Struct(Some(name), _) => element_with_content(name, content)?,
+        Content::Variant(name, content) => element_with_content(name, *content)?,
+        Content::UnitVariant(name) => element_with_content(name, Content::Unit)?,
+        _ => return Err(anyhow::anyhow!("Root element name can not be inferred, use to_string_with_root")),
+    };
+    Ok(write_xml(&root))
+}
+
+pub fn to_string_with_root<T: Serialize + ?Sized>(value: &T, root: &str) -> anyhow::Result<String> {
+    let content = value.serialize(ContentSerializer)?;
+    Ok(write_xml(&element_with_content(root, content)?))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Error(message.to_string())
+    }
+}
+
+// Intermediate form of a serialized value before it is placed into elements.
+enum Content {
+    None,
+    Unit,
+    Scalar(String),
+    UnitVariant(&'static str),
+    Variant(&'static str, Box<Content>),
+    Seq(Vec<Content>),
+    // Sequence inside a newtype struct, written as one element that contains the items.
+    Wrapped(Vec<Content>),
+    Struct(Option<&'static str>, Vec<(String, Content)>),
+}
+
+fn element_with_content(name: &str, content: Content) -> Result<Element, Error> {
+    if !is_valid_name(name) { return Err(Error(format!("Invalid element name \"{}\"", name))); }
+    let mut element = Element { name: name.to_string(), attributes: vec![], children: vec![] };
+    write_content(&mut element, content)?;
+    Ok(element)
+}
+
+fn write_content(element: &mut Element, content: Content) -> Result<(), Error> {
+    match content {
+        Content::None | Content::Unit => {}
+        Content::Scalar(text) => element.children.push(Node::Text(text)),
+        Content::UnitVariant(name) => element.children.push(Node::Text(name.to_string())),
+        Content::Variant(name, content) => element.children.push(Node::Element(element_with_content(name, *content)?)),
+        Content::Seq(items) | Content::Wrapped(items) => {
+            for item in items { element.children.push(Node::Element(item_element(item)?)); }
+        }
+        Content::Struct(_, fields) => {
+            for (name, value) in fields { write_field(element, name, value)?; }
+        }
+    }
+    Ok(())
+}
+
+fn write_field(element: &mut Element, name: String, content: Content) -> Result<(), Error> {
+    match content {
+        Content::None => Ok(()),
+        Content::Seq(items) if !name.starts_with('_') => {
+            for item in items { write_field(element, name.clone(), item)?; }
+            Ok(())
+        }
+        Content::Scalar(text) if name == "_text" => {
+            element.children.push(Node::Text(text));
+            Ok(())
+        }
+        Content::Scalar(value) if name.starts_with('_') => {
+            if !is_valid_name(&name[1..]) { return Err(Error(format!("Invalid attribute name \"{}\"", &name[1..]))); }
+            element.attributes.push((name[1..].to_string(), value));
+            Ok(())
+        }
+        Content::UnitVariant(value) if name.starts_with('_') => write_field(element, name, Content::Scalar(value.to_string())),
+        _ if name.starts_with('_') => Err(Error(format!("Field {} must be a scalar value", name))),
+        content => {
+            element.children.push(Node::Element(element_with_content(&name, content)?));
+            Ok(())
+        }
+    }
+}
+
+// Items of a sequence inside an element are named after their enum variant or struct.
+fn item_element(content: Content) -> Result<Element, Error> {
+    match content {
+        Content::Variant(name, content) => element_with_content(name, *content),
+        Content::UnitVariant(name) => element_with_content(name, Content::Unit),
+        Content::Struct(Some(name), _) => element_with_content(name, content),
+        content => element_with_content("item", content),
+    }
+}
+
+fn write_xml(root: &Element) -> String {
+    let mut output = String::new();
+    write_element(root, &mut output);
+    output
+}
+
+fn write_element(element: &Element, output: &mut String) {
+    output.push('<');
+    output.push_str(&element.name);
+    for (attribute_name, attribute_value) in &element.attributes {
+        output.push_str(&format!(" {}=\"{}\"", attribute_name, escape_attribute(attribute_value)));
+    }
+
+    if element.children.is_empty() {
+        output.push_str("/>");
+        return;
+    }
+
+    output.push('>');
+    for child in &element.children {
+        match child {
+            Node::Element(child) => write_element(child, output),
+            Node::Text(text) => output.push_str(&escape_text(text)),
+            Node::Comment(comment) => output.push_str(&format!("<!--{}-->", comment)),
+        }
+    }
+    output.push_str(&format!("</{}>", element.name));
+}
+
+struct ContentSerializer;
+
+macro_rules! serialize_display {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method(self, value: $ty) -> Result<Content, Error> {
+                Ok(Content::Scalar(value.to_string()))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for ContentSerializer {
+    type Ok = Content;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = StructSerializer;
+    type SerializeStruct = StructSerializer;
+    type SerializeStructVariant = VariantSerializer<StructSerializer>;
+
+    serialize_display! {
+        serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32), serialize_i64(i64), serialize_i128(i128),
+        serialize_u8(u8), serialize_u16(u16), serialize_u32(u32), serialize_u64(u64), serialize_u128(u128),
+        serialize_f32(f32), serialize_f64(f64), serialize_char(char), serialize_str(&str),
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<Content, Error> {
+        Err(Error("Bytes can not be written as XML".to_string()))
+    }
+
+    fn serialize_none(self) -> Result<Content, Error> {
+        Ok(Content::None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Content, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Content, Error> {
+        Ok(Content::Unit)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Content, Error> {
+        Ok(Content::Unit)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Content, Error> {
+        Ok(Content::UnitVariant(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Content, Error> {
+        match value.serialize(self)? {
+            Content::Seq(items) => Ok(Content::Wrapped(items)),
+            content => Ok(content),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<Content, Error> {
+        Ok(Content::Variant(variant, Box::new(value.serialize(self)?)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<VariantSerializer<SeqSerializer>, Error> {
+        Ok(VariantSerializer(variant, self.serialize_seq(Some(len))?))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<StructSerializer, Error> {
+        Ok(StructSerializer { name: None, fields: vec![], key: None })
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<StructSerializer, Error> {
+        Ok(StructSerializer { name: Some(name), fields: vec![], key: None })
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<VariantSerializer<StructSerializer>, Error> {
+        Ok(VariantSerializer(variant, StructSerializer { name: None, fields: vec![], key: None }))
+    }
+}
+
+struct SeqSerializer(Vec<Content>);
+
+impl SeqSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.0.push(value.serialize(ContentSerializer)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Content;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Content, Error> {
+        Ok(Content::Seq(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Content;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Content, Error> {
+        Ok(Content::Seq(self.0))
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Content;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Content, Error> {
+        Ok(Content::Seq(self.0))
+    }
+}
+
+struct StructSerializer {
+    name: Option<&'static str>,
+    fields: Vec<(String, Content)>,
+    key: Option<String>,
+}
+
+impl ser::SerializeStruct for StructSerializer {
+    type Ok = Content;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.fields.push((key.to_string(), value.serialize(ContentSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Content, Error> {
+        Ok(Content::Struct(self.name, self.fields))
+    }
+}
+
+impl ser::SerializeMap for StructSerializer {
+    type Ok = Content;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(ContentSerializer)? {
+            Content::Scalar(key) => self.key = Some(key),
+            Content::UnitVariant(key) => self.key = Some(key.to_string()),
+            _ => return Err(Error("Map keys must be scalar values".to_string())),
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().ok_or_else(|| Error("Map value is serialized before key".to_string()))?;
+        self.fields.push((key, value.serialize(ContentSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Content, Error> {
+        Ok(Content::Struct(self.name, self.fields))
+    }
+}
+
+struct VariantSerializer<S>(&'static str, S);
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = Content;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.1.push(value)
+    }
+
+    fn end(self) -> Result<Content, Error> {
+        Ok(Content::Variant(self.0, Box::new(Content::Seq(self.1.0))))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<StructSerializer> {
+    type Ok = Content;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(&mut self.1, key, value)
+    }
+
+    fn end(self) -> Result<Content, Error> {
+        Ok(Content::Variant(self.0, Box::new(Content::Struct(None, self.1.fields))))
+    }
+}
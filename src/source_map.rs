@@ -0,0 +1,120 @@
+use crate::escape::escape_pointer;
+use crate::{parse_element, parse_pairs, Rule};
+use anyhow::*;
+use pest::iterators::{Pair, Pairs};
+use pest::Span;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl SourceLocation {
+    fn from_span(span: Span) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        SourceLocation { line, column, start: span.start(), end: span.end() }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({ "line": self.line, "column": self.column, "start": self.start, "end": self.end })
+    }
+}
+
+// JSON Pointer of every converted value mapped to the XML it came from.
+pub type SourceMap = BTreeMap<String, SourceLocation>;
+
+pub fn source_map_to_json(source_map: &SourceMap) -> Value {
+    Value::Object(source_map.iter().map(|(pointer, location)| (pointer.clone(), location.to_json())).collect())
+}
+
+pub fn start_parser_with_source_map(input: &str) -> Result<(Value, SourceMap)> {
+    if input.trim().is_empty() { return Err(anyhow!("XML is empty")); }
+    let parsed = parse_pairs(input)?;
+    let root = parsed.into_inner().find(|pair| pair.as_rule() == Rule::element).unwrap();
+
+    let value = parse_element(root.clone())?;
+    let mut source_map = SourceMap::new();
+    record_element(root, &mut source_map);
+
+    Ok((value, source_map))
+}
+
+// Follows the shape produced by `parse_element`: text gets its own pointer only next to attributes and without nested elements,
+// repeated elements get the index in their array.
+// Open elements are kept on an explicit stack like in `check`.
+fn record_element(root: Pair<Rule>, source_map: &mut SourceMap) {
+    let pointer = format!("/{}", escape_pointer(element_name(&root)));
+    let mut stack = vec![RecordFrame::new(root, pointer, source_map)];
+
+    while let Some(frame) = stack.last_mut() {
+        let Some(next) = frame.pairs.next() else {
+            let frame = stack.pop().unwrap();
+            if let (false, true, Some((first, last))) = (frame.has_nested, frame.has_attributes, frame.text_span) {
+                let span = first.start_pos().span(&last.end_pos());
+                source_map.insert(format!("{}/_text", frame.pointer), SourceLocation::from_span(span));
+            }
+            continue;
+        };
+
+        match next.as_rule() {
+            Rule::element => {
+                frame.has_nested = true;
+                let name = element_name(&next);
+                let mut pointer = format!("{}/{}", frame.pointer, escape_pointer(name));
+                if let Some(index) = frame.repeated.get_mut(name) {
+                    pointer = format!("{}/{}", pointer, index);
+                    *index += 1;
+                }
+                stack.push(RecordFrame::new(next, pointer, source_map));
+            }
+            Rule::inner_text if !next.as_str().trim().is_empty() => {
+                let span = next.as_span();
+                frame.text_span = Some(match frame.text_span {
+                    Some((first, _)) => (first, span),
+                    None => (span, span),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn element_name<'a>(pair: &Pair<'a, Rule>) -> &'a str {
+    pair.clone().into_inner().next().unwrap().into_inner().next().unwrap().as_str()
+}
+
+struct RecordFrame<'a> {
+    pointer: String,
+    pairs: Pairs<'a, Rule>,
+    // Names of the children that occur more than once with the array index of the next one.
+    repeated: HashMap<&'a str, usize>,
+    has_attributes: bool,
+    has_nested: bool,
+    text_span: Option<(Span<'a>, Span<'a>)>,
+}
+
+impl<'a> RecordFrame<'a> {
+    // Records the element and its attributes, the content is read from `pairs`.
+    fn new(pair: Pair<'a, Rule>, pointer: String, source_map: &mut SourceMap) -> Self {
+        source_map.insert(pointer.clone(), SourceLocation::from_span(pair.as_span()));
+        let mut pairs = pair.into_inner();
+        let open_tag_inner = pairs.next().unwrap().into_inner().skip(1);
+
+        let mut counts = HashMap::new();
+        for child in pairs.clone().filter(|pair| pair.as_rule() == Rule::element) { *counts.entry(element_name(&child)).or_insert(0) += 1; }
+        let repeated = counts.into_iter().filter(|(_, count)| *count > 1).map(|(name, _)| (name, 0)).collect();
+
+        let mut has_attributes = false;
+        for attribute in open_tag_inner.filter(|pair| pair.as_rule() == Rule::attribute) {
+            let attribute_name = attribute.clone().into_inner().next().unwrap().as_str();
+            source_map.insert(format!("{}/_{}", pointer, escape_pointer(attribute_name)), SourceLocation::from_span(attribute.as_span()));
+            has_attributes = true;
+        }
+        RecordFrame { pointer, pairs, repeated, has_attributes, has_nested: false, text_span: None }
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{insert_child, parse_pairs, wrap, Rule};
+use anyhow::*;
+use pest::iterators::{Pair, Pairs};
+use serde_json::Value;
+
+// Lossless representation of a parsed document: keeps comments, text exactly as written and the order of nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub declaration: Option<String>,
+    pub doctype: Option<String>,
+    pub prolog: Vec<Node>,
+    pub root: Element,
+    pub epilog: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+    Comment(String),
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Node::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    pub fn text(&self) -> String {
+        self.children.iter().filter_map(|child| match child {
+            Node::Text(text) => Some(text.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    // Text pieces trimmed and joined the way `parse_element` does it.
+    pub fn trimmed_text(&self) -> String {
+        self.children.iter().filter_map(|child| match child {
+            Node::Text(text) => Some(text.trim()),
+            _ => None,
+        }).collect()
+    }
+
+    // Same conversion as `parse_element`, text pieces are trimmed and only kept when there are no nested elements.
+    pub fn to_json(&self) -> Value {
+        wrap(self.name.clone(), self.content_to_json())
+    }
+
+    // Post-order walk with an explicit stack, so deep trees from the recovering scanner do not overflow.
+    pub fn content_to_json(&self) -> Value {
+        let mut stack = vec![JsonFrame::new(self)];
+
+        loop {
+            let frame = stack.last_mut().unwrap();
+            match frame.elements.next() {
+                Some(element) => stack.push(JsonFrame::new(element)),
+                None => {
+                    let frame = stack.pop().unwrap();
+                    let name = &frame.element.name;
+                    let value = frame.finish();
+                    match stack.last_mut() {
+                        Some(parent) => insert_child(&mut parent.json, name.clone(), value),
+                        None => return value,
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct JsonFrame<'a> {
+    element: &'a Element,
+    elements: Box<dyn Iterator<Item = &'a Element> + 'a>,
+    json: serde_json::Map<String, Value>,
+}
+
+impl<'a> JsonFrame<'a> {
+    fn new(element: &'a Element) -> Self {
+        let mut json = serde_json::Map::new();
+        for (attribute_name, attribute_value) in &element.attributes {
+            json.insert(format!("_{}", attribute_name), Value::String(attribute_value.clone()));
+        }
+        JsonFrame { element, elements: Box::new(element.elements()), json }
+    }
+
+    fn finish(mut self) -> Value {
+        let text_content = self.element.trimmed_text();
+
+        if self.element.elements().next().is_none() && !text_content.is_empty() {
+            if self.json.is_empty() { return Value::String(text_content); }
+            self.json.insert("_text".to_string(), Value::String(text_content));
+        }
+
+        Value::Object(self.json)
+    }
+}
+
+// Takes a tree apart with an explicit stack, the default drop recurses once per nesting level.
+// Used for the trees of the recovering scanner, which are not limited in depth by pest.
+pub(crate) fn drop_element(element: Element) {
+    let mut stack = element.children;
+    while let Some(node) = stack.pop() {
+        if let Node::Element(mut element) = node { stack.append(&mut element.children); }
+    }
+}
+
+pub fn parse_document(input: &str) -> Result<Document> {
+    let DocumentParts { declaration, doctype, prolog, root, epilog } = build_document::<Owned>(input)?;
+    Ok(Document { declaration: declaration.map(str::to_string), doctype: doctype.map(str::to_string), prolog, root, epilog })
+}
+
+// Node types produced by `build_document`: owned text exactly as written here, `Cow` slices with decoded entities in `borrowed`.
+pub(crate) trait Nodes<'a> {
+    type Element;
+    type Node;
+
+    // `offset` is the byte offset of the '<' of the open tag, every attribute comes with the offset of its name.
+    fn element(name: &'a str, offset: usize, attributes: Vec<(&'a str, &'a str, usize)>) -> Self::Element;
+    fn text(text: &'a str) -> Self::Node;
+    fn comment(comment: &'a str) -> Self::Node;
+    fn node(element: Self::Element) -> Self::Node;
+    fn push(parent: &mut Self::Element, child: Self::Node);
+}
+
+struct Owned;
+
+impl<'a> Nodes<'a> for Owned {
+    type Element = Element;
+    type Node = Node;
+
+    fn element(name: &'a str, _offset: usize, attributes: Vec<(&'a str, &'a str, usize)>) -> Element {
+        let attributes = attributes.into_iter().map(|(key, value, _)| (key.to_string(), value.to_string())).collect();
+        Element { name: name.to_string(), attributes, children: vec![] }
+    }
+
+    fn text(text: &'a str) -> Node {
+        Node::Text(text.to_string())
+    }
+
+    fn comment(comment: &'a str) -> Node {
+        Node::Comment(comment.to_string())
+    }
+
+    fn node(element: Element) -> Node {
+        Node::Element(element)
+    }
+
+    fn push(parent: &mut Element, child: Node) {
+        parent.children.push(child);
+    }
+}
+
+pub(crate) struct DocumentParts<'a, N: Nodes<'a>> {
+    pub declaration: Option<&'a str>,
+    pub doctype: Option<&'a str>,
+    pub prolog: Vec<N::Node>,
+    pub root: N::Element,
+    pub epilog: Vec<N::Node>,
+}
+
+pub(crate) fn build_document<'a, N: Nodes<'a>>(input: &'a str) -> Result<DocumentParts<'a, N>> {
+    if input.trim().is_empty() { return Err(anyhow!("XML is empty")); }
+    let parsed = parse_pairs(input)?;
+
+    let mut declaration = None;
+    let mut doctype = None;
+    let mut prolog = vec![];
+    let mut root = None;
+    let mut epilog = vec![];
+
+    for pair in parsed.into_inner() {
+        match pair.as_rule() {
+            Rule::declaration => declaration = Some(pair.as_str()),
+            Rule::doctype => doctype = Some(pair.as_str()),
+            Rule::element => root = Some(build_element::<N>(pair, input)?),
+            Rule::comment => {
+                let comment = build_comment::<N>(pair);
+                if root.is_none() { prolog.push(comment); } else { epilog.push(comment); }
+            }
+            _ => {}
+        }
+    }
+
+    let root = root.ok_or_else(|| anyhow!("Invalid XML"))?;
+    Ok(DocumentParts { declaration, doctype, prolog, root, epilog })
+}
+
+fn build_comment<'a, N: Nodes<'a>>(pair: Pair<'a, Rule>) -> N::Node {
+    let raw = pair.as_str();
+    N::comment(&raw["<!--".len()..raw.len() - "-->".len()])
+}
+
+struct BuildFrame<'a, N: Nodes<'a>> {
+    name: &'a str,
+    element: N::Element,
+    pairs: Pairs<'a, Rule>,
+    cursor: usize,
+}
+
+impl<'a, N: Nodes<'a>> BuildFrame<'a, N> {
+    fn new(pair: Pair<'a, Rule>) -> Self {
+        let offset = pair.as_span().start();
+        let mut pairs = pair.into_inner();
+        let open_tag = pairs.next().unwrap();
+        let cursor = open_tag.as_span().end();
+
+        let mut open_tag_inner = open_tag.into_inner();
+        let name = open_tag_inner.next().unwrap().as_str();
+        let attributes = open_tag_inner.filter(|pair| pair.as_rule() == Rule::attribute).map(|attribute| {
+            let mut inner = attribute.into_inner();
+            let name = inner.next().unwrap();
+            (name.as_str(), inner.next().unwrap().as_str(), name.as_span().start())
+        }).collect();
+
+        BuildFrame { name, element: N::element(name, offset, attributes), pairs, cursor }
+    }
+}
+
+// Nested elements are kept on an explicit stack like in `parse_element`.
+fn build_element<'a, N: Nodes<'a>>(pair: Pair<'a, Rule>, input: &'a str) -> Result<N::Element> {
+    let mut stack = vec![BuildFrame::<N>::new(pair)];
+
+    loop {
+        let frame = stack.last_mut().unwrap();
+        let Some(next) = frame.pairs.next() else {
+            let frame = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some(parent) => N::push(&mut parent.element, N::node(frame.element)),
+                None => return Ok(frame.element),
+            }
+            continue;
+        };
+
+        // Text is sliced from the input between child nodes, so whitespace skipped by the grammar is kept.
+        let start = next.as_span().start();
+        if start > frame.cursor && matches!(next.as_rule(), Rule::element | Rule::comment | Rule::close_tag) {
+            N::push(&mut frame.element, N::text(&input[frame.cursor..start]));
+        }
+
+        match next.as_rule() {
+            Rule::element => {
+                frame.cursor = next.as_span().end();
+                stack.push(BuildFrame::new(next));
+            }
+            Rule::comment => {
+                frame.cursor = next.as_span().end();
+                N::push(&mut frame.element, build_comment::<N>(next));
+            }
+            Rule::close_tag => {
+                let close_tag_name = next.into_inner().next().unwrap().as_str();
+                if close_tag_name != frame.name { return Err(anyhow!("There are different open and close tags names")); }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::lenient::*;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+use crate::output::*;
+use crate::recover::*;
+use crate::start_parser;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Output {
+    #[default]
+    Object,
+    Json,
+    Yaml,
+    Toml,
+}
+
+// Options object of `xmlToJson`, every field is optional: `{ output: "object" | "json" | "yaml" | "toml", recover, lenient }`.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Options {
+    output: Output,
+    recover: bool,
+    lenient: bool,
+}
+
+fn convert(xml: &str, options: &Options) -> anyhow::Result<Value> {
+    if options.recover && options.lenient { anyhow::bail!("Options recover and lenient can not be used together"); }
+    Ok(match (options.recover, options.lenient) {
+        (true, _) => parse_recovering(xml).value,
+        (_, true) => parse_lenient(xml, &LenientOptions::default()).value,
+        _ => start_parser(xml)?,
+    })
+}
+
+// Same conversion as the `parse` command, returns a plain JS object or, with the `output` option, a string.
+#[wasm_bindgen(js_name = xmlToJson)]
+pub fn xml_to_json(xml: &str, options: JsValue) -> Result<JsValue, JsError> {
+    let options: Options = if options.is_undefined() || options.is_null() { Options::default() } else { serde_wasm_bindgen::from_value(options)? };
+    let json = convert(xml, &options).map_err(|error| JsError::new(&error.to_string()))?;
+
+    let text = match options.output {
+        Output::Object => return Ok(json.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?),
+        Output::Json => serde_json::to_string_pretty(&json)?,
+        #[cfg(feature = "yaml")]
+        Output::Yaml => to_yaml_string(&json).map_err(|error| JsError::new(&error.to_string()))?,
+        #[cfg(not(feature = "yaml"))]
+        Output::Yaml => return Err(JsError::new("YAML output needs the yaml feature")),
+        #[cfg(feature = "toml")]
+        Output::Toml => to_toml_string(&json).map_err(|error| JsError::new(&error.to_string()))?,
+        #[cfg(not(feature = "toml"))]
+        Output::Toml => return Err(JsError::new("TOML output needs the toml feature")),
+    };
+    Ok(JsValue::from_str(&text))
+}
@@ -0,0 +1,1060 @@
+use crate::borrowed::{self, parse_borrowed};
+use crate::check::Diagnostic;
+use crate::encoding::decode_bytes;
+use crate::schema::repeated_schema;
+use crate::tree::{parse_document, Document, Element};
+use anyhow::*;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+// XML Schema 1.0 validation of element structure, occurrences, simple types and attribute use.
+// Names are compared without namespace prefixes, substitution groups, identity constraints and `xsi:type` are not supported.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    elements: HashMap<String, ElementDecl>,
+    types: HashMap<String, ComplexType>,
+}
+
+const XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+#[derive(Debug, Clone)]
+struct ElementDecl {
+    name: String,
+    type_ref: TypeRef,
+    fixed: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum TypeRef {
+    Any,
+    Named(String),
+    Simple(SimpleType),
+    Complex(Box<ComplexType>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct ComplexType {
+    attributes: Vec<AttributeDecl>,
+    any_attribute: bool,
+    content: Content,
+}
+
+#[derive(Debug, Clone)]
+enum Content {
+    Simple(SimpleType),
+    Elements { particle: Option<Particle>, mixed: bool },
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Content::Elements { particle: None, mixed: false }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AttributeDecl {
+    name: String,
+    simple: SimpleType,
+    required: bool,
+    prohibited: bool,
+    fixed: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Particle {
+    term: Term,
+    min: usize,
+    max: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Element(Box<ElementDecl>),
+    ElementRef(String),
+    Sequence(Vec<Particle>),
+    Choice(Vec<Particle>),
+    All(Vec<Particle>),
+    Any(Process),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Process {
+    Strict,
+    Lax,
+    Skip,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum SimpleType {
+    Builtin(&'static str),
+    Restriction(Box<SimpleType>, Box<Facets>),
+    List(Box<SimpleType>),
+    Union(Vec<SimpleType>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Facets {
+    enumeration: Vec<String>,
+    pattern: Option<(Regex, String)>,
+    min_inclusive: Option<String>,
+    max_inclusive: Option<String>,
+    min_exclusive: Option<String>,
+    max_exclusive: Option<String>,
+    length: Option<usize>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    total_digits: Option<usize>,
+    fraction_digits: Option<usize>,
+}
+
+pub(crate) fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
+
+fn prefix(name: &str) -> &str {
+    name.rsplit_once(':').map_or("", |(prefix, _)| prefix)
+}
+
+// Timezone of the date and time types.
+const TZ: &str = r"(Z|[+-]((0[0-9]|1[0-3]):[0-5][0-9]|14:00))?";
+const YEAR: &str = r"-?([1-9][0-9]{4,}|[0-9]{4})";
+const MONTH: &str = r"(0[1-9]|1[0-2])";
+const DAY: &str = r"(0[1-9]|[12][0-9]|3[01])";
+const TIME: &str = r"(([01][0-9]|2[0-3]):[0-5][0-9]:[0-5][0-9](\.[0-9]+)?|24:00:00(\.0+)?)";
+
+// Built-in simple types that accept any text.
+const ANY_TEXT: &[&str] = &["anySimpleType", "string", "normalizedString", "token", "anyURI", "NOTATION"];
+
+// Built-in simple types with the pattern of their lexical space.
+static BUILTINS: LazyLock<HashMap<&'static str, Regex>> = LazyLock::new(|| {
+    let integer = r"[+-]?[0-9]+".to_string();
+    let name = r"[\p{L}_:][\p{L}\p{N}._:\-]*".to_string();
+    let nc_name = r"[\p{L}_][\p{L}\p{N}._\-]*".to_string();
+    let nm_token = r"[\p{L}\p{N}._:\-]+".to_string();
+    let types = [
+        ("language", r"[a-zA-Z]{1,8}(-[a-zA-Z0-9]{1,8})*".to_string()),
+        ("Name", name), ("NCName", nc_name.clone()), ("ID", nc_name.clone()), ("IDREF", nc_name.clone()), ("ENTITY", nc_name.clone()),
+        ("IDREFS", format!("{0}( {0})*", nc_name)), ("ENTITIES", format!("{0}( {0})*", nc_name)),
+        ("QName", format!("({0}:)?{0}", nc_name)), ("NMTOKEN", nm_token.clone()), ("NMTOKENS", format!("{0}( {0})*", nm_token)),
+        ("boolean", "true|false|1|0".to_string()),
+        ("decimal", r"[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)".to_string()),
+        ("float", r"[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)([eE][+-]?[0-9]+)?|[+-]?INF|NaN".to_string()),
+        ("double", r"[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)([eE][+-]?[0-9]+)?|[+-]?INF|NaN".to_string()),
+        ("integer", integer.clone()), ("long", integer.clone()), ("int", integer.clone()), ("short", integer.clone()), ("byte", integer.clone()),
+        ("nonNegativeInteger", integer.clone()), ("positiveInteger", integer.clone()), ("nonPositiveInteger", integer.clone()),
+        ("negativeInteger", integer.clone()), ("unsignedLong", integer.clone()), ("unsignedInt", integer.clone()),
+        ("unsignedShort", integer.clone()), ("unsignedByte", integer),
+        ("date", format!("{}-{}-{}{}", YEAR, MONTH, DAY, TZ)),
+        ("dateTime", format!("{}-{}-{}T{}{}", YEAR, MONTH, DAY, TIME, TZ)),
+        ("time", format!("{}{}", TIME, TZ)),
+        ("gYear", format!("{}{}", YEAR, TZ)),
+        ("gYearMonth", format!("{}-{}{}", YEAR, MONTH, TZ)),
+        ("gMonth", format!("--{}{}", MONTH, TZ)),
+        ("gDay", format!("---{}{}", DAY, TZ)),
+        ("gMonthDay", format!("--{}-{}{}", MONTH, DAY, TZ)),
+        ("duration", r"-?P([0-9]+Y)?([0-9]+M)?([0-9]+D)?(T([0-9]+H)?([0-9]+M)?([0-9]+(\.[0-9]+)?S)?)?".to_string()),
+        ("hexBinary", "([0-9a-fA-F]{2})*".to_string()),
+        ("base64Binary", r"([A-Za-z0-9+/] ?){4}*(([A-Za-z0-9+/] ?){2}(= ?)?=|([A-Za-z0-9+/] ?){3}=)?".to_string()),
+    ];
+    let any_text = ANY_TEXT.iter().map(|name| (*name, "(?s:.*)".to_string()));
+    types.into_iter().chain(any_text).map(|(name, pattern)| (name, Regex::new(&format!("^(?:{})$", pattern)).unwrap())).collect()
+});
+
+fn integer_range(name: &str) -> Option<(i128, i128)> {
+    Some(match name {
+        "long" => (i64::MIN.into(), i64::MAX.into()),
+        "int" => (i32::MIN.into(), i32::MAX.into()),
+        "short" => (i16::MIN.into(), i16::MAX.into()),
+        "byte" => (i8::MIN.into(), i8::MAX.into()),
+        "nonNegativeInteger" => (0, i128::MAX),
+        "positiveInteger" => (1, i128::MAX),
+        "nonPositiveInteger" => (i128::MIN, 0),
+        "negativeInteger" => (i128::MIN, -1),
+        "unsignedLong" => (0, u64::MAX.into()),
+        "unsignedInt" => (0, u32::MAX.into()),
+        "unsignedShort" => (0, u16::MAX.into()),
+        "unsignedByte" => (0, u8::MAX.into()),
+        _ => return None,
+    })
+}
+
+fn is_numeric(name: &str) -> bool {
+    matches!(name, "decimal" | "float" | "double" | "integer") || integer_range(name).is_some()
+}
+
+impl SimpleType {
+    fn builtin_base(&self) -> &'static str {
+        match self {
+            SimpleType::Builtin(name) => name,
+            SimpleType::Restriction(base, _) => base.builtin_base(),
+            SimpleType::List(_) | SimpleType::Union(_) => "token",
+        }
+    }
+
+    fn is_list(&self) -> bool {
+        match self {
+            SimpleType::Restriction(base, _) => base.is_list(),
+            SimpleType::List(_) => true,
+            _ => false,
+        }
+    }
+
+    // Whitespace is kept in strings, replaced in normalized strings and collapsed in every other type.
+    pub(crate) fn normalize<'v>(&self, value: &'v str) -> Cow<'v, str> {
+        match self.builtin_base() {
+            "string" | "anySimpleType" => Cow::Borrowed(value),
+            "normalizedString" => Cow::Owned(value.replace(['\t', '\n', '\r'], " ")),
+            _ => Cow::Owned(value.split_whitespace().collect::<Vec<_>>().join(" ")),
+        }
+    }
+
+    pub(crate) fn check(&self, value: &str) -> Result<()> {
+        let normalized = self.normalize(value);
+        match self {
+            SimpleType::Builtin(name) => check_builtin(name, &normalized),
+            SimpleType::Restriction(base, facets) => {
+                base.check(value)?;
+                facets.check(&normalized, self.is_list(), is_numeric(self.builtin_base()))
+            }
+            SimpleType::List(item) => normalized.split(' ').filter(|item| !item.is_empty()).try_for_each(|value| item.check(value)),
+            SimpleType::Union(members) => {
+                if members.iter().any(|member| member.check(value).is_ok()) { return Ok(()); }
+                Err(anyhow!("{:?} does not match any member type of the union", normalized))
+            }
+        }
+    }
+}
+
+fn check_builtin(name: &str, value: &str) -> Result<()> {
+    if !BUILTINS[name].is_match(value) || (name == "duration" && (value.ends_with('P') || value.ends_with('T'))) {
+        return Err(anyhow!("{:?} is not a valid {}", value, name));
+    }
+    if let Some((min, max)) = integer_range(name) {
+        let number = value.trim_start_matches('+').parse::<i128>().map_err(|_| anyhow!("{:?} is out of range for {}", value, name))?;
+        if number < min || number > max { return Err(anyhow!("{:?} is out of range for {}", value, name)); }
+    }
+    Ok(())
+}
+
+// Digits of a decimal without sign, leading zeros of the integer part and trailing zeros of the fraction.
+fn decimal_digits(value: &str) -> (usize, usize) {
+    let unsigned = value.trim_start_matches(['+', '-']);
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let integer = integer.trim_start_matches('0');
+    let fraction = fraction.trim_end_matches('0');
+    (integer.len() + fraction.len(), fraction.len())
+}
+
+impl Facets {
+    fn check(&self, value: &str, is_list: bool, is_numeric: bool) -> Result<()> {
+        if !self.enumeration.is_empty() && !self.enumeration.iter().any(|allowed| allowed == value) {
+            return Err(anyhow!("{:?} is not one of {}", value, self.enumeration.iter().map(|allowed| format!("{:?}", allowed)).collect::<Vec<_>>().join(", ")));
+        }
+        if let Some((pattern, source)) = &self.pattern && !pattern.is_match(value) {
+            return Err(anyhow!("{:?} does not match the pattern {}", value, source));
+        }
+
+        let length = if is_list { value.split(' ').filter(|item| !item.is_empty()).count() } else { value.chars().count() };
+        if self.length.is_some_and(|expected| length != expected) { return Err(anyhow!("{:?} must have length {}", value, self.length.unwrap())); }
+        if self.min_length.is_some_and(|min| length < min) { return Err(anyhow!("{:?} is shorter than {}", value, self.min_length.unwrap())); }
+        if self.max_length.is_some_and(|max| length > max) { return Err(anyhow!("{:?} is longer than {}", value, self.max_length.unwrap())); }
+
+        let (total_digits, fraction_digits) = decimal_digits(value);
+        if self.total_digits.is_some_and(|max| total_digits > max) { return Err(anyhow!("{:?} has more than {} digits", value, self.total_digits.unwrap())); }
+        if self.fraction_digits.is_some_and(|max| fraction_digits > max) { return Err(anyhow!("{:?} has more than {} fraction digits", value, self.fraction_digits.unwrap())); }
+
+        // Numbers are compared by value, dates and times of the same form compare as text.
+        let compare = |bound: &str| -> Option<std::cmp::Ordering> {
+            if is_numeric { value.parse::<f64>().ok()?.partial_cmp(&bound.parse::<f64>().ok()?) } else { Some(value.cmp(bound)) }
+        };
+        let bounds = [
+            (&self.min_inclusive, "less than", [std::cmp::Ordering::Less].as_slice()),
+            (&self.max_inclusive, "greater than", &[std::cmp::Ordering::Greater]),
+            (&self.min_exclusive, "less than or equal to", &[std::cmp::Ordering::Less, std::cmp::Ordering::Equal]),
+            (&self.max_exclusive, "greater than or equal to", &[std::cmp::Ordering::Greater, std::cmp::Ordering::Equal]),
+        ];
+        for (bound, description, rejected) in bounds {
+            let Some(bound) = bound else { continue };
+            if compare(bound).is_some_and(|ordering| rejected.contains(&ordering)) { return Err(anyhow!("{:?} is {} {}", value, description, bound)); }
+        }
+        Ok(())
+    }
+}
+
+// Top-level definitions of the schema file and the files it includes, compiled once all of them are known.
+#[derive(Default)]
+struct Definitions {
+    elements: HashMap<String, Element>,
+    types: HashMap<String, Element>,
+    groups: HashMap<String, Element>,
+    attribute_groups: HashMap<String, Element>,
+    attributes: HashMap<String, Element>,
+    xsd_prefixes: HashSet<String>,
+    loaded: HashSet<PathBuf>,
+}
+
+// Nesting of type derivations, groups and attribute groups, deeper definitions are circular.
+const MAX_DEPTH: usize = 64;
+
+impl Definitions {
+    fn load_file(&mut self, path: &Path) -> Result<()> {
+        let path = fs::canonicalize(path).with_context(|| format!("Error reading schema {}", path.display()))?;
+        if !self.loaded.insert(path.clone()) { return Ok(()); }
+
+        let bytes = fs::read(&path).with_context(|| format!("Error reading schema {}", path.display()))?;
+        let document = parse_document(&decode_bytes(&bytes)?).with_context(|| format!("Error parsing schema {}", path.display()))?;
+        self.load(document, path.parent())
+    }
+
+    fn load(&mut self, document: Document, base: Option<&Path>) -> Result<()> {
+        let root = &document.root;
+        if local_name(&root.name) != "schema" { return Err(anyhow!("Root element of a schema must be <schema>, got <{}>", root.name)); }
+
+        for (name, value) in &root.attributes {
+            if value != XSD_NAMESPACE { continue; }
+            if name == "xmlns" { self.xsd_prefixes.insert(String::new()); }
+            if let Some(prefix) = name.strip_prefix("xmlns:") { self.xsd_prefixes.insert(prefix.to_string()); }
+        }
+
+        for child in root.elements() {
+            let kind = local_name(&child.name);
+            if matches!(kind, "include" | "import" | "redefine") {
+                // Imports without a location refer to schemas that are not available.
+                let Some(location) = child.attribute("schemaLocation") else { continue };
+                if location.contains("://") { return Err(anyhow!("Only local schema files are supported, got {}", location)); }
+                let path = base.map_or_else(|| PathBuf::from(location), |base| base.join(location));
+                self.load_file(&path)?;
+                continue;
+            }
+
+            let definitions = match kind {
+                "element" => &mut self.elements,
+                "complexType" | "simpleType" => &mut self.types,
+                "group" => &mut self.groups,
+                "attributeGroup" => &mut self.attribute_groups,
+                "attribute" => &mut self.attributes,
+                _ => continue,
+            };
+            let name = child.attribute("name").ok_or_else(|| anyhow!("Top-level <{}> in a schema must have a name", child.name))?;
+            definitions.insert(name.to_string(), child.clone());
+        }
+        Ok(())
+    }
+
+    fn builtin(&self, name: &str) -> Option<&'static str> {
+        let is_xsd = self.xsd_prefixes.contains(prefix(name));
+        if !is_xsd && self.types.contains_key(local_name(name)) { return None; }
+        BUILTINS.get_key_value(local_name(name)).map(|(name, _)| *name)
+    }
+
+    fn definition<'d>(definitions: &'d HashMap<String, Element>, kind: &str, name: &str) -> Result<&'d Element> {
+        definitions.get(local_name(name)).ok_or_else(|| anyhow!("Unknown {} {} in schema", kind, name))
+    }
+
+    fn type_ref(&self, name: &str, depth: usize) -> Result<TypeRef> {
+        if local_name(name) == "anyType" && self.xsd_prefixes.contains(prefix(name)) { return Ok(TypeRef::Any); }
+        if let Some(builtin) = self.builtin(name) { return Ok(TypeRef::Simple(SimpleType::Builtin(builtin))); }
+
+        let definition = Self::definition(&self.types, "type", name)?;
+        if local_name(&definition.name) == "complexType" { Ok(TypeRef::Named(local_name(name).to_string())) } else { Ok(TypeRef::Simple(self.simple_type(definition, depth + 1)?)) }
+    }
+
+    fn simple_type_ref(&self, name: &str, depth: usize) -> Result<SimpleType> {
+        match self.type_ref(name, depth)? {
+            TypeRef::Simple(simple) => Ok(simple),
+            _ => Err(anyhow!("Type {} is complex where a simple type is expected", name)),
+        }
+    }
+
+    fn inner_simple_type(&self, element: &Element, depth: usize) -> Result<Option<SimpleType>> {
+        element.elements().find(|child| local_name(&child.name) == "simpleType").map(|child| self.simple_type(child, depth + 1)).transpose()
+    }
+
+    fn simple_type(&self, element: &Element, depth: usize) -> Result<SimpleType> {
+        if depth > MAX_DEPTH { return Err(anyhow!("Type definitions in schema are circular")); }
+
+        for child in element.elements() {
+            match local_name(&child.name) {
+                "restriction" => {
+                    let base = match child.attribute("base") {
+                        Some(base) => self.simple_type_ref(base, depth)?,
+                        None => self.inner_simple_type(child, depth)?.ok_or_else(|| anyhow!("Restriction without a base type in schema"))?,
+                    };
+                    return Ok(SimpleType::Restriction(Box::new(base), Box::new(facets(facet_values(child))?)));
+                }
+                "list" => {
+                    let item = match child.attribute("itemType") {
+                        Some(item) => self.simple_type_ref(item, depth)?,
+                        None => self.inner_simple_type(child, depth)?.ok_or_else(|| anyhow!("List without an item type in schema"))?,
+                    };
+                    return Ok(SimpleType::List(Box::new(item)));
+                }
+                "union" => {
+                    let mut members = vec![];
+                    for member in child.attribute("memberTypes").unwrap_or("").split_whitespace() { members.push(self.simple_type_ref(member, depth)?); }
+                    for inner in child.elements().filter(|inner| local_name(&inner.name) == "simpleType") { members.push(self.simple_type(inner, depth + 1)?); }
+                    return Ok(SimpleType::Union(members));
+                }
+                _ => {}
+            }
+        }
+        Err(anyhow!("<simpleType> in schema must have a restriction, list or union"))
+    }
+
+    fn complex_type_ref(&self, name: &str, depth: usize) -> Result<ComplexType> {
+        match self.type_ref(name, depth)? {
+            TypeRef::Any => Ok(ComplexType { any_attribute: true, ..ComplexType::default() }),
+            TypeRef::Named(name) => self.complex_type(&self.types[&name], depth + 1),
+            TypeRef::Simple(simple) => Ok(ComplexType { content: Content::Simple(simple), ..ComplexType::default() }),
+            TypeRef::Complex(complex) => Ok(*complex),
+        }
+    }
+
+    fn complex_type(&self, element: &Element, depth: usize) -> Result<ComplexType> {
+        if depth > MAX_DEPTH { return Err(anyhow!("Type definitions in schema are circular")); }
+        let mut complex = ComplexType::default();
+        let mixed = element.attribute("mixed") == Some("true");
+        let mut particle = None;
+
+        for child in element.elements() {
+            match local_name(&child.name) {
+                "sequence" | "choice" | "all" | "group" => particle = Some(self.particle(child, depth)?),
+                "attribute" | "attributeGroup" | "anyAttribute" => self.attribute_uses(child, &mut complex, depth)?,
+                "simpleContent" | "complexContent" => return self.derived_type(child, mixed, depth),
+                _ => {}
+            }
+        }
+
+        complex.content = Content::Elements { particle, mixed };
+        Ok(complex)
+    }
+
+    // Extension adds content and attributes to the base type, restriction restates the content and changes attributes.
+    fn derived_type(&self, element: &Element, mixed: bool, depth: usize) -> Result<ComplexType> {
+        let mixed = mixed || element.attribute("mixed") == Some("true");
+        let derivation = element.elements().find(|child| matches!(local_name(&child.name), "extension" | "restriction"))
+            .ok_or_else(|| anyhow!("<{}> in schema must have an extension or restriction", element.name))?;
+        let is_extension = local_name(&derivation.name) == "extension";
+        let base_name = derivation.attribute("base").ok_or_else(|| anyhow!("<{}> in schema must have a base", derivation.name))?;
+        let mut complex = self.complex_type_ref(base_name, depth)?;
+
+        let mut particle = None;
+        for child in derivation.elements() {
+            match local_name(&child.name) {
+                "sequence" | "choice" | "all" | "group" => particle = Some(self.particle(child, depth)?),
+                "attribute" | "attributeGroup" | "anyAttribute" => self.attribute_uses(child, &mut complex, depth)?,
+                _ => {}
+            }
+        }
+
+        if local_name(&element.name) == "simpleContent" {
+            let Content::Simple(base) = complex.content else { return Err(anyhow!("Base type {} of simple content must have simple content", base_name)) };
+            let simple = if is_extension { base } else { SimpleType::Restriction(Box::new(base), Box::new(facets(facet_values(derivation))?)) };
+            complex.content = Content::Simple(simple);
+            return Ok(complex);
+        }
+
+        let base_particle = match complex.content {
+            Content::Elements { particle, .. } if is_extension => particle,
+            _ => None,
+        };
+        let particle = match (base_particle, particle) {
+            (Some(base), Some(own)) => Some(Particle { term: Term::Sequence(vec![base, own]), min: 1, max: Some(1) }),
+            (base, own) => base.or(own),
+        };
+        complex.content = Content::Elements { particle, mixed };
+        Ok(complex)
+    }
+
+    fn attribute_uses(&self, element: &Element, complex: &mut ComplexType, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH { return Err(anyhow!("Attribute groups in schema are circular")); }
+
+        match local_name(&element.name) {
+            "anyAttribute" => complex.any_attribute = true,
+            "attributeGroup" => {
+                let name = element.attribute("ref").ok_or_else(|| anyhow!("<{}> in a type must have a ref", element.name))?;
+                for child in Self::definition(&self.attribute_groups, "attribute group", name)?.elements() { self.attribute_uses(child, complex, depth + 1)?; }
+            }
+            _ => {
+                // A reference takes the name and type from the top-level declaration and the use from the reference.
+                let declaration = match element.attribute("ref") {
+                    Some(name) => Self::definition(&self.attributes, "attribute", name)?,
+                    None => element,
+                };
+                let name = declaration.attribute("name").ok_or_else(|| anyhow!("<{}> in schema must have a name", element.name))?;
+                let simple = match declaration.attribute("type") {
+                    Some(type_name) => self.simple_type_ref(type_name, depth)?,
+                    None => self.inner_simple_type(declaration, depth)?.unwrap_or(SimpleType::Builtin("anySimpleType")),
+                };
+                let attribute = AttributeDecl {
+                    name: local_name(name).to_string(),
+                    simple,
+                    required: element.attribute("use") == Some("required"),
+                    prohibited: element.attribute("use") == Some("prohibited"),
+                    fixed: element.attribute("fixed").or(declaration.attribute("fixed")).map(str::to_string),
+                };
+                complex.attributes.retain(|existing| existing.name != attribute.name);
+                complex.attributes.push(attribute);
+            }
+        }
+        Ok(())
+    }
+
+    fn particle(&self, element: &Element, depth: usize) -> Result<Particle> {
+        if depth > MAX_DEPTH { return Err(anyhow!("Groups in schema are circular")); }
+        let occurs = |name: &str, default: usize| -> Result<usize> {
+            element.attribute(name).map_or(Ok(default), |value| value.trim().parse().map_err(|_| anyhow!("Invalid {} {} in schema", name, value)))
+        };
+        let min = occurs("minOccurs", 1)?;
+        let max = if element.attribute("maxOccurs") == Some("unbounded") { None } else { Some(occurs("maxOccurs", 1)?) };
+
+        let particles = |group: &Element| -> Result<Vec<Particle>> {
+            group.elements()
+                .filter(|child| matches!(local_name(&child.name), "element" | "sequence" | "choice" | "all" | "group" | "any"))
+                .map(|child| self.particle(child, depth + 1))
+                .collect()
+        };
+
+        let term = match local_name(&element.name) {
+            "element" => match element.attribute("ref") {
+                Some(name) => {
+                    Self::definition(&self.elements, "element", name)?;
+                    Term::ElementRef(local_name(name).to_string())
+                }
+                None => Term::Element(Box::new(self.element_decl(element, depth)?)),
+            },
+            "sequence" => Term::Sequence(particles(element)?),
+            "choice" => Term::Choice(particles(element)?),
+            "all" => Term::All(particles(element)?),
+            "group" => {
+                let name = element.attribute("ref").ok_or_else(|| anyhow!("<{}> in a type must have a ref", element.name))?;
+                let group = Self::definition(&self.groups, "group", name)?;
+                let model = group.elements().find(|child| matches!(local_name(&child.name), "sequence" | "choice" | "all"))
+                    .ok_or_else(|| anyhow!("Group {} in schema must have a sequence, choice or all", name))?;
+                self.particle(model, depth + 1)?.term
+            }
+            _ => Term::Any(match element.attribute("processContents") {
+                Some("skip") => Process::Skip,
+                Some("lax") => Process::Lax,
+                _ => Process::Strict,
+            }),
+        };
+        Ok(Particle { term, min, max })
+    }
+
+    fn element_decl(&self, element: &Element, depth: usize) -> Result<ElementDecl> {
+        let name = element.attribute("name").ok_or_else(|| anyhow!("<{}> in schema must have a name or ref", element.name))?;
+        let inner = element.elements().find(|child| matches!(local_name(&child.name), "complexType" | "simpleType"));
+
+        let type_ref = match (element.attribute("type"), inner) {
+            (Some(type_name), _) => self.type_ref(type_name, depth)?,
+            (None, Some(inner)) if local_name(&inner.name) == "complexType" => TypeRef::Complex(Box::new(self.complex_type(inner, depth + 1)?)),
+            (None, Some(inner)) => TypeRef::Simple(self.simple_type(inner, depth + 1)?),
+            (None, None) => TypeRef::Any,
+        };
+        Ok(ElementDecl { name: local_name(name).to_string(), type_ref, fixed: element.attribute("fixed").map(str::to_string) })
+    }
+
+    fn compile(self) -> Result<Schema> {
+        let mut schema = Schema { elements: HashMap::new(), types: HashMap::new() };
+        for (name, definition) in &self.types {
+            if local_name(&definition.name) == "complexType" { schema.types.insert(name.clone(), self.complex_type(definition, 0)?); }
+            else { self.simple_type(definition, 0)?; }
+        }
+        for (name, definition) in &self.elements { schema.elements.insert(name.clone(), self.element_decl(definition, 0)?); }
+        Ok(schema)
+    }
+}
+
+fn facet_values(restriction: &Element) -> Vec<(&str, &str)> {
+    restriction.elements().filter_map(|facet| Some((local_name(&facet.name), facet.attribute("value")?))).collect()
+}
+
+fn facets(values: Vec<(&str, &str)>) -> Result<Facets> {
+    let mut facets = Facets::default();
+    let mut patterns = vec![];
+
+    for (facet, value) in values {
+        let number = || value.trim().parse::<usize>().map_err(|_| anyhow!("Invalid value {} of <{}> in schema", value, facet));
+        match facet {
+            "enumeration" => facets.enumeration.push(value.to_string()),
+            "pattern" => patterns.push(value),
+            "minInclusive" => facets.min_inclusive = Some(value.trim().to_string()),
+            "maxInclusive" => facets.max_inclusive = Some(value.trim().to_string()),
+            "minExclusive" => facets.min_exclusive = Some(value.trim().to_string()),
+            "maxExclusive" => facets.max_exclusive = Some(value.trim().to_string()),
+            "length" => facets.length = Some(number()?),
+            "minLength" => facets.min_length = Some(number()?),
+            "maxLength" => facets.max_length = Some(number()?),
+            "totalDigits" => facets.total_digits = Some(number()?),
+            "fractionDigits" => facets.fraction_digits = Some(number()?),
+            _ => {}
+        }
+    }
+
+    // Patterns of one restriction are alternatives and always match the whole value.
+    if !patterns.is_empty() {
+        let pattern = format!("^(?:{})$", patterns.iter().map(|pattern| xsd_pattern(pattern)).collect::<Vec<_>>().join("|"));
+        let regex = Regex::new(&pattern).map_err(|error| anyhow!("Unsupported pattern in schema: {}", error))?;
+        facets.pattern = Some((regex, patterns.join(" | ")));
+    }
+    Ok(facets)
+}
+
+// Built-in type restricted by facets given as name and value, for schema languages that use the XML Schema datatypes.
+pub(crate) fn restricted_type(name: &str, values: Vec<(&str, &str)>) -> Result<SimpleType> {
+    let (name, _) = BUILTINS.get_key_value(name).ok_or_else(|| anyhow!("Unknown datatype {}", name))?;
+    if values.is_empty() { return Ok(SimpleType::Builtin(name)); }
+    Ok(SimpleType::Restriction(Box::new(SimpleType::Builtin(name)), Box::new(facets(values)?)))
+}
+
+// XML Schema patterns are close to the regex syntax, only the name character classes are translated.
+fn xsd_pattern(pattern: &str) -> String {
+    let pattern = pattern.replace(r"\i", r"[\p{L}_:]").replace(r"\I", r"[^\p{L}_:]");
+    let pattern = pattern.replace(r"\c", r"[\p{L}\p{N}._:\-]").replace(r"\C", r"[^\p{L}\p{N}._:\-]");
+    format!("(?:{})", pattern)
+}
+
+impl Schema {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Schema> {
+        let mut definitions = Definitions::default();
+        definitions.load_file(path.as_ref())?;
+        definitions.compile()
+    }
+
+    // Included schemas are resolved from the current directory.
+    pub fn parse(xsd: &str) -> Result<Schema> {
+        let mut definitions = Definitions::default();
+        definitions.load(parse_document(xsd)?, None)?;
+        definitions.compile()
+    }
+
+    pub fn validate(&self, input: &str) -> Result<Vec<Diagnostic>> {
+        Ok(self.validate_document(&parse_borrowed(input)?, input))
+    }
+
+    // Locations are the offsets in `document`, which must be parsed from `input`.
+    pub fn validate_document(&self, document: &borrowed::Document, input: &str) -> Vec<Diagnostic> {
+        let mut validator = Validator { schema: self, input, diagnostics: vec![] };
+        let root = &document.root;
+        match self.elements.get(local_name(&root.name)) {
+            Some(declaration) => validator.element(root, declaration),
+            None => validator.report(root.offset, format!("No declaration for root element <{}>", root.name)),
+        }
+        validator.diagnostics
+    }
+
+    // JSON Schema of the `start_parser` output for documents that are valid against the schema.
+    // Global elements and named complex types are `$defs`, so recursive declarations are references.
+    pub fn to_json_schema(&self) -> Value {
+        let mut definitions = Map::new();
+        let mut roots = Map::new();
+        for (name, declaration) in &self.elements {
+            definitions.insert(format!("element.{}", name), element_schema(declaration));
+            roots.insert(key_pattern(name), element_ref(name));
+        }
+        for (name, complex) in &self.types { definitions.insert(format!("type.{}", name), complex_schema(complex)); }
+
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "patternProperties": roots,
+            "additionalProperties": false,
+            "minProperties": 1,
+            "maxProperties": 1,
+            "$defs": definitions
+        })
+    }
+}
+
+fn element_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/$defs/element.{}", name) })
+}
+
+fn empty_object() -> Value {
+    json!({ "type": "object", "maxProperties": 0 })
+}
+
+fn element_schema(declaration: &ElementDecl) -> Value {
+    match (&declaration.type_ref, &declaration.fixed) {
+        (TypeRef::Simple(_), Some(fixed)) if fixed.trim().is_empty() => empty_object(),
+        (TypeRef::Simple(_), Some(fixed)) => json!({ "const": fixed.trim() }),
+        (TypeRef::Simple(simple), None) => text_schema(simple),
+        (TypeRef::Any, _) => json!({}),
+        (TypeRef::Named(name), _) => json!({ "$ref": format!("#/$defs/type.{}", name) }),
+        (TypeRef::Complex(complex), _) => complex_schema(complex),
+    }
+}
+
+// The converter trims element text and turns an element without text into an empty object.
+fn text_schema(simple: &SimpleType) -> Value {
+    let schema = simple_schema(simple);
+    if simple.check("").is_ok() { json!({ "anyOf": [schema, empty_object()] }) } else { schema }
+}
+
+// Values are always strings, so value ranges and digit counts are not part of the JSON Schema.
+fn simple_schema(simple: &SimpleType) -> Value {
+    match simple {
+        SimpleType::Builtin(name) if ANY_TEXT.contains(name) => json!({ "type": "string" }),
+        SimpleType::Builtin("boolean") => json!({ "type": "string", "enum": ["true", "false", "1", "0"] }),
+        SimpleType::Builtin(name) => json!({ "type": "string", "pattern": BUILTINS[name].as_str() }),
+        SimpleType::Restriction(base, facets) => {
+            if !facets.enumeration.is_empty() { return json!({ "type": "string", "enum": facets.enumeration }); }
+
+            let mut own = Map::new();
+            if let Some((pattern, _)) = &facets.pattern { own.insert("pattern".to_string(), json!(pattern.as_str())); }
+            if !simple.is_list() {
+                if let Some(min) = facets.length.or(facets.min_length) { own.insert("minLength".to_string(), json!(min)); }
+                if let Some(max) = facets.length.or(facets.max_length) { own.insert("maxLength".to_string(), json!(max)); }
+            }
+
+            let mut schema = simple_schema(base);
+            let Some(object) = schema.as_object_mut() else { return schema };
+            if own.is_empty() { return schema; }
+            if own.keys().any(|key| object.contains_key(key)) { return json!({ "allOf": [schema, own] }); }
+            object.extend(own);
+            schema
+        }
+        SimpleType::List(_) => json!({ "type": "string" }),
+        SimpleType::Union(members) => json!({ "anyOf": members.iter().map(simple_schema).collect::<Vec<_>>() }),
+    }
+}
+
+// The converter keeps the namespace prefixes of the document, which the schema does not know,
+// so keys are patterns that allow any prefix in front of the name, after the `_` of attributes.
+fn key_pattern(key: &str) -> String {
+    let (marker, name) = key.strip_prefix('_').map_or(("", key), |name| ("_", name));
+    format!("^{}([^:]+:)?{}$", marker, regex::escape(name))
+}
+
+// Most times each element name occurs in one match of the particle, `None` is unbounded.
+fn max_occurs(particle: &Particle) -> HashMap<&str, Option<usize>> {
+    let mut occurs = HashMap::new();
+    match &particle.term {
+        Term::Element(declaration) => { occurs.insert(declaration.name.as_str(), Some(1)); }
+        Term::ElementRef(name) => { occurs.insert(name.as_str(), Some(1)); }
+        Term::Sequence(particles) | Term::All(particles) => {
+            for (name, count) in particles.iter().flat_map(max_occurs) {
+                let total = occurs.entry(name).or_insert(Some(0));
+                *total = total.zip(count).map(|(total, count)| total + count);
+            }
+        }
+        Term::Choice(particles) => {
+            for (name, count) in particles.iter().flat_map(max_occurs) {
+                let most = occurs.entry(name).or_insert(Some(0));
+                *most = most.zip(count).map(|(most, count)| most.max(count));
+            }
+        }
+        Term::Any(_) => {}
+    }
+    for count in occurs.values_mut() { *count = count.zip(particle.max).map(|(count, max)| count * max); }
+    occurs
+}
+
+// Elements with text and no attributes are strings, anything else is an object with `_`-prefixed attributes, `_text` and children.
+// Elements that can occur more than once may be an array. Namespace declarations and `xsi:` attributes are allowed
+// because validation ignores them.
+fn complex_schema(complex: &ComplexType) -> Value {
+    let mut properties = Map::new();
+    let mut required = BTreeSet::new();
+    for attribute in complex.attributes.iter().filter(|attribute| !attribute.prohibited) {
+        let key = format!("_{}", attribute.name);
+        if attribute.required { required.insert(key.clone()); }
+        let schema = match &attribute.fixed {
+            Some(fixed) => json!({ "const": fixed }),
+            None => simple_schema(&attribute.simple),
+        };
+        properties.insert(key, schema);
+    }
+    let attributes_optional = required.is_empty();
+
+    let mut any_element = false;
+    let text = match &complex.content {
+        Content::Simple(simple) => {
+            if properties.is_empty() && !complex.any_attribute { return text_schema(simple); }
+            if simple.check("").is_err() { required.insert("_text".to_string()); }
+            properties.insert("_text".to_string(), simple_schema(simple));
+            attributes_optional.then(|| text_schema(simple))
+        }
+        Content::Elements { particle, mixed } => {
+            if let Some(particle) = particle {
+                let mut declarations = HashMap::new();
+                let mut wildcard = None;
+                collect_declarations(particle, &mut declarations, &mut wildcard);
+                let occurs = max_occurs(particle);
+                for (name, declaration) in declarations {
+                    let schema = match declaration {
+                        Declaration::Local(declaration) => element_schema(declaration),
+                        Declaration::Global(name) => element_ref(name),
+                    };
+                    properties.insert(name.to_string(), if occurs[name] == Some(1) { schema } else { repeated_schema(schema) });
+                }
+                required.extend(required_elements(particle));
+                any_element = wildcard.is_some();
+            }
+
+            // Text is only kept when there are no child elements.
+            if *mixed { properties.insert("_text".to_string(), json!({ "type": "string" })); }
+            (*mixed && required.is_empty()).then(|| json!({ "type": "string" }))
+        }
+    };
+
+    let own_text = properties.remove("_text");
+    let mut patterns: Map<_, _> = properties.into_iter().map(|(key, schema)| (key_pattern(&key), schema)).collect();
+    patterns.insert("^_xmlns(:|$)".to_string(), json!({ "type": "string" }));
+    patterns.insert("^_xsi:".to_string(), json!({ "type": "string" }));
+    if complex.any_attribute && !any_element { patterns.insert("^_".to_string(), json!({ "type": "string" })); }
+
+    let mut object = json!({ "type": "object", "patternProperties": patterns });
+    if let Some(own_text) = own_text { object["properties"] = json!({ "_text": own_text }); }
+    // `required` can not match prefixed keys, a required key is instead a property name that must match its pattern.
+    let (required_text, keys): (Vec<_>, Vec<_>) = required.into_iter().partition(|key| key == "_text");
+    if !required_text.is_empty() { object["required"] = json!(required_text); }
+    if !keys.is_empty() {
+        let present = keys.iter().map(|key| json!({ "not": { "propertyNames": { "not": { "pattern": key_pattern(key) } } } }));
+        object["allOf"] = present.collect();
+    }
+    if !any_element { object["additionalProperties"] = json!(false); }
+
+    match text {
+        Some(text) => json!({ "anyOf": [text, object] }),
+        None => object,
+    }
+}
+
+// Names of the elements that every match of the particle contains.
+fn required_elements(particle: &Particle) -> BTreeSet<String> {
+    if particle.min == 0 { return BTreeSet::new(); }
+    match &particle.term {
+        Term::Element(declaration) => BTreeSet::from([declaration.name.clone()]),
+        Term::ElementRef(name) => BTreeSet::from([name.clone()]),
+        Term::Sequence(particles) | Term::All(particles) => particles.iter().flat_map(required_elements).collect(),
+        Term::Choice(particles) => particles.iter().map(required_elements).reduce(|first, second| &first & &second).unwrap_or_default(),
+        Term::Any(_) => BTreeSet::new(),
+    }
+}
+
+struct Validator<'s, 'i> {
+    schema: &'s Schema,
+    input: &'i str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+fn is_reserved_attribute(name: &str) -> bool {
+    name == "xmlns" || name.starts_with("xmlns:") || name.starts_with("xsi:")
+}
+
+impl Validator<'_, '_> {
+    fn report(&mut self, offset: usize, message: String) {
+        self.diagnostics.push(Diagnostic::validity(self.input, offset, message));
+    }
+
+    fn element(&mut self, element: &borrowed::Element, declaration: &ElementDecl) {
+        match &declaration.type_ref {
+            TypeRef::Any => {}
+            TypeRef::Named(name) => {
+                let schema = self.schema;
+                self.complex(element, &schema.types[name]);
+            }
+            TypeRef::Simple(simple) => {
+                self.attributes(element, &ComplexType::default());
+                self.simple_content(element, simple);
+            }
+            TypeRef::Complex(complex) => self.complex(element, complex),
+        }
+
+        if let Some(fixed) = &declaration.fixed && element.text().trim() != fixed.trim() {
+            self.report(element.offset, format!("Element <{}> must have the fixed value {:?}", element.name, fixed));
+        }
+    }
+
+    fn simple_content(&mut self, element: &borrowed::Element, simple: &SimpleType) {
+        if let Some(child) = element.elements().next() {
+            self.report(child.offset, format!("Element <{}> can not contain elements, found <{}>", element.name, child.name));
+            return;
+        }
+        if let Err(message) = simple.check(&element.text()) { self.report(element.offset, format!("Element <{}>: {}", element.name, message)); }
+    }
+
+    fn attributes(&mut self, element: &borrowed::Element, complex: &ComplexType) {
+        for (name, value, offset) in element.attributes_with_offsets() {
+            if is_reserved_attribute(name) { continue; }
+            let declaration = complex.attributes.iter().find(|attribute| attribute.name == local_name(name));
+            match declaration {
+                Some(attribute) if attribute.prohibited => self.report(offset, format!("Attribute {} is prohibited on element <{}>", name, element.name)),
+                Some(attribute) => {
+                    if attribute.fixed.as_deref().is_some_and(|fixed| fixed != value) {
+                        self.report(offset, format!("Attribute {} must have the fixed value {:?}", name, attribute.fixed.as_deref().unwrap()));
+                    } else if let Err(message) = attribute.simple.check(value) {
+                        self.report(offset, format!("Attribute {}: {}", name, message));
+                    }
+                }
+                None if !complex.any_attribute => self.report(offset, format!("Attribute {} is not allowed on element <{}>", name, element.name)),
+                None => {}
+            }
+        }
+
+        for attribute in complex.attributes.iter().filter(|attribute| attribute.required) {
+            let present = element.attributes.iter().any(|(name, _)| local_name(name) == attribute.name);
+            if !present { self.report(element.offset, format!("Element <{}> is missing required attribute {}", element.name, attribute.name)); }
+        }
+    }
+
+    fn complex(&mut self, element: &borrowed::Element, complex: &ComplexType) {
+        self.attributes(element, complex);
+
+        let (particle, mixed) = match &complex.content {
+            Content::Simple(simple) => return self.simple_content(element, simple),
+            Content::Elements { particle, mixed } => (particle, *mixed),
+        };
+
+        if !mixed && !element.text().trim().is_empty() { self.report(element.offset, format!("Text is not allowed in element <{}>", element.name)); }
+
+        let children: Vec<_> = element.elements().collect();
+        let Some(particle) = particle else {
+            if let Some(child) = children.first() { self.report(child.offset, format!("Element <{}> must be empty, found <{}>", element.name, child.name)); }
+            return;
+        };
+
+        let names: Vec<_> = children.iter().map(|child| local_name(&child.name)).collect();
+        let mut matcher = Matcher { names: &names, furthest: 0, expected: BTreeSet::new() };
+        if !matcher.particle(particle, BTreeSet::from([0])).contains(&names.len()) {
+            let expected = matcher.expected.iter().map(|name| format!("<{}>", name)).collect::<Vec<_>>().join(", ");
+            match children.get(matcher.furthest) {
+                Some(child) => self.report(child.offset, format!("Element <{}> is not expected here, expected {}", child.name, expected)),
+                None => self.report(element.offset, format!("Element <{}> is incomplete, expected {}", element.name, expected)),
+            }
+        }
+
+        let mut declarations = HashMap::new();
+        let mut wildcard = None;
+        collect_declarations(particle, &mut declarations, &mut wildcard);
+
+        let schema = self.schema;
+        for child in children {
+            let name = local_name(&child.name);
+            match (declarations.get(name), wildcard) {
+                (Some(Declaration::Local(declaration)), _) => self.element(child, declaration),
+                (Some(Declaration::Global(name)), _) => self.element(child, &schema.elements[*name]),
+                (None, Some(Process::Skip)) | (None, None) => {}
+                (None, Some(process)) => match schema.elements.get(name) {
+                    Some(declaration) => self.element(child, declaration),
+                    None if process == Process::Strict => self.report(child.offset, format!("No declaration for element <{}>", child.name)),
+                    None => {}
+                },
+            }
+        }
+    }
+}
+
+enum Declaration<'s> {
+    Local(&'s ElementDecl),
+    Global(&'s str),
+}
+
+// Element declarations with the same name in one content model have the same type, so children are checked by name.
+fn collect_declarations<'s>(particle: &'s Particle, declarations: &mut HashMap<&'s str, Declaration<'s>>, wildcard: &mut Option<Process>) {
+    match &particle.term {
+        Term::Element(declaration) => { declarations.entry(&declaration.name).or_insert(Declaration::Local(declaration)); }
+        Term::ElementRef(name) => { declarations.entry(name).or_insert(Declaration::Global(name)); }
+        Term::Sequence(particles) | Term::Choice(particles) | Term::All(particles) => {
+            for particle in particles { collect_declarations(particle, declarations, wildcard); }
+        }
+        Term::Any(process) => { wildcard.get_or_insert(*process); }
+    }
+}
+
+// Matches child element names against a content model with sets of positions, so no backtracking is needed.
+// `furthest` is the last position that was tried and `expected` the names that were tried there.
+struct Matcher<'n> {
+    names: &'n [&'n str],
+    furthest: usize,
+    expected: BTreeSet<String>,
+}
+
+impl Matcher<'_> {
+    fn particle(&mut self, particle: &Particle, starts: BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut ends = if particle.min == 0 { starts.clone() } else { BTreeSet::new() };
+        let mut seen = BTreeSet::new();
+        let mut current = starts;
+        let mut count = 0;
+
+        while !current.is_empty() && particle.max.is_none_or(|max| count < max) {
+            let next = self.term(&particle.term, &current);
+            count += 1;
+            // Content that matches nothing new can be repeated up to the minimum without changing the positions.
+            if next == current && count < particle.min { count = particle.min; }
+            current = next;
+
+            if count >= particle.min {
+                current.retain(|position| seen.insert(*position));
+                ends.extend(current.iter().copied());
+            }
+        }
+        ends
+    }
+
+    fn tried(&mut self, position: usize, expected: &str) {
+        if position > self.furthest {
+            self.furthest = position;
+            self.expected.clear();
+        }
+        if position == self.furthest { self.expected.insert(expected.to_string()); }
+    }
+
+    fn element(&mut self, name: &str, starts: &BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut ends = BTreeSet::new();
+        for &position in starts {
+            self.tried(position, name);
+            if self.names.get(position) == Some(&name) { ends.insert(position + 1); }
+        }
+        ends
+    }
+
+    fn term(&mut self, term: &Term, starts: &BTreeSet<usize>) -> BTreeSet<usize> {
+        match term {
+            Term::Element(declaration) => self.element(&declaration.name, starts),
+            Term::ElementRef(name) => self.element(name, starts),
+            Term::Any(_) => {
+                for &position in starts { self.tried(position, "any element"); }
+                starts.iter().filter(|position| **position < self.names.len()).map(|position| position + 1).collect()
+            }
+            Term::Sequence(particles) => particles.iter().fold(starts.clone(), |positions, particle| self.particle(particle, positions)),
+            Term::Choice(particles) => {
+                let mut ends = BTreeSet::new();
+                for particle in particles { ends.extend(self.particle(particle, starts.clone())); }
+                ends
+            }
+            Term::All(particles) => starts.iter().filter_map(|&start| self.all(particles, start)).collect(),
+        }
+    }
+
+    // Members of `all` are elements that appear at most once in any order.
+    fn all(&mut self, particles: &[Particle], start: usize) -> Option<usize> {
+        let member_name = |particle: &Particle| match &particle.term {
+            Term::Element(declaration) => Some(declaration.name.clone()),
+            Term::ElementRef(name) => Some(name.clone()),
+            _ => None,
+        };
+        let names: Vec<_> = particles.iter().map(member_name).collect();
+        let mut used = vec![false; particles.len()];
+        let mut position = start;
+
+        loop {
+            for (name, _) in names.iter().zip(&used).filter(|(_, used)| !**used) {
+                if let Some(name) = name { self.tried(position, name); }
+            }
+            let member = names.iter().zip(&used).position(|(name, used)| !used && name.as_deref() == self.names.get(position).copied());
+            let Some(member) = member else { break };
+            used[member] = true;
+            position += 1;
+        }
+
+        particles.iter().zip(&used).all(|(particle, used)| *used || particle.min == 0).then_some(position)
+    }
+}
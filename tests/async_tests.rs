@@ -0,0 +1,128 @@
+#![cfg(feature = "async")]
+
+use XML_to_JSON_Parser::async_reader::*;
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+
+// Returns at most `chunk` bytes per read, so tags and records are split between reads.
+struct Chunked<'a> {
+    bytes: &'a [u8],
+    chunk: usize,
+}
+
+impl AsyncRead for Chunked<'_> {
+    fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        let length = self.chunk.min(self.bytes.len()).min(buf.remaining());
+        buf.put_slice(&self.bytes[..length]);
+        self.bytes = &self.bytes[length..];
+        Poll::Ready(Ok(()))
+    }
+}
+
+async fn records(input: &str, chunk: usize) -> Vec<anyhow::Result<Value>> {
+    parse_records(Chunked { bytes: input.as_bytes(), chunk }).collect().await
+}
+
+#[tokio::test]
+async fn parse_async_test() -> anyhow::Result<()> {
+    let test_text = r#"<parser><title id = "1">XML_to_JSON</title><author>Artur Nozhenko</author></parser>"#;
+    let value = parse_async(Chunked { bytes: test_text.as_bytes(), chunk: 3 }).await?;
+    assert_eq!(value, json!({ "parser": { "title": { "_id": "1", "_text": "XML_to_JSON" }, "author": "Artur Nozhenko" } }));
+
+    assert!(parse_async(Chunked { bytes: b"<parser><title>XML_to_JSON</qwerty></parser>", chunk: 3 }).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn parse_records_test() -> anyhow::Result<()> {
+    let test_text = "\u{feff}<?xml version=\"1.0\"?>
+<!-- books -->
+<!DOCTYPE books SYSTEM \"books.dtd\" [
+    <!-- <book> ] -->
+    <!ELEMENT books (book*)>
+    <!ATTLIST book note CDATA \"]>\">
+]>
+<books count=\"3\">
+    <book id=\"1\"><title>First</title></book>
+    <!-- <book id=\"skipped\"/> -->
+    <book id='2' note='a > b'><title>Second</title><author><name>Author</name></author></book>
+    <book id=\"3\"/>
+</books>
+<!-- end -->
+";
+    let expected = vec![
+        json!({ "book": { "_id": "1", "title": "First" } }),
+        json!({ "book": { "_id": "2", "_note": "a > b", "title": "Second", "author": { "name": "Author" } } }),
+        json!({ "book": { "_id": "3" } }),
+    ];
+
+    for chunk in [1, 2, 7, 1024] {
+        let values = records(test_text, chunk).await.into_iter().collect::<anyhow::Result<Vec<_>>>()?;
+        assert_eq!(values, expected);
+    }
+    assert!(records("<books/>", 1).await.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn records_are_yielded_before_end_of_input_test() -> anyhow::Result<()> {
+    let (mut writer, reader) = tokio::io::duplex(64);
+    let mut stream = pin!(parse_records(reader));
+
+    tokio::io::AsyncWriteExt::write_all(&mut writer, b"<books><book>First</book>").await?;
+    assert_eq!(stream.next().await.unwrap()?, json!({ "book": "First" }));
+
+    tokio::io::AsyncWriteExt::write_all(&mut writer, b"</books>").await?;
+    drop(writer);
+    assert!(stream.next().await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn parse_records_errors_test() {
+    let error = |input: &'static str| async move {
+        let mut results = records(input, 2).await;
+        assert!(results.last().unwrap().is_err());
+        results.pop().unwrap().unwrap_err().to_string()
+    };
+
+    assert_eq!(error("").await, "XML is empty");
+    assert_eq!(error("<books><book>First</book>").await, "Element <books> is not closed");
+    assert_eq!(error("<books><book>First</book></qwerty>").await, "There are different open and close tags names");
+    assert_eq!(error("<books></books><books/>").await, "Element <books> after the root element");
+    assert_eq!(error("text<books/>").await, "Text outside of the root element");
+    assert_eq!(error("<books><book>First</qwerty></book></books>").await, "There are different open and close tags names");
+    assert_eq!(error("<books><book").await, "Unexpected end of input");
+    assert_eq!(error("<!DOCTYPE books [ <!ELEMENT books ANY>").await, "Unexpected end of input");
+    assert_eq!(error("<books><!DOCTYPE books></books>").await, "Document type declaration after the start of the root element");
+    assert_eq!(error("<books><1a/></books>").await, "Invalid tag name \"1a\"");
+    assert_eq!(error("<books></ ></books>").await, "Invalid tag name \"\"");
+
+    // Records before the error are still yielded, the stream ends with the error.
+    let results = records("<books><book>First</book><book>Second</qwerty></book><book>Third</book></books>", 2).await;
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap(), &json!({ "book": "First" }));
+}
+
+#[tokio::test]
+async fn parse_records_encodings_test() -> anyhow::Result<()> {
+    let expected = vec![json!({ "book": "Café" }), json!({ "book": { "_lang": "ü", "_text": "Second" } })];
+
+    let mut utf16: Vec<u8> = vec![0xFF, 0xFE];
+    utf16.extend("<books><book>Café</book><book lang=\"ü\">Second</book></books>".encode_utf16().flat_map(u16::to_le_bytes));
+    let latin1 = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><books><book>Caf\xE9</book><book lang=\"\xFC\">Second</book></books>";
+
+    for bytes in [utf16.as_slice(), latin1.as_slice()] {
+        for chunk in [1, 3, 1024] {
+            let results: Vec<anyhow::Result<Value>> = parse_records(Chunked { bytes, chunk }).collect().await;
+            assert_eq!(results.into_iter().collect::<anyhow::Result<Vec<_>>>()?, expected);
+        }
+    }
+
+    let results: Vec<anyhow::Result<Value>> = parse_records(Chunked { bytes: b"<books><book>Caf\xE9</book></books>", chunk: 4 }).collect().await;
+    assert_eq!(results.last().unwrap().as_ref().unwrap_err().to_string(), "Input is not valid UTF-8");
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use XML_to_JSON_Parser::borrowed::*;
+use XML_to_JSON_Parser::escape::unescape;
+use std::borrow::Cow;
+
+fn is_borrowed(text: Cow<str>) -> bool {
+    matches!(text, Cow::Borrowed(_))
+}
+
+#[test]
+fn borrows_from_input_test() -> anyhow::Result<()> {
+    let test_text = r#"<?xml version="1.0"?>
+<!-- prolog -->
+<parser>
+    <title id = "1">XML_to_JSON</title>
+    <author>Artur Nozhenko</author>
+</parser>"#;
+
+    let document = parse_borrowed(test_text)?;
+    assert_eq!(document.declaration.as_deref(), Some(r#"<?xml version="1.0"?>"#));
+    assert_eq!(document.prolog, vec![Node::Comment(Cow::Borrowed(" prolog "))]);
+
+    let root = &document.root;
+    assert!(is_borrowed(root.name.clone()));
+    let title = root.elements().next().unwrap();
+    assert_eq!(title.attribute("id"), Some("1"));
+    assert!(is_borrowed(title.attributes[0].0.clone()) && is_borrowed(title.attributes[0].1.clone()));
+    assert!(is_borrowed(title.text()));
+    assert_eq!(title.text(), "XML_to_JSON");
+
+    // Every string points into the input.
+    let input_range = test_text.as_bytes().as_ptr_range();
+    assert!(input_range.contains(&title.name.as_ptr()));
+    Ok(())
+}
+
+#[test]
+fn entities_are_decoded_test() -> anyhow::Result<()> {
+    let document = parse_borrowed(r#"<parser value="a &amp; b" other="&unknown;">1 &lt; 2</parser>"#)?;
+    let root = &document.root;
+
+    assert_eq!(root.attributes[0].1, Cow::<str>::Owned("a & b".to_string()));
+    assert!(!is_borrowed(root.attributes[0].1.clone()));
+    assert!(is_borrowed(root.attributes[1].1.clone()));
+    assert_eq!(root.text(), "1 < 2");
+    assert!(is_borrowed(unescape("no entities")));
+    Ok(())
+}
+
+#[test]
+fn keeps_text_and_comments_test() -> anyhow::Result<()> {
+    let document = parse_borrowed("<parser>first<!-- comment --><item/> second </parser>")?;
+    let root = &document.root;
+
+    assert_eq!(root.children.len(), 4);
+    assert_eq!(root.children[1], Node::Comment(Cow::Borrowed(" comment ")));
+    assert_eq!(root.text(), "first second ");
+    assert!(!is_borrowed(root.text()));
+    Ok(())
+}
+
+#[test]
+fn into_owned_test() -> anyhow::Result<()> {
+    let owned = {
+        let input = String::from("<parser><title>XML_to_JSON</title></parser>");
+        parse_borrowed(&input)?.into_owned()
+    };
+    assert_eq!(owned.root.elements().next().unwrap().text(), "XML_to_JSON");
+    Ok(())
+}
+
+#[test]
+fn errors_test() {
+    assert!(parse_borrowed("").is_err());
+    assert!(parse_borrowed("<parser><title>XML_to_JSON</qwerty></parser>").is_err());
+}
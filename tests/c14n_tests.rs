@@ -0,0 +1,69 @@
+use XML_to_JSON_Parser::c14n::*;
+
+#[test]
+fn expands_empty_elements_test() -> anyhow::Result<()> {
+    let test_text = r#"<doc><e1   /><e2   ></e2   ><e3 name = "elem3" id="elem3" /></doc>"#;
+    let expected = r#"<doc><e1></e1><e2></e2><e3 id="elem3" name="elem3"></e3></doc>"#;
+    assert_eq!(canonicalize(test_text, false)?, expected);
+    Ok(())
+}
+
+#[test]
+fn sorts_attributes_by_namespace_test() -> anyhow::Result<()> {
+    let test_text = r#"<doc xmlns:b="http://www.ietf.org" xmlns:a="http://www.w3.org" xmlns="http://example.org"><e5 a:attr="out" b:attr="sorted" attr2="all" attr="I'm"></e5></doc>"#;
+    let expected = r#"<doc xmlns="http://example.org" xmlns:a="http://www.w3.org" xmlns:b="http://www.ietf.org"><e5 attr="I'm" attr2="all" b:attr="sorted" a:attr="out"></e5></doc>"#;
+    assert_eq!(canonicalize(test_text, false)?, expected);
+    Ok(())
+}
+
+#[test]
+fn removes_superfluous_namespace_declarations_test() -> anyhow::Result<()> {
+    let test_text = r#"<doc xmlns:a="http://a"><e6 xmlns=""><e7 xmlns:a="http://a" xmlns="http://b"><e8 xmlns="http://b"></e8></e7></e6></doc>"#;
+    let expected = r#"<doc xmlns:a="http://a"><e6><e7 xmlns="http://b"><e8></e8></e7></e6></doc>"#;
+    assert_eq!(canonicalize(test_text, false)?, expected);
+    Ok(())
+}
+
+#[test]
+fn normalizes_line_endings_and_escapes_text_test() -> anyhow::Result<()> {
+    let test_text = "<doc>\r\n  <text>First line&#x0d;&#10;Second &quot;line&quot; &amp; &gt; more</text>\r\n</doc>";
+    let expected = "<doc>\n  <text>First line&#xD;\nSecond \"line\" &amp; &gt; more</text>\n</doc>";
+    assert_eq!(canonicalize(test_text, false)?, expected);
+    Ok(())
+}
+
+#[test]
+fn normalizes_attribute_values_test() -> anyhow::Result<()> {
+    let test_text = "<doc value='a\"b\n c&#9;&lt;'></doc>";
+    let expected = "<doc value=\"a&quot;b  c&#x9;&lt;\"></doc>";
+    assert_eq!(canonicalize(test_text, false)?, expected);
+    Ok(())
+}
+
+#[test]
+fn comments_test() -> anyhow::Result<()> {
+    let test_text = "<!--before-->\n<doc><!--inside-->text</doc>\n<!--after-->";
+    assert_eq!(canonicalize(test_text, false)?, "<doc>text</doc>");
+    assert_eq!(canonicalize(test_text, true)?, "<!--before-->\n<doc><!--inside-->text</doc>\n<!--after-->");
+    Ok(())
+}
+
+#[test]
+fn undeclared_prefix_test() {
+    let test_text = r#"<doc a:attr="1"></doc>"#;
+    assert!(canonicalize(test_text, false).is_err());
+
+    assert_eq!(canonicalize("<a:doc><a:b/></a:doc>", false).unwrap_err().to_string(), "Namespace prefix a is not declared");
+    assert_eq!(canonicalize(r#"<a:doc xmlns:a="urn:a"><a:b/></a:doc>"#, false).unwrap(), r#"<a:doc xmlns:a="urn:a"><a:b></a:b></a:doc>"#);
+}
+
+#[test]
+fn unknown_entity_test() {
+    assert_eq!(canonicalize("<doc>&foo; &amp;</doc>", false).unwrap_err().to_string(), "Unknown entity reference &foo;");
+    assert_eq!(canonicalize(r#"<doc a="x & y"/>"#, false).unwrap_err().to_string(), "Unknown entity reference &");
+}
+
+#[test]
+fn different_tags_test() {
+    assert!(canonicalize("<doc></qwerty>", false).is_err());
+}
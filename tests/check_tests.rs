@@ -0,0 +1,56 @@
+use XML_to_JSON_Parser::check::*;
+
+#[test]
+fn valid_xml_has_no_diagnostics() {
+    let test_text = r#"
+     <parser>
+        <title id = "1">XML_to_JSON</title>
+        <author>Artur Nozhenko</author>
+    </parser>
+    "#;
+
+    assert!(check(test_text).is_empty());
+}
+
+#[test]
+fn empty_text_is_syntax_error() {
+    let diagnostics = check("   ");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::Syntax);
+}
+
+#[test]
+fn syntax_error_reports_position() {
+    let test_text = "<parser>\n    <title id = 1>XML_to_JSON</title>\n</parser>";
+    let diagnostics = check(test_text);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::Syntax);
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 12));
+}
+
+#[test]
+fn reports_all_mismatched_tags() {
+    let test_text = "<parser>\n    <title>XML_to_JSON</qwerty>\n    <author>Artur Nozhenko</qwerty>\n</parser>";
+    let diagnostics = check(test_text);
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics.iter().all(|d| d.kind == DiagnosticKind::Structure));
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 23));
+    assert_eq!((diagnostics[1].line, diagnostics[1].column), (3, 27));
+}
+
+#[test]
+fn diagnostic_to_json_test() {
+    let diagnostics = check("<parser></qwerty>");
+    let json = diagnostics[0].to_json("xml.xml");
+    assert_eq!(json["file"], "xml.xml");
+    assert_eq!(json["line"], 1);
+    assert_eq!(json["column"], 9);
+    assert_eq!(json["kind"], "structure");
+}
+
+#[test]
+fn syntax_and_structure_exit_codes_differ() {
+    assert_ne!(DiagnosticKind::Syntax.exit_code(), DiagnosticKind::Structure.exit_code());
+    assert_ne!(DiagnosticKind::Syntax.exit_code(), 0);
+    assert_ne!(DiagnosticKind::Structure.exit_code(), 0);
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "cli")]
+
+use XML_to_JSON_Parser::output::to_json_string;
+use XML_to_JSON_Parser::{drop_value, start_parser};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn write_file(name: &str, contents: &str) -> anyhow::Result<PathBuf> {
+    let directory = std::env::temp_dir().join("xml_to_json_parser_cli_test");
+    fs::create_dir_all(&directory)?;
+    let path = directory.join(name);
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+fn run(args: &[&str]) -> anyhow::Result<Output> {
+    Ok(Command::new(env!("CARGO_BIN_EXE_XML_to_JSON_Parser")).args(args).output()?)
+}
+
+fn deep_document(depth: usize, leaf: &str) -> String {
+    format!("{}{}{}", "<level>".repeat(depth), leaf, "</level>".repeat(depth))
+}
+
+#[test]
+fn parse_very_deep_document_test() -> anyhow::Result<()> {
+    let depth = 2000;
+    let path = write_file("deep.xml", &deep_document(depth, "XML_to_JSON"))?;
+
+    let output = run(&["parse", path.to_str().unwrap()])?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let json = start_parser(&deep_document(depth, "XML_to_JSON"))?;
+    assert!(String::from_utf8(output.stdout)? == to_json_string(&json, true) + "\n");
+    drop_value(json);
+
+    let output = run(&["parse", "--to", "yaml", path.to_str().unwrap()])?;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.contains("YAML output supports at most 1024 nested levels"));
+    Ok(())
+}
+
+// Commands built on the pest pair tree refuse documents deeper than pest can parse, `check` and JSON Patch handle them.
+#[test]
+fn very_deep_document_commands_test() -> anyhow::Result<()> {
+    let depth = 2000;
+    let deep = write_file("deep_commands.xml", &deep_document(depth, "XML_to_JSON"))?;
+    let changed = write_file("deep_changed.xml", &deep_document(depth, "JSON_to_XML"))?;
+    let broken = write_file("deep_broken.xml", &deep_document(depth, "<1leaf/>"))?;
+    let dtd = write_file("deep.dtd", "<!ELEMENT level ANY>")?;
+    let source_map = std::env::temp_dir().join("xml_to_json_parser_cli_test").join("deep.map.json");
+    let (deep, changed, broken, dtd, source_map) = (deep.to_str().unwrap(), changed.to_str().unwrap(), broken.to_str().unwrap(), dtd.to_str().unwrap(), source_map.to_str().unwrap());
+
+    assert_eq!(run(&["check", deep])?.status.code(), Some(0));
+    let output = run(&["check", broken])?;
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)?.contains(&format!(":1:{}: ", depth * "<level>".len() + 2)));
+
+    let refused: [&[&str]; 7] = [
+        &["validate", "--dtd", dtd, deep],
+        &["fmt", "--check", deep],
+        &["c14n", deep],
+        &["diff", deep, changed],
+        &["infer-schema", deep],
+        &["codegen", "rust", deep],
+        &["parse", "--source-map", source_map, deep],
+    ];
+    for args in refused {
+        let output = run(args)?;
+        assert!(matches!(output.status.code(), Some(1 | 2)), "{:?}", args);
+        assert!(String::from_utf8(output.stderr)?.contains("Document is nested deeper than 256 levels"), "{:?}", args);
+    }
+
+    let output = run(&["diff", "--format", "json-patch", deep, changed])?;
+    assert_eq!(output.status.code(), Some(1));
+    let patch: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(patch[0]["op"], "replace");
+    assert_eq!(patch[0]["value"], "JSON_to_XML");
+    assert_eq!(patch[0]["path"].as_str().unwrap().len(), depth * "/level".len());
+    Ok(())
+}
+
+#[test]
+fn parse_with_and_without_dtd_test() -> anyhow::Result<()> {
+    write_file("item.dtd", "<!ATTLIST item currency CDATA 'EUR' sale CDATA 'no'>")?;
+    let dtd = write_file("extra.dtd", "<!ATTLIST item currency CDATA 'USD' sale CDATA 'no'>")?;
+    let input = write_file("item.xml", "<!DOCTYPE item SYSTEM \"item.dtd\" [<!ATTLIST item sale CDATA 'yes'>]><item>10</item>")?;
+
+    // The system identifier is followed like in validate, `--dtd` takes its place.
+    let output = run(&["parse", input.to_str().unwrap()])?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&output.stdout)?, json!({ "item": { "_currency": "EUR", "_sale": "yes", "_text": "10" } }));
+
+    let output = run(&["parse", "--dtd", dtd.to_str().unwrap(), input.to_str().unwrap()])?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&output.stdout)?, json!({ "item": { "_currency": "USD", "_sale": "yes", "_text": "10" } }));
+
+    let source_map = std::env::temp_dir().join("xml_to_json_parser_cli_test").join("item.map.json");
+    for mode in [&["--recover"][..], &["--lenient"], &["--source-map", source_map.to_str().unwrap()]] {
+        let output = run(&[&["parse", "--dtd", dtd.to_str().unwrap()], mode, &[input.to_str().unwrap()]].concat())?;
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&output.stdout)?, json!({ "item": { "_currency": "USD", "_sale": "yes", "_text": "10" } }));
+    }
+    Ok(())
+}
+
+#[test]
+fn validate_with_doctype_system_id_test() -> anyhow::Result<()> {
+    write_file("letter.dtd", "<!ELEMENT letter (to, body)>\n<!ELEMENT to (#PCDATA)>\n<!ELEMENT body (#PCDATA)>")?;
+    let valid = write_file("letter.xml", "<!DOCTYPE letter SYSTEM \"letter.dtd\">\n<letter><to>A</to><body>B</body></letter>")?;
+    let invalid = write_file("letter_invalid.xml", "<!DOCTYPE letter SYSTEM \"letter.dtd\">\n<letter><body>B</body></letter>")?;
+
+    // The system identifier is resolved relative to the document, not to the working directory.
+    let output = run(&["validate", valid.to_str().unwrap()])?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = run(&["validate", invalid.to_str().unwrap()])?;
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains(":2:1: ") && !stderr.contains("is not declared"), "{}", stderr);
+
+    let missing = write_file("missing.xml", "<!DOCTYPE letter SYSTEM \"missing.dtd\"><letter/>")?;
+    let output = run(&["validate", missing.to_str().unwrap()])?;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.contains("Error reading DTD"));
+    Ok(())
+}
+
+#[test]
+fn diff_exit_codes_test() -> anyhow::Result<()> {
+    let old = write_file("old.xml", "<parser><item>a</item></parser>")?;
+    let new = write_file("new.xml", "<parser><item>b</item></parser>")?;
+    let broken = write_file("broken.xml", "<parser><item>a</qwerty></parser>")?;
+
+    assert_eq!(run(&["diff", old.to_str().unwrap(), old.to_str().unwrap()])?.status.code(), Some(0));
+    assert_eq!(run(&["diff", old.to_str().unwrap(), new.to_str().unwrap()])?.status.code(), Some(1));
+    assert_eq!(run(&["diff", old.to_str().unwrap(), broken.to_str().unwrap()])?.status.code(), Some(2));
+    assert_eq!(run(&["diff", "--format", "json-patch", old.to_str().unwrap(), "missing.xml"])?.status.code(), Some(2));
+    Ok(())
+}
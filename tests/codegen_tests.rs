@@ -0,0 +1,140 @@
+use XML_to_JSON_Parser::codegen::*;
+use XML_to_JSON_Parser::de::from_str;
+use XML_to_JSON_Parser::schema::Shape;
+use XML_to_JSON_Parser::tree::parse_document;
+
+fn shape(inputs: &[&str]) -> anyhow::Result<Shape> {
+    let documents = inputs.iter().map(|input| parse_document(input)).collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Shape::from_documents(&documents))
+}
+
+const SAMPLES: [&str; 2] = [
+    r#"<feed version="1"><item id="1" type="new"><price>10</price><tag>a</tag><tag>b</tag></item><empty/></feed>"#,
+    r#"<feed version="2"><item id="2" on-sale="true"><price>9.5</price><title lang="en">First</title></item><empty/><note>text</note></feed>"#,
+];
+
+// Output of `rust_types` for SAMPLES, checked below and compiled here.
+mod generated {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Feed {
+        #[serde(rename = "_version")]
+        pub version: i64,
+        pub empty: Empty,
+        pub item: Item,
+        pub note: Option<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Empty {}
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Item {
+        #[serde(rename = "_id")]
+        pub id: i64,
+        #[serde(rename = "_on-sale")]
+        pub on_sale: Option<bool>,
+        #[serde(rename = "_type")]
+        pub r#type: Option<String>,
+        pub price: f64,
+        #[serde(default)]
+        pub tag: Vec<String>,
+        pub title: Option<Title>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Title {
+        #[serde(rename = "_lang")]
+        pub lang: String,
+        #[serde(rename = "_text")]
+        pub text: String,
+    }
+}
+
+#[test]
+fn rust_types_test() -> anyhow::Result<()> {
+    let source = std::fs::read_to_string("tests/codegen_tests.rs")?;
+    let start = source.find("    use serde::").unwrap();
+    let end = source.find("\n}\n\n#[test]").unwrap();
+    let expected = source[start..end].lines().map(|line| line.strip_prefix("    ").unwrap_or(line)).collect::<Vec<_>>().join("\n");
+
+    assert_eq!(rust_types(&shape(&SAMPLES)?).trim_end(), expected);
+    Ok(())
+}
+
+#[test]
+fn generated_types_read_samples_test() -> anyhow::Result<()> {
+    let first: generated::Feed = from_str(SAMPLES[0])?;
+    assert_eq!(first.item.tag, vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(first.item.r#type.as_deref(), Some("new"));
+
+    let second: generated::Feed = from_str(SAMPLES[1])?;
+    assert_eq!(second.item.on_sale, Some(true));
+    assert_eq!(second.item.title.unwrap().text, "First");
+    assert_eq!(second.note.as_deref(), Some("text"));
+    Ok(())
+}
+
+#[test]
+fn sanitizes_names_test() -> anyhow::Result<()> {
+    let code = rust_types(&shape(&[r#"<self fooBar="1" foo-bar="2"><type>a</type><loop><String>b</String></loop><Self/><item/><a><item/></a><b><item x="1"/></b></self>"#])?);
+
+    assert!(code.contains("pub struct Self_ {"));
+    assert!(code.contains("    #[serde(rename = \"_foo-bar\")]\n    pub foo_bar: i64,\n    #[serde(rename = \"_fooBar\")]\n    pub foo_bar2: i64,"));
+    assert!(code.contains("    #[serde(rename = \"Self\")]\n    pub self_: SelfSelf,"));
+    assert!(code.contains("    pub r#type: String,"));
+    assert!(code.contains("    pub r#loop: Loop,"));
+    assert!(code.contains("    #[serde(rename = \"String\")]\n    pub string: String,"));
+    // Elements with the same name deeper in the document get the parent name.
+    assert!(code.contains("    pub item: Item,") && code.contains("pub struct Item {}"));
+    assert!(code.contains("pub struct AItem {}") && code.contains("pub struct BItem {"));
+    Ok(())
+}
+
+#[test]
+fn typescript_types_test() -> anyhow::Result<()> {
+    let expected = r#"export interface ParsedDocument {
+    feed: Feed;
+}
+
+export interface Feed {
+    _version: string;
+    empty: Record<string, never>;
+    item: Item;
+    note?: string;
+}
+
+export interface Item {
+    _id: string;
+    "_on-sale"?: "true" | "false";
+    _type?: string;
+    price: string;
+    tag?: string | string[];
+    title?: Title;
+}
+
+export interface Title {
+    _lang: string;
+    _text: string;
+}
+"#;
+    assert_eq!(typescript_types(&shape(&SAMPLES)?), expected);
+    Ok(())
+}
+
+#[test]
+fn typescript_string_or_object_test() -> anyhow::Result<()> {
+    let code = typescript_types(&shape(&[
+        "<list><entry>text</entry><flag/><Object/></list>",
+        "<list><entry><name>a</name></entry><flag>true</flag><Object><x>1</x></Object></list>",
+        "<list><entry>a</entry><entry><name>b</name></entry><flag/><Object/></list>",
+    ])?);
+
+    assert!(code.contains("    entry: string | Entry | (string | Entry)[];\n"));
+    assert!(code.contains("    flag: \"true\" | \"false\" | Record<string, never>;\n"));
+    // An empty element is valid for an interface where every member is optional.
+    assert!(code.contains("    Object: Object_;\n") && code.contains("export interface Object_ {\n    x?: string;\n}\n"));
+    assert!(code.contains("export interface Entry {\n    name: string;\n}\n"));
+    Ok(())
+}
@@ -0,0 +1,5 @@
+use XML_to_JSON_Parser::check::Diagnostic;
+
+pub fn messages(diagnostics: &[Diagnostic]) -> Vec<(usize, usize, &str)> {
+    diagnostics.iter().map(|diagnostic| (diagnostic.line, diagnostic.column, diagnostic.message.as_str())).collect()
+}
@@ -0,0 +1,163 @@
+use XML_to_JSON_Parser::de::from_str;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Parser {
+    #[serde(rename = "_id")]
+    id: u32,
+    title: Title,
+    author: String,
+    #[serde(default)]
+    tag: Vec<String>,
+    year: Option<u16>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Title {
+    #[serde(rename = "_lang")]
+    lang: String,
+    #[serde(rename = "_text")]
+    text: String,
+}
+
+#[test]
+fn basic_struct_test() -> anyhow::Result<()> {
+    let test_text = r#"
+     <parser id = "1">
+        <title lang = "en">XML_to_JSON</title>
+        <author>Artur Nozhenko</author>
+        <tag>xml</tag>
+        <tag>json</tag>
+    </parser>
+    "#;
+
+    let parsed: Parser = from_str(test_text)?;
+    let expected = Parser {
+        id: 1,
+        title: Title { lang: "en".to_string(), text: "XML_to_JSON".to_string() },
+        author: "Artur Nozhenko".to_string(),
+        tag: vec!["xml".to_string(), "json".to_string()],
+        year: None,
+    };
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn scalars_test() -> anyhow::Result<()> {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Scalars {
+        #[serde(rename = "_enabled")]
+        enabled: bool,
+        count: i64,
+        ratio: f64,
+        letter: char,
+    }
+
+    let parsed: Scalars = from_str("<s enabled='true'><count> -5 </count><ratio>0.5</ratio><letter>x</letter></s>")?;
+    assert_eq!(parsed, Scalars { enabled: true, count: -5, ratio: 0.5, letter: 'x' });
+    Ok(())
+}
+
+#[test]
+fn invalid_number_test() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Count {
+        count: u8,
+    }
+
+    let error = from_str::<Count>("<s><count>many</count></s>").unwrap_err();
+    assert_eq!(error.to_string(), "Cannot parse \"many\" as u8");
+}
+
+#[test]
+fn entities_are_decoded_test() -> anyhow::Result<()> {
+    let parsed: HashMap<String, String> = from_str("<s a='&lt;1&gt;'><b>x &amp; y</b></s>")?;
+    assert_eq!(parsed["_a"], "<1>");
+    assert_eq!(parsed["b"], "x & y");
+    Ok(())
+}
+
+#[test]
+fn empty_text_field_test() -> anyhow::Result<()> {
+    let parsed: Title = from_str("<title lang='en'/>")?;
+    assert_eq!(parsed, Title { lang: "en".to_string(), text: String::new() });
+    Ok(())
+}
+
+#[test]
+fn repeated_element_for_single_value_test() {
+    assert!(from_str::<Parser>("<parser id='1'><title lang='en'>t</title><author>a</author><author>b</author></parser>").is_err());
+}
+
+#[test]
+fn root_sequence_test() -> anyhow::Result<()> {
+    let parsed: Vec<u32> = from_str("<numbers><n>1</n><n>2</n><n>3</n></numbers>")?;
+    assert_eq!(parsed, vec![1, 2, 3]);
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Shape {
+    Circle {
+        #[serde(rename = "_r")]
+        r: f32,
+    },
+    Square(String),
+    Point,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Color {
+    Red,
+    Green,
+}
+
+#[test]
+fn enums_from_element_names_test() -> anyhow::Result<()> {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Shapes(Vec<Shape>);
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Drawing {
+        main: Shape,
+        color: Color,
+        layer: Vec<Shape>,
+        shapes: Shapes,
+    }
+
+    let test_text = r#"
+    <drawing>
+        <main><circle r="2.5"/></main>
+        <color>green</color>
+        <layer><point/></layer>
+        <layer><square>small</square></layer>
+        <shapes><square>big</square><point/><circle r="1"/></shapes>
+    </drawing>
+    "#;
+
+    let parsed: Drawing = from_str(test_text)?;
+    let expected = Drawing {
+        main: Shape::Circle { r: 2.5 },
+        color: Color::Green,
+        layer: vec![Shape::Point, Shape::Square("small".to_string())],
+        shapes: Shapes(vec![Shape::Square("big".to_string()), Shape::Point, Shape::Circle { r: 1.0 }]),
+    };
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn root_enum_test() -> anyhow::Result<()> {
+    assert_eq!(from_str::<Color>("<red></red>")?, Color::Red);
+    Ok(())
+}
+
+#[test]
+fn invalid_xml_test() {
+    assert!(from_str::<Title>("<title></qwerty>").is_err());
+}
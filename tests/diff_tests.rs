@@ -0,0 +1,97 @@
+use XML_to_JSON_Parser::diff::*;
+use XML_to_JSON_Parser::start_parser;
+use XML_to_JSON_Parser::tree::*;
+use serde_json::json;
+
+fn diff(old: &str, new: &str) -> anyhow::Result<Vec<Change>> {
+    Ok(diff_documents(&parse_document(old)?, &parse_document(new)?))
+}
+
+#[test]
+fn equal_documents_test() -> anyhow::Result<()> {
+    let old = r#"<parser id="1" author="Artur"><title>XML_to_JSON</title></parser>"#;
+    let new = "<parser author = 'Artur'   id = \"1\">\n    <title>  XML_to_JSON\n</title>\n</parser>";
+    assert!(diff(old, new)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn attribute_changes_test() -> anyhow::Result<()> {
+    let changes = diff(r#"<parser id="1" v="2"></parser>"#, r#"<parser id="2" x="3"></parser>"#)?;
+    assert_eq!(changes, vec![
+        Change::Changed { path: "/parser/@id".to_string(), old: "\"1\"".to_string(), new: "\"2\"".to_string() },
+        Change::Removed { path: "/parser/@v".to_string(), value: "\"2\"".to_string() },
+        Change::Added { path: "/parser/@x".to_string(), value: "\"3\"".to_string() },
+    ]);
+    Ok(())
+}
+
+#[test]
+fn element_and_text_changes_test() -> anyhow::Result<()> {
+    let old = "<parser><title>XML_to_JSON</title><item>a</item></parser>";
+    let new = "<parser><title>JSON_to_XML</title><item>a</item><item>b</item><author>Artur</author></parser>";
+    let changes = diff(old, new)?;
+    let lines: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+    assert_eq!(lines, vec![
+        "~ /parser/title/text(): \"XML_to_JSON\" -> \"JSON_to_XML\"",
+        "+ /parser/item[2]: \"b\"",
+        "+ /parser/author: \"Artur\"",
+    ]);
+    Ok(())
+}
+
+#[test]
+fn removed_element_test() -> anyhow::Result<()> {
+    let changes = diff("<parser><title id=\"1\">t</title></parser>", "<parser></parser>")?;
+    assert_eq!(changes, vec![Change::Removed { path: "/parser/title".to_string(), value: r#"{"_id":"1","_text":"t"}"#.to_string() }]);
+    Ok(())
+}
+
+#[test]
+fn different_roots_test() -> anyhow::Result<()> {
+    let changes = diff("<parser></parser>", "<other></other>")?;
+    assert_eq!(changes.len(), 2);
+    assert!(matches!(&changes[0], Change::Removed { path, .. } if path == "/parser"));
+    assert!(matches!(&changes[1], Change::Added { path, .. } if path == "/other"));
+    Ok(())
+}
+
+#[test]
+fn json_patch_test() -> anyhow::Result<()> {
+    let old = start_parser(r#"<parser id="1" v="2"><title>XML_to_JSON</title></parser>"#)?;
+    let new = start_parser(r#"<parser v="2" x="a/b"><title>JSON_to_XML</title></parser>"#)?;
+    let expected = json!([
+        { "op": "remove", "path": "/parser/_id" },
+        { "op": "replace", "path": "/parser/title", "value": "JSON_to_XML" },
+        { "op": "add", "path": "/parser/_x", "value": "a/b" },
+    ]);
+    assert_eq!(json_patch(&old, &new), expected);
+    assert_eq!(json_patch(&old, &old), json!([]));
+    Ok(())
+}
+
+#[test]
+fn json_patch_arrays_test() {
+    let patch = json_patch(&json!({ "a": [1, 2, 3], "b/c": 1 }), &json!({ "a": [1, 4], "b/c": 2 }));
+    let expected = json!([
+        { "op": "replace", "path": "/a/1", "value": 4 },
+        { "op": "remove", "path": "/a/2" },
+        { "op": "replace", "path": "/b~1c", "value": 2 },
+    ]);
+    assert_eq!(patch, expected);
+}
+
+#[test]
+fn json_patch_very_deep_test() -> anyhow::Result<()> {
+    let depth = 100_000;
+    let old = start_parser(&format!("{}a{}", "<level>".repeat(depth), "</level>".repeat(depth)))?;
+    let new = start_parser(&format!("{}<leaf/>{}", "<level>".repeat(depth), "</level>".repeat(depth)))?;
+    let patch = json_patch(&old, &new);
+    assert_eq!(patch[0]["path"].as_str().unwrap(), "/level".repeat(depth));
+    assert_eq!(patch[0]["value"], json!({ "leaf": {} }));
+
+    let patch = json_patch(&json!({}), &new);
+    assert_eq!(patch[0]["op"], "add");
+    for value in [old, new, patch] { XML_to_JSON_Parser::drop_value(value); }
+    Ok(())
+}
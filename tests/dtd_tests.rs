@@ -0,0 +1,206 @@
+#![cfg(feature = "validation")]
+
+mod common;
+
+use XML_to_JSON_Parser::check::*;
+use XML_to_JSON_Parser::dtd::*;
+use XML_to_JSON_Parser::fmt::format_xml;
+use XML_to_JSON_Parser::schema::infer_schema;
+use XML_to_JSON_Parser::start_parser;
+use XML_to_JSON_Parser::tree::parse_document;
+use common::messages;
+use serde_json::json;
+use std::fs;
+
+const CATALOG: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE catalog [
+    <!ENTITY % text "(#PCDATA)">
+    <!ELEMENT catalog (book+, updated?)>
+    <!ELEMENT book (title, (author | editor)+, note*)>
+    <!ELEMENT title %text;>
+    <!ELEMENT author %text;>
+    <!ELEMENT editor %text;>
+    <!ELEMENT note (#PCDATA | em)*>
+    <!ELEMENT em (#PCDATA)>
+    <!ELEMENT updated EMPTY>
+    <!-- ] is fine in comments -->
+    <!ATTLIST catalog version CDATA #FIXED "1.0">
+    <!ATTLIST book
+        id ID #REQUIRED
+        format (hardcover | paperback) "paperback"
+        related IDREFS #IMPLIED>
+    <!ATTLIST updated on NMTOKEN #REQUIRED>
+]>
+"#;
+
+fn validate(body: &str) -> anyhow::Result<Vec<Diagnostic>> {
+    let input = format!("{}{}", CATALOG, body);
+    let dtd = Dtd::for_document(&input, None, None)?;
+    let diagnostics = dtd.validate(&input)?;
+    // Lines are counted from the start of `body`.
+    let offset = CATALOG.matches('\n').count();
+    Ok(diagnostics.into_iter().map(|diagnostic| Diagnostic { line: diagnostic.line - offset, ..diagnostic }).collect())
+}
+
+#[test]
+fn doctype_is_accepted_test() -> anyhow::Result<()> {
+    let input = format!("{}<catalog><book id=\"b1\"><title>T</title><author>A</author></book></catalog>", CATALOG);
+    assert!(check(&input).is_empty());
+    assert_eq!(start_parser(&input)?, json!({ "catalog": { "book": { "_id": "b1", "title": "T", "author": "A" } } }));
+
+    let document = parse_document(&input)?;
+    assert!(document.doctype.unwrap().starts_with("<!DOCTYPE catalog ["));
+    assert!(format_xml(&input)?.starts_with("<?xml version=\"1.0\"?>\n<!DOCTYPE catalog [\n"));
+    Ok(())
+}
+
+#[test]
+fn valid_document_test() -> anyhow::Result<()> {
+    let body = r#"<catalog version="1.0">
+    <book id="b1" format="hardcover" related="b2"><title>T</title><author>A</author><editor>E</editor><note>See <em>this</em></note></book>
+    <book id="b2" related=" b1  b2 "><title>Second</title><editor>E</editor></book>
+    <updated on="2025-11-10"/>
+</catalog>"#;
+    assert!(validate(body)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn reports_content_errors_test() -> anyhow::Result<()> {
+    let body = "<catalog>\n    <book id=\"b1\"><author>A</author><title>T</title></book>\n    <book id=\"b2\"><title>T</title><editor>E</editor><note>x<b>y</b></note></book>\n    <updated on=\"x\">text</updated>\n    <magazine/>\n</catalog>";
+
+    assert!(validate(body)?.iter().all(|diagnostic| diagnostic.kind == DiagnosticKind::Validity));
+    assert_eq!(messages(&validate(body)?), vec![
+        (1, 1, "Content of element <catalog> must be (book+, updated?), found (book, book, updated, magazine)"),
+        (2, 5, "Content of element <book> must be (title, (author | editor)+, note*), found (author, title)"),
+        (3, 60, "Element <note> can not contain <b>"),
+        (3, 60, "Element <b> is not declared"),
+        (4, 5, "Element <updated> must be empty"),
+        (5, 5, "Element <magazine> is not declared"),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn reports_attribute_errors_test() -> anyhow::Result<()> {
+    let body = r#"<catalog version="2.0"><book format="ebook" color="red" related="b3"><title>T</title><author>A</author></book><book id="b1"><title>T</title><author>A</author></book><book id="b1"><title>T</title><author>A</author></book><updated on="a b"/></catalog>"#;
+
+    assert_eq!(messages(&validate(body)?), vec![
+        (1, 10, "Attribute version must have the fixed value \"1.0\""),
+        (1, 30, "Attribute format: \"ebook\" is not one of hardcover, paperback"),
+        (1, 45, "Attribute color is not declared for element <book>"),
+        (1, 24, "Element <book> is missing required attribute id"),
+        (1, 172, "Attribute id: ID \"b1\" is already used"),
+        (1, 230, "Attribute on: \"a b\" is not a valid NMTOKEN"),
+        (1, 57, "Attribute related: no element has the ID \"b3\""),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn root_must_match_doctype_test() -> anyhow::Result<()> {
+    assert_eq!(messages(&validate("<book id=\"b1\"><title>T</title><author>A</author></book>")?), vec![
+        (1, 1, "Root element <book> does not match the document type catalog"),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn applies_default_attributes_test() -> anyhow::Result<()> {
+    let input = format!("{}<catalog><book id=\"b1\"><title>T</title><author>A</author></book><updated on=\"today\"/></catalog>", CATALOG);
+    assert_eq!(parse_with_dtd(&input, None, None)?, json!({
+        "catalog": {
+            "_version": "1.0",
+            "book": { "_id": "b1", "_format": "paperback", "title": "T", "author": "A" },
+            "updated": { "_on": "today" }
+        }
+    }));
+
+    // Text-only elements become objects with `_text`, given attributes are kept.
+    let dtd = Dtd::parse(r#"<!ATTLIST price currency CDATA "EUR" tax CDATA #FIXED "20"> <!ATTLIST item sale (yes|no) "no">"#)?;
+    let json = parse_with_dtd(r#"<item sale="yes"><price>10</price></item>"#, None, Some(&dtd))?;
+    assert_eq!(json, json!({ "item": { "_sale": "yes", "price": { "_currency": "EUR", "_tax": "20", "_text": "10" } } }));
+
+    // Every element of an array of repeated elements gets the defaults.
+    let json = parse_with_dtd(r#"<item><price>10</price><price currency="USD">12</price></item>"#, None, Some(&dtd))?;
+    assert_eq!(json["item"]["price"], json!([
+        { "_currency": "EUR", "_tax": "20", "_text": "10" },
+        { "_currency": "USD", "_tax": "20", "_text": "12" }
+    ]));
+    Ok(())
+}
+
+#[test]
+fn applies_tree_defaults_test() -> anyhow::Result<()> {
+    let input = format!("{}<catalog><book id=\"b1\"><title>T</title><author>A</author></book><updated on=\"today\"/></catalog>", CATALOG);
+    let mut document = parse_document(&input)?;
+    Dtd::for_document(&input, None, None)?.apply_tree_defaults(&mut document.root);
+    assert_eq!(document.root.to_json(), parse_with_dtd(&input, None, None)?);
+
+    // Inferred schemas describe the defaulted attributes like the output of `parse`.
+    let schema = infer_schema([&document]);
+    assert_eq!(schema["properties"]["catalog"]["properties"]["book"]["required"], json!(["_format", "_id", "author", "title"]));
+    Ok(())
+}
+
+#[test]
+fn external_dtd_test() -> anyhow::Result<()> {
+    let directory = std::env::temp_dir().join("xml_to_json_parser_dtd_test");
+    fs::create_dir_all(&directory)?;
+    fs::write(directory.join("feed.dtd"), r#"<?xml version="1.0" encoding="UTF-8"?>
+<!ENTITY % common SYSTEM "common.dtd">
+%common;
+<!ENTITY % draft "IGNORE">
+<![%draft;[ <!ELEMENT feed ANY> ]]>
+<![ INCLUDE [ <!ELEMENT feed (entry*)> ]]>
+<!ELEMENT entry (#PCDATA)>
+<!ATTLIST entry %lang;>
+"#)?;
+    fs::write(directory.join("common.dtd"), r#"<!ENTITY % lang "lang NMTOKEN 'en'">"#)?;
+
+    let dtd = Dtd::from_file(directory.join("feed.dtd"))?;
+    assert!(dtd.validate("<feed><entry>a</entry><entry lang=\"de\">b</entry></feed>")?.is_empty());
+    assert_eq!(messages(&dtd.validate("<feed>text</feed>")?), vec![(1, 1, "Text is not allowed in element <feed>")]);
+
+    // The internal subset takes precedence over the external DTD.
+    let input = "<!DOCTYPE feed [<!ATTLIST entry lang NMTOKEN 'fr'>]><feed><entry>a</entry></feed>";
+    assert_eq!(parse_with_dtd(input, None, Some(&dtd))?, json!({ "feed": { "entry": { "_lang": "fr", "_text": "a" } } }));
+
+    fs::write(directory.join("loop.dtd"), r#"<!ENTITY % loop SYSTEM "loop.dtd"> %loop;"#)?;
+    assert_eq!(Dtd::from_file(directory.join("loop.dtd")).unwrap_err().to_string(), "Parameter entities in DTD are circular");
+    Ok(())
+}
+
+#[test]
+fn doctype_system_id_test() -> anyhow::Result<()> {
+    let directory = std::env::temp_dir().join("xml_to_json_parser_dtd_system_test");
+    fs::create_dir_all(directory.join("schemas"))?;
+    fs::write(directory.join("schemas/note.dtd"), "<!ELEMENT note (to)>\n<!ELEMENT to (#PCDATA)>\n<!ATTLIST to kind CDATA 'person'>")?;
+    let document = directory.join("note.xml");
+
+    for doctype in [r#"<!DOCTYPE note SYSTEM "schemas/note.dtd">"#, r#"<!DOCTYPE note PUBLIC "-//Example//Note" 'schemas/note.dtd' [<!ATTLIST note id ID #IMPLIED>]>"#] {
+        let input = format!("{}<note><to>A</to></note>", doctype);
+        let dtd = Dtd::for_document(&input, Some(&document), None)?;
+        assert!(dtd.validate(&input)?.is_empty());
+        assert_eq!(parse_with_dtd(&input, Some(&document), None)?, json!({ "note": { "to": { "_kind": "person", "_text": "A" } } }));
+        assert_eq!(messages(&dtd.validate(&format!("{}<note/>", doctype))?), vec![(1, doctype.len() + 1, "Content of element <note> must be (to), found ()")]);
+
+        // Without a path or with an explicit DTD the system identifier is not followed.
+        assert!(Dtd::for_document(&input, None, None)?.validate(&input)?.iter().any(|diagnostic| diagnostic.message.contains("is not declared")));
+        assert_eq!(parse_with_dtd(&input, None, None)?, json!({ "note": { "to": "A" } }));
+    }
+
+    let input = r#"<!DOCTYPE note SYSTEM "http://example.com/note.dtd"><note/>"#;
+    assert!(Dtd::for_document(input, Some(&document), None).unwrap_err().to_string().contains("Only local DTD files are supported"));
+    assert!(Dtd::for_document(input, Some(&document), Some(&Dtd::parse("<!ELEMENT note EMPTY>")?))?.validate(input)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn dtd_errors_test() {
+    assert_eq!(Dtd::parse("<!ELEMENT a (b)><!ELEMENT a ANY>").unwrap_err().to_string(), "Element a is declared twice in DTD");
+    assert_eq!(Dtd::parse("<!ELEMENT a %missing;>").unwrap_err().to_string(), "Unknown parameter entity %missing; in DTD");
+    assert!(Dtd::parse("<!ENTITY % a SYSTEM 'http://example.com/a.dtd'>").unwrap_err().to_string().contains("Only local DTD files are supported"));
+    assert!(Dtd::parse("<!ATTLIST a b CDATA>").is_err());
+    assert!(Dtd::parse("<!ELEMENT a (b, c>").is_err());
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "encoding")]
+
+use XML_to_JSON_Parser::encoding::*;
+use serde_json::json;
+
+fn utf16(text: &str, little_endian: bool, bom: bool) -> Vec<u8> {
+    let mut bytes = vec![];
+    let units = if bom { vec![0xFEFF] } else { vec![] };
+    for unit in units.into_iter().chain(text.encode_utf16()) {
+        bytes.extend(if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() });
+    }
+    bytes
+}
+
+#[test]
+fn utf8_test() -> anyhow::Result<()> {
+    let parsed = parse_bytes("<parser>Артур</parser>".as_bytes())?;
+    assert_eq!(parsed, json!({ "parser": "Артур" }));
+    Ok(())
+}
+
+#[test]
+fn utf8_bom_is_stripped_test() -> anyhow::Result<()> {
+    let mut bytes = b"\xEF\xBB\xBF".to_vec();
+    bytes.extend(b"<parser>XML_to_JSON</parser>");
+    assert_eq!(decode_bytes(&bytes)?, "<parser>XML_to_JSON</parser>");
+    assert_eq!(parse_bytes(&bytes)?, json!({ "parser": "XML_to_JSON" }));
+    Ok(())
+}
+
+#[test]
+fn utf16_with_bom_test() -> anyhow::Result<()> {
+    let text = r#"<?xml version="1.0" encoding="UTF-16"?><parser>Артур</parser>"#;
+    let expected = json!({ "parser": "Артур" });
+    assert_eq!(parse_bytes(&utf16(text, true, true))?, expected);
+    assert_eq!(parse_bytes(&utf16(text, false, true))?, expected);
+    Ok(())
+}
+
+#[test]
+fn utf16_without_bom_test() -> anyhow::Result<()> {
+    let text = r#"<?xml version="1.0"?><parser>XML_to_JSON</parser>"#;
+    assert_eq!(decode_bytes(&utf16(text, true, false))?, text);
+    assert_eq!(decode_bytes(&utf16(text, false, false))?, text);
+    Ok(())
+}
+
+#[test]
+fn declared_latin1_test() -> anyhow::Result<()> {
+    let mut bytes = b"<?xml version='1.0' encoding='ISO-8859-1'?>\n<parser>Caf".to_vec();
+    bytes.push(0xE9);
+    bytes.extend(b"</parser>");
+    assert_eq!(parse_bytes(&bytes)?, json!({ "parser": "Café" }));
+    Ok(())
+}
+
+#[test]
+fn unknown_encoding_test() {
+    let error = decode_bytes(b"<?xml version=\"1.0\" encoding=\"KLINGON\"?><parser></parser>").unwrap_err();
+    assert_eq!(error.to_string(), "Unknown encoding KLINGON");
+}
+
+#[test]
+fn invalid_utf8_test() {
+    let error = decode_bytes(b"<parser>Caf\xE9</parser>").unwrap_err();
+    assert_eq!(error.to_string(), "Input is not valid UTF-8");
+}
+
+#[test]
+fn declared_utf16_without_bom_test() {
+    assert!(decode_bytes(b"<?xml version=\"1.0\" encoding=\"UTF-16\"?><parser></parser>").is_err());
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "ffi")]
+
+use XML_to_JSON_Parser::ffi::*;
+use serde_json::{json, Value};
+use std::ffi::{CStr, c_char};
+use std::ptr;
+
+fn parse(xml: &CStr) -> (XmlToJsonStatus, Option<String>) {
+    let mut out_json: *mut c_char = ptr::null_mut();
+    let status = unsafe { xml_to_json_parse(xml.as_ptr(), &mut out_json) };
+    if out_json.is_null() { return (status, None); }
+
+    let json = unsafe { CStr::from_ptr(out_json) }.to_str().unwrap().to_string();
+    unsafe { xml_to_json_free(out_json) };
+    (status, Some(json))
+}
+
+fn last_error() -> Option<String> {
+    let message = xml_to_json_last_error();
+    if message.is_null() { None } else { Some(unsafe { CStr::from_ptr(message) }.to_str().unwrap().to_string()) }
+}
+
+#[test]
+fn parse_test() -> anyhow::Result<()> {
+    let (status, json) = parse(c"<parser><title id = \"1\">XML_to_JSON</title><author>Artur Nozhenko</author></parser>");
+    assert_eq!(status, XmlToJsonStatus::Ok);
+    assert_eq!(serde_json::from_str::<Value>(&json.unwrap())?, json!({ "parser": { "title": { "_id": "1", "_text": "XML_to_JSON" }, "author": "Artur Nozhenko" } }));
+    assert_eq!(last_error(), None);
+    Ok(())
+}
+
+#[test]
+fn parse_error_test() {
+    let (status, json) = parse(c"<parser><title>XML_to_JSON</qwerty></parser>");
+    assert_eq!(status, XmlToJsonStatus::ParseError);
+    assert_eq!(json, None);
+    assert_eq!(last_error().as_deref(), Some("There are different open and close tags names"));
+
+    parse(c"<parser/>");
+    assert_eq!(last_error(), None);
+}
+
+#[test]
+fn invalid_input_test() {
+    let mut out_json: *mut c_char = ptr::null_mut();
+    assert_eq!(unsafe { xml_to_json_parse(ptr::null(), &mut out_json) }, XmlToJsonStatus::NullPointer);
+    assert_eq!(unsafe { xml_to_json_parse(c"<parser/>".as_ptr(), ptr::null_mut()) }, XmlToJsonStatus::NullPointer);
+    assert!(last_error().is_some());
+
+    assert_eq!(parse(c"<parser>\xFF</parser>").0, XmlToJsonStatus::InvalidUtf8);
+    unsafe { xml_to_json_free(ptr::null_mut()) };
+}
+
+#[test]
+fn parse_bytes_test() -> anyhow::Result<()> {
+    let bytes = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><parser>caf\xE9</parser>";
+    let mut out_json: *mut c_char = ptr::null_mut();
+    let status = unsafe { xml_to_json_parse_bytes(bytes.as_ptr(), bytes.len(), &mut out_json) };
+    assert_eq!(status, XmlToJsonStatus::Ok);
+
+    let json = unsafe { CStr::from_ptr(out_json) }.to_str()?.to_string();
+    unsafe { xml_to_json_free(out_json) };
+    assert_eq!(serde_json::from_str::<Value>(&json)?, json!({ "parser": "café" }));
+    Ok(())
+}
+
+#[test]
+fn header_is_up_to_date_test() -> anyhow::Result<()> {
+    let generated = std::fs::read_to_string(env!("XML_TO_JSON_PARSER_HEADER"))?;
+    let committed = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/include/xml_to_json_parser.h"))?;
+    assert!(generated == committed, "include/xml_to_json_parser.h is outdated, rebuild with XML_TO_JSON_PARSER_UPDATE_HEADER=1");
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use XML_to_JSON_Parser::fmt::*;
+use XML_to_JSON_Parser::tree::*;
+
+#[test]
+fn basic_format_test() -> anyhow::Result<()> {
+    let test_text = r#"<parser><title   id = "1">XML_to_JSON</title>
+              <author>Artur Nozhenko</author></parser>"#;
+    let expected = "<parser>\n    <title id=\"1\">XML_to_JSON</title>\n    <author>Artur Nozhenko</author>\n</parser>\n";
+    assert_eq!(format_xml(test_text)?, expected);
+    Ok(())
+}
+
+#[test]
+fn format_keeps_comments_and_order_test() -> anyhow::Result<()> {
+    let test_text = "<!--top--><parser><b>2</b><!--  middle  --><a>1</a></parser>";
+    let expected = "<!-- top -->\n<parser>\n    <b>2</b>\n    <!-- middle -->\n    <a>1</a>\n</parser>\n";
+    assert_eq!(format_xml(test_text)?, expected);
+    Ok(())
+}
+
+#[test]
+fn format_normalizes_text_whitespace_test() -> anyhow::Result<()> {
+    let test_text = "<author>  Artur \n\t Nozhenko  </author>";
+    assert_eq!(format_xml(test_text)?, "<author>Artur Nozhenko</author>\n");
+    Ok(())
+}
+
+#[test]
+fn format_normalizes_attribute_quotes_test() -> anyhow::Result<()> {
+    let test_text = r#"<parser id='1' title='say "hi"'></parser>"#;
+    assert_eq!(format_xml(test_text)?, "<parser id=\"1\" title='say \"hi\"'></parser>\n");
+    Ok(())
+}
+
+#[test]
+fn format_is_idempotent_test() -> anyhow::Result<()> {
+    let test_text = "<parser> text <title>t</title>tail<!--c--></parser><!--end-->";
+    let formatted = format_xml(test_text)?;
+    assert_eq!(format_xml(&formatted)?, formatted);
+    assert!(is_formatted(&formatted)?);
+    assert!(!is_formatted(test_text)?);
+    Ok(())
+}
+
+#[test]
+fn format_invalid_xml_test() {
+    assert!(format_xml("<parser></qwerty>").is_err());
+    assert!(format_xml("<parser>").is_err());
+}
+
+#[test]
+fn document_is_lossless_test() -> anyhow::Result<()> {
+    let test_text = "<!--a--><parser id=\"1\">\n  text <!--b--><title>t</title>\n</parser>";
+    let document = parse_document(test_text)?;
+    assert_eq!(document.prolog, vec![Node::Comment("a".to_string())]);
+    assert_eq!(document.root.attribute("id"), Some("1"));
+    assert_eq!(document.root.children, vec![
+        Node::Text("\n  text ".to_string()),
+        Node::Comment("b".to_string()),
+        Node::Element(Element { name: "title".to_string(), attributes: vec![], children: vec![Node::Text("t".to_string())] }),
+        Node::Text("\n".to_string()),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn format_keeps_declaration_test() -> anyhow::Result<()> {
+    let test_text = "<?xml version=\"1.0\"?>   <parser></parser>";
+    assert_eq!(format_xml(test_text)?, "<?xml version=\"1.0\"?>\n<parser></parser>\n");
+    Ok(())
+}
@@ -454,3 +454,66 @@ fn empty_string_xml_test() {
 }
 
 
+
+// comment tests
+
+#[test]
+fn basic_comment_test() -> anyhow::Result<()> {
+    let test_text = "<!-- some <text> -->";
+    let mut res = Grammar::parse(Rule::comment, test_text)?;
+    let pair = res.next().ok_or_else(|| anyhow!("No comment parsed"))?;
+    assert_eq!(pair.as_str(), test_text);
+    Ok(())
+}
+
+#[test]
+fn unclosed_comment_test() {
+    let test_text = "<!-- some text";
+    let res = Grammar::parse(Rule::comment, test_text);
+    assert!(res.is_err(), "Comment is not closed");
+}
+
+#[test]
+fn element_with_comments_test() -> anyhow::Result<()> {
+    let test_text = "<!--top--><title><!--inner-->title</title><!--bottom-->";
+    let mut res = Grammar::parse(Rule::xml, test_text)?;
+    let pair = res.next().unwrap();
+    assert_eq!(pair.as_str(), test_text);
+    Ok(())
+}
+
+#[test]
+fn single_quoted_attribute_test() -> anyhow::Result<()> {
+    let test_text = "id = 'say \"hi\"'";
+    let mut res = Grammar::parse(Rule::attribute, test_text)?;
+    let pair = res.next().unwrap();
+    assert_eq!(pair.as_str(), test_text);
+    Ok(())
+}
+
+#[test]
+fn empty_element_test() -> anyhow::Result<()> {
+    let test_text = "<title id = \"1\" />";
+    let mut res = Grammar::parse(Rule::element, test_text)?;
+    let pair = res.next().unwrap();
+    assert_eq!(pair.as_str(), test_text);
+    Ok(())
+}
+
+#[test]
+fn prefixed_name_test() -> anyhow::Result<()> {
+    let test_text = "xs:element.name";
+    let mut res = Grammar::parse(Rule::name, test_text)?;
+    let pair = res.next().unwrap();
+    assert_eq!(pair.as_str(), test_text);
+    Ok(())
+}
+
+#[test]
+fn xml_with_declaration_test() -> anyhow::Result<()> {
+    let test_text = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<title>title</title>";
+    let mut res = Grammar::parse(Rule::xml, test_text)?;
+    let pair = res.next().unwrap();
+    assert_eq!(pair.as_str(), test_text);
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use XML_to_JSON_Parser::check::*;
+use XML_to_JSON_Parser::lenient::*;
+use XML_to_JSON_Parser::recover::*;
+use XML_to_JSON_Parser::start_parser;
+use serde_json::json;
+
+fn kinds(recovered: &Recovered) -> Vec<DiagnosticKind> {
+    recovered.diagnostics.iter().map(|d| d.kind).collect()
+}
+
+#[test]
+fn valid_xml_has_no_warnings_test() -> anyhow::Result<()> {
+    let test_text = r#"<parser><title id="1">XML_to_JSON</title><!-- comment --><empty/></parser>"#;
+    let recovered = parse_lenient(test_text, &LenientOptions::default());
+    assert!(recovered.diagnostics.is_empty());
+    assert!(!recovered.partial);
+    assert_eq!(recovered.value, start_parser(test_text)?);
+    Ok(())
+}
+
+#[test]
+fn unquoted_and_valueless_attributes_test() {
+    let test_text = "<form><input type=text disabled name='q'/></form>";
+    let recovered = parse_lenient(test_text, &LenientOptions::default());
+    assert_eq!(kinds(&recovered), vec![DiagnosticKind::Warning, DiagnosticKind::Warning]);
+    assert_eq!(recovered.diagnostics[0].message, "Value of attribute type is not quoted");
+    assert_eq!(recovered.diagnostics[1].message, "Attribute disabled has no value, empty value is used");
+    assert!(!recovered.partial);
+    assert_eq!(recovered.value, json!({ "form": { "input": { "_type": "text", "_disabled": "", "_name": "q" } } }));
+}
+
+#[test]
+fn unclosed_list_items_and_paragraphs_test() {
+    let test_text = "<div>\n  <ul>\n    <li>one\n    <li>two\n  </ul>\n  <p>first\n  <p>second\n</div>";
+    let recovered = parse_lenient(test_text, &LenientOptions::default());
+    assert!(!recovered.partial);
+    assert_eq!(recovered.diagnostics.len(), 4);
+    assert!(recovered.diagnostics.iter().all(|d| d.kind == DiagnosticKind::Warning));
+    assert_eq!((recovered.diagnostics[0].line, recovered.diagnostics[0].column), (3, 5));
+    assert_eq!(recovered.diagnostics[0].message, "Element <li> is closed before <li>");
+    assert_eq!(recovered.diagnostics[1].message, "Element <li> is closed by </ul>");
+    assert_eq!(recovered.value, json!({ "div": { "ul": { "li": ["one", "two"] }, "p": ["first", "second"] } }));
+}
+
+#[test]
+fn void_elements_test() {
+    let test_text = "<p>line<br>next<img src=a.png></p>";
+    let recovered = parse_lenient(test_text, &LenientOptions::default());
+    assert_eq!(kinds(&recovered), vec![DiagnosticKind::Warning; 3]);
+    assert_eq!(recovered.value, json!({ "p": { "br": {}, "img": { "_src": "a.png" } } }));
+}
+
+#[test]
+fn case_insensitive_close_tags_test() {
+    let test_text = "<Parser><TITLE>XML_to_JSON</title></parser>";
+    let recovered = parse_lenient(test_text, &LenientOptions::default());
+    assert_eq!(kinds(&recovered), vec![DiagnosticKind::Warning, DiagnosticKind::Warning]);
+    assert_eq!(recovered.diagnostics[0].message, "Close tag </title> matches <TITLE> ignoring case");
+    assert_eq!(recovered.value, json!({ "Parser": { "TITLE": "XML_to_JSON" } }));
+
+    let options = LenientOptions { case_insensitive: false, ..LenientOptions::default() };
+    let recovered = parse_lenient(test_text, &options);
+    assert!(recovered.partial);
+    assert_eq!(kinds(&recovered), vec![DiagnosticKind::Structure, DiagnosticKind::Structure]);
+}
+
+#[test]
+fn custom_auto_close_rules_test() {
+    let test_text = "<list><item>one<item>two</list>";
+    let mut options = LenientOptions::default();
+    options.auto_close.insert("item".to_string(), vec!["item".to_string()]);
+
+    let recovered = parse_lenient(test_text, &options);
+    assert!(!recovered.partial);
+    assert_eq!(kinds(&recovered), vec![DiagnosticKind::Warning; 2]);
+    assert_eq!(exit_code(&recovered.diagnostics), 0);
+
+    let recovered = parse_lenient(test_text, &LenientOptions::default());
+    assert!(recovered.partial);
+    assert_eq!(exit_code(&recovered.diagnostics), 3);
+}
+
+#[test]
+fn errors_are_still_reported_test() {
+    let recovered = parse_lenient("<parser><title>XML_to_JSON</parser>\n<$bad>", &LenientOptions::default());
+    assert!(recovered.partial);
+    assert_eq!(kinds(&recovered), vec![DiagnosticKind::Structure, DiagnosticKind::Syntax]);
+}
+
+#[test]
+fn html_doctype_test() {
+    for doctype in ["<!DOCTYPE html>", "<!doctype html>"] {
+        let test_text = format!("{}\n<html><body><p>XML_to_JSON</body></html>", doctype);
+        let recovered = parse_lenient(&test_text, &LenientOptions::default());
+        assert!(!recovered.partial);
+        assert_eq!(kinds(&recovered), vec![DiagnosticKind::Warning]);
+        assert_eq!(recovered.value, json!({ "html": { "body": { "p": "XML_to_JSON" } } }));
+    }
+
+    assert_eq!(parse_recovering("<!doctype html><html/>").diagnostics[0].message, "Invalid tag name \"!doctype\", tag is skipped");
+}
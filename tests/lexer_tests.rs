@@ -0,0 +1,86 @@
+use XML_to_JSON_Parser::lexer::try_convert;
+use XML_to_JSON_Parser::{start_parser, start_pest_parser};
+use serde_json::json;
+
+fn assert_same(input: &str) {
+    assert_eq!(try_convert(input), start_pest_parser(input).ok(), "input {:?}", input);
+}
+
+#[test]
+fn same_result_as_pest_test() {
+    let inputs = [
+        r#"<parser><title id = "1">XML_to_JSON</title><author>Artur Nozhenko</author></parser>"#,
+        "  <?xml version=\"1.0\"?>\n<!-- before --> <parser/> <!-- after -->\n",
+        "< parser >text</ parser >",
+        r#"<parser x="1"y='2' x = "3"/>"#,
+        r#"<parser id="  padded  ">text</parser>"#,
+        r#"<parser value="a > b & c">text</parser>"#,
+        "<parser>first<!-- comment -->second<nested/>third</parser>",
+        "<parser>\u{a0}text\u{a0}</parser>",
+        "<parser><item>1</item><item>2</item></parser>",
+        "<parser>\n    <title>XML_to_JSON</title>\n</parser>",
+        "<?xml version=\"1.0\"?>\n<!DOCTYPE parser SYSTEM \"parser.dtd\">\n<parser/>",
+        "<!-- a --><!DOCTYPE parser [\n<!ELEMENT parser (#PCDATA)>\n<!-- ] > -->\n<!ATTLIST parser x CDATA \"]>\">\n] ><!-- b --><parser>text</parser>",
+    ];
+    for input in inputs { assert_same(input); }
+}
+
+#[test]
+fn rejected_by_both_test() {
+    let inputs = [
+        "<parser><title>XML_to_JSON</qwerty></parser>",
+        "<parser>",
+        "<parser/>text",
+        "<parser x=1/>",
+        "<parser / >",
+        "<parser><!-- unclosed</parser>",
+        "<?xml version=\"1.0\"<parser/>",
+        "\u{feff}<parser/>",
+        "<parser/><parser/>",
+        "<!DOCTYPE parser [<!ELEMENT parser ANY>><parser/>",
+        "<parser/><!DOCTYPE parser>",
+        "<!DOCTYPE a><!DOCTYPE a><a/>",
+    ];
+    for input in inputs {
+        assert_eq!(try_convert(input), None, "input {:?}", input);
+        assert!(start_pest_parser(input).is_err(), "input {:?}", input);
+    }
+}
+
+#[test]
+fn errors_come_from_pest_test() -> anyhow::Result<()> {
+    let input = "<parser><title>XML_to_JSON</qwerty></parser>";
+    assert_eq!(start_parser(input).unwrap_err().to_string(), start_pest_parser(input).unwrap_err().to_string());
+
+    let input = "<parser>\n    <title>XML_to_JSON\n</parser>";
+    assert_eq!(start_parser(input).unwrap_err().to_string(), start_pest_parser(input).unwrap_err().to_string());
+
+    assert_eq!(start_parser("<parser><title>XML_to_JSON</title></parser>")?, json!({ "parser": { "title": "XML_to_JSON" } }));
+    Ok(())
+}
+
+// Random token soups, both converters must accept the same inputs with the same result.
+#[test]
+fn random_inputs_test() {
+    let tokens = ["<", ">", "</", "/>", "a", "b", " ", "=", "\"", "'", "x", "<!--", "-->", "<?xml", "?>", "<!DOCTYPE a", "[", "]", "\n", "<a>", "</a>", "<b/>", "<a x=\"1\">"];
+    let mut seed: u64 = 7;
+    let mut next = move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize
+    };
+
+    for _ in 0..20_000 {
+        let length = next() % 10 + 1;
+        let input: String = (0..length).map(|_| tokens[next() % tokens.len()]).collect();
+        if !input.trim().is_empty() { assert_same(&input); }
+    }
+}
+
+#[test]
+fn deep_nesting_test() {
+    let depth = 100_000;
+    let input = format!("{}x{}", "<a>".repeat(depth), "</a>".repeat(depth));
+    let mut value = try_convert(&input).unwrap();
+    for _ in 0..depth - 1 { value = value["a"].take(); }
+    assert_eq!(value, json!({ "a": "x" }));
+}
@@ -0,0 +1,69 @@
+use XML_to_JSON_Parser::output::*;
+use serde_json::json;
+
+#[test]
+#[cfg(feature = "yaml")]
+fn basic_yaml_test() -> anyhow::Result<()> {
+    let test_text = r#"<parser><title id = "1">XML_to_JSON</title><author>Artur Nozhenko</author></parser>"#;
+    let expected = "parser:\n  author: Artur Nozhenko\n  title:\n    _id: '1'\n    _text: XML_to_JSON\n";
+    assert_eq!(xml_to_yaml(test_text)?, expected);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn basic_toml_test() -> anyhow::Result<()> {
+    let test_text = r#"<parser><title id = "1">XML_to_JSON</title><author>Artur Nozhenko</author></parser>"#;
+    let expected = "[parser]\nauthor = \"Artur Nozhenko\"\n\n[parser.title]\n_id = \"1\"\n_text = \"XML_to_JSON\"\n";
+    assert_eq!(xml_to_toml(test_text)?, expected);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "yaml")]
+fn yaml_round_trip_test() -> anyhow::Result<()> {
+    let value = json!({ "parser": { "title": "XML_to_JSON", "items": ["1", "2"], "empty": {} } });
+    let parsed: serde_json::Value = serde_norway::from_str(&to_yaml_string(&value)?)?;
+    assert_eq!(parsed, value);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn toml_root_array_test() {
+    let error = to_toml_string(&json!(["1", "2"])).unwrap_err();
+    assert_eq!(error.to_string(), "TOML document root must be a table, got array");
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn toml_null_test() {
+    let error = to_toml_string(&json!({ "parser": { "items": ["1", null] } })).unwrap_err();
+    assert_eq!(error.to_string(), "TOML cannot represent null at /parser/items/1");
+}
+
+#[test]
+#[cfg(all(feature = "yaml", feature = "toml"))]
+fn invalid_xml_test() {
+    assert!(xml_to_yaml("<parser></qwerty>").is_err());
+    assert!(xml_to_toml("").is_err());
+}
+
+#[test]
+fn json_string_matches_serde_json_test() -> anyhow::Result<()> {
+    let value = json!({ "parser": { "title": { "_id": "1", "_text": "XML \"to\" JSON\n" }, "items": ["1", 2, 3.5, null, true, [], {}], "empty": {} } });
+    assert_eq!(to_json_string(&value, true), serde_json::to_string_pretty(&value)?);
+    assert_eq!(to_json_string(&value, false), serde_json::to_string(&value)?);
+    assert_eq!(to_json_string(&json!("XML_to_JSON"), true), "\"XML_to_JSON\"");
+    Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "yaml", feature = "toml"))]
+fn too_deep_yaml_test() {
+    let mut value = json!("XML_to_JSON");
+    for _ in 0..2000 { value = serde_json::Value::Object([("level".to_string(), value)].into_iter().collect()); }
+    assert_eq!(to_yaml_string(&value).unwrap_err().to_string(), "YAML output supports at most 1024 nested levels");
+    assert_eq!(to_toml_string(&value).unwrap_err().to_string(), "TOML output supports at most 1024 nested levels");
+    XML_to_JSON_Parser::drop_value(value);
+}
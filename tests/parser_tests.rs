@@ -182,4 +182,113 @@ fn tag_name_starts_with_number_test() {
     let test_text = r#"<1parser>XML_to_JSON</1parser>"#;
     let parsed = start_parser(test_text);
     assert!(parsed.is_err())
-}
\ No newline at end of file
+}
+#[test]
+fn comments_are_ignored_test() -> anyhow::Result<()> {
+    let test_text = r#"<!-- header --><parser><!-- note --><title id='1'>XML_to_JSON</title></parser>"#;
+    let parsed = start_parser(test_text)?;
+    let expected = json!({ "parser": { "title": { "_id": "1", "_text": "XML_to_JSON" } } });
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn empty_element_test() -> anyhow::Result<()> {
+    let test_text = r#"<parser><title id = "1"/><author/></parser>"#;
+    let parsed = start_parser(test_text)?;
+    let expected = json!({ "parser": { "title": { "_id": "1" }, "author": {} } });
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn repeated_elements_test() -> anyhow::Result<()> {
+    let test_text = r#"<parser><item>1</item><title>XML_to_JSON</title><item id="2">two</item><item/></parser>"#;
+    let parsed = start_parser(test_text)?;
+    let expected = json!({ "parser": { "item": ["1", { "_id": "2", "_text": "two" }, {}], "title": "XML_to_JSON" } });
+    assert_eq!(parsed, expected);
+    assert_eq!(start_pest_parser(test_text)?, expected);
+    Ok(())
+}
+
+const DEEP: usize = 100_000;
+
+fn deep_document(depth: usize, leaf: &str, close: &str) -> String {
+    format!("{}{}{}", "<level>".repeat(depth), leaf, close.repeat(depth))
+}
+
+// Walks down the single chain of `level` objects and returns the depth and the innermost value, dropping the chain level by level.
+fn unwind(mut value: serde_json::Value) -> (usize, serde_json::Value) {
+    let mut depth = 0;
+    while let Some(inner) = value.get_mut("level") {
+        let inner = inner.take();
+        drop_value(value);
+        value = inner;
+        depth += 1;
+    }
+    (depth, value)
+}
+
+#[test]
+fn very_deep_document_test() -> anyhow::Result<()> {
+    let parsed = start_parser(&deep_document(DEEP, "<leaf id = \"1\">XML_to_JSON</leaf>", "</level>"))?;
+    let (depth, leaf) = unwind(parsed);
+    if depth != DEEP { return Err(anyhow!("Expected {} levels, got {}", DEEP, depth)); }
+    assert_eq!(leaf, json!({ "leaf": { "_id": "1", "_text": "XML_to_JSON" } }));
+    Ok(())
+}
+
+#[test]
+fn very_deep_document_is_dropped_test() -> anyhow::Result<()> {
+    drop_value(start_parser(&deep_document(DEEP, "XML_to_JSON", "</level>"))?);
+    Ok(())
+}
+
+#[test]
+fn very_deep_mismatched_tags_test() {
+    let test_text = format!("{}</qwerty>", deep_document(DEEP, "XML_to_JSON", "</level>").strip_suffix("</level>").unwrap());
+    let error = start_parser(&test_text).unwrap_err();
+    assert_eq!(error.to_string(), "There are different open and close tags names");
+}
+
+#[test]
+fn mismatched_tags_before_very_deep_content_test() {
+    let test_text = format!("<root><b>x</c>{}</root>", deep_document(DEEP, "XML_to_JSON", "</level>"));
+    let error = start_parser(&test_text).unwrap_err();
+    assert_eq!(error.to_string(), "There are different open and close tags names");
+}
+
+#[test]
+fn very_deep_syntax_error_test() {
+    let test_text = deep_document(DEEP, "<1leaf/>", "</level>");
+    let error = start_parser(&test_text).unwrap_err();
+    assert_eq!(error.to_string(), format!("Invalid XML at 1:{}", DEEP * "<level>".len() + 2));
+
+    let test_text = deep_document(DEEP, "XML_to_JSON", "</level>");
+    assert!(start_parser(&test_text[..test_text.len() - 1]).is_err());
+}
+
+// Quoted '>' and "/>" do not end the tag when the depth is counted, so this input is not given to pest.
+#[test]
+fn very_deep_syntax_error_with_quoted_tag_end_test() {
+    let open = r#"<level note="/>">"#;
+    let test_text = format!("{}<1leaf/>{}", open.repeat(300), "</level>".repeat(300));
+    let error = start_parser(&test_text).unwrap_err();
+    assert_eq!(error.to_string(), format!("Invalid XML at 1:{}", 300 * open.len() + 2));
+}
+
+#[test]
+fn deep_document_through_pest_test() -> anyhow::Result<()> {
+    let test_text = deep_document(200, "<leaf id = \"1\"/>", "</level>");
+    assert_eq!(start_pest_parser(&test_text)?, start_parser(&test_text)?);
+    Ok(())
+}
+
+#[test]
+fn very_deep_recovered_document_test() {
+    let recovered = recover::parse_recovering(&deep_document(DEEP, "XML_to_JSON", ""));
+    assert_eq!(recovered.diagnostics.len(), DEEP);
+    let (depth, leaf) = unwind(recovered.value);
+    assert_eq!(depth, DEEP);
+    assert_eq!(leaf, json!("XML_to_JSON"));
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "python")]
+
+use XML_to_JSON_Parser::python::xml_to_json_parser;
+use pyo3::ffi::c_str;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::wrap_pymodule;
+
+fn run(script: &std::ffi::CStr) -> PyResult<()> {
+    Python::initialize();
+    Python::attach(|py| {
+        let globals = PyDict::new(py);
+        globals.set_item("parser", wrap_pymodule!(xml_to_json_parser)(py))?;
+        py.run(script, Some(&globals), None)
+    })
+}
+
+#[test]
+fn parse_returns_dict_test() -> PyResult<()> {
+    run(c_str!(r#"
+result = parser.parse('<parser><title id="1">XML_to_JSON</title><author>Artur Nozhenko</author></parser>')
+assert result == {"parser": {"title": {"_id": "1", "_text": "XML_to_JSON"}, "author": "Artur Nozhenko"}}, result
+"#))
+}
+
+#[test]
+fn errors_have_positions_test() -> PyResult<()> {
+    run(c_str!(r#"
+try:
+    parser.parse("<parser>\n    <title>XML_to_JSON</qwerty>\n</parser>")
+    raise AssertionError("no error")
+except parser.XmlParseError as error:
+    assert isinstance(error, ValueError)
+    assert (error.line, error.column) == (2, 23), (error.line, error.column)
+    assert str(error).endswith("at 2:23"), str(error)
+"#))
+}
+
+#[test]
+fn options_test() -> PyResult<()> {
+    run(c_str!(r#"
+import warnings
+with warnings.catch_warnings(record=True) as caught:
+    warnings.simplefilter("always")
+    assert parser.parse("<ul><li>one<li>two</ul>", lenient=True) == {"ul": {"li": ["one", "two"]}}
+    assert parser.parse("<parser><title>XML_to_JSON</parser>", recover=True) == {"parser": {"title": "XML_to_JSON"}}
+assert len(caught) == 4, [str(warning.message) for warning in caught]
+assert [warning.category for warning in caught].count(parser.PartialResultWarning) == 1
+assert str(caught[-1].message) == "<string>: output is partial", str(caught[-1].message)
+
+try:
+    parser.parse("<parser/>", strict=True)
+    raise AssertionError("no error")
+except TypeError:
+    pass
+"#))
+}
+
+#[test]
+fn parse_file_test() -> PyResult<()> {
+    let path = std::env::temp_dir().join("xml_to_json_parser_python_test.xml");
+    std::fs::write(&path, b"\xEF\xBB\xBF<parser><title>XML_to_JSON</title></parser>")?;
+
+    Python::initialize();
+    Python::attach(|py| {
+        let module = wrap_pymodule!(xml_to_json_parser)(py);
+        let result = module.getattr(py, "parse_file")?.call1(py, (path.to_str().unwrap(),))?;
+        let title: String = result.bind(py).get_item("parser")?.get_item("title")?.extract()?;
+        assert_eq!(title, "XML_to_JSON");
+        Ok(())
+    })
+}
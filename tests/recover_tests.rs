@@ -0,0 +1,108 @@
+use XML_to_JSON_Parser::check::*;
+use XML_to_JSON_Parser::recover::*;
+use XML_to_JSON_Parser::start_parser;
+use serde_json::json;
+
+fn positions(recovered: &Recovered) -> Vec<(DiagnosticKind, usize, usize)> {
+    recovered.diagnostics.iter().map(|d| (d.kind, d.line, d.column)).collect()
+}
+
+#[test]
+fn valid_xml_is_not_partial_test() -> anyhow::Result<()> {
+    let test_text = r#"
+     <parser>
+        <title id = "1">XML_to_JSON</title>
+        <!-- comment -->
+        <author>Artur Nozhenko</author>
+        <empty/>
+    </parser>
+    "#;
+
+    let recovered = parse_recovering(test_text);
+    assert!(!recovered.partial);
+    assert!(recovered.diagnostics.is_empty());
+    assert_eq!(recovered.value, start_parser(test_text)?);
+    Ok(())
+}
+
+#[test]
+fn mismatched_close_tags_test() {
+    let test_text = "<parser>\n    <title>XML_to_JSON</qwerty>\n    <author>Artur Nozhenko</qwerty>\n</parser>";
+    let recovered = parse_recovering(test_text);
+    assert!(recovered.partial);
+    assert_eq!(positions(&recovered), vec![(DiagnosticKind::Structure, 2, 23), (DiagnosticKind::Structure, 3, 27)]);
+    assert_eq!(recovered.value, json!({ "parser": { "title": "XML_to_JSON", "author": "Artur Nozhenko" } }));
+}
+
+#[test]
+fn unclosed_elements_test() {
+    let test_text = "<parser>\n  <title>XML_to_JSON\n  <author>Artur</author>\n</parser>\n";
+    let recovered = parse_recovering(test_text);
+    assert_eq!(positions(&recovered), vec![(DiagnosticKind::Structure, 2, 3)]);
+    assert_eq!(recovered.diagnostics[0].message, "Element <title> is not closed");
+    assert_eq!(recovered.value, json!({ "parser": { "title": { "author": "Artur" } } }));
+}
+
+#[test]
+fn missing_close_tags_at_end_test() {
+    let recovered = parse_recovering("<parser><title>XML_to_JSON");
+    assert_eq!(recovered.diagnostics.len(), 2);
+    assert_eq!(recovered.value, json!({ "parser": { "title": "XML_to_JSON" } }));
+}
+
+#[test]
+fn bad_tags_are_skipped_test() {
+    let test_text = "<parser>\n  <1title>XML_to_JSON</1title>\n  <author id=1 name=\"x\">Artur</author>\n  <year>2025</year>\n</parser>";
+    let recovered = parse_recovering(test_text);
+    assert_eq!(positions(&recovered), vec![
+        (DiagnosticKind::Syntax, 2, 4),
+        (DiagnosticKind::Syntax, 2, 22),
+        (DiagnosticKind::Syntax, 3, 11),
+    ]);
+    assert_eq!(recovered.value, json!({ "parser": { "author": "Artur", "year": "2025" } }));
+}
+
+#[test]
+fn stray_close_tag_and_extra_root_test() {
+    let recovered = parse_recovering("</qwerty><parser>XML_to_JSON</parser><other></other>");
+    assert_eq!(recovered.diagnostics.len(), 2);
+    assert_eq!(recovered.value, json!({ "parser": "XML_to_JSON" }));
+}
+
+#[test]
+fn unclosed_tag_bracket_test() {
+    let recovered = parse_recovering("<parser><title id=\"1\"<author>Artur</author></parser>");
+    assert_eq!(recovered.diagnostics[0].message, "Tag is not closed with >");
+    assert!(recovered.partial);
+}
+
+#[test]
+fn empty_input_test() {
+    let recovered = parse_recovering("  ");
+    assert!(recovered.partial);
+    assert_eq!(recovered.value, serde_json::Value::Null);
+}
+
+#[test]
+fn doctype_is_skipped_test() -> anyhow::Result<()> {
+    let test_text = r#"<?xml version="1.0"?>
+<!DOCTYPE parser [
+    <!-- <title> ] -->
+    <!ELEMENT parser (title)>
+    <!ATTLIST title id CDATA "]>">
+]>
+<parser><title id = "1">XML_to_JSON</title></parser>"#;
+
+    let recovered = parse_recovering(test_text);
+    assert!(recovered.diagnostics.is_empty());
+    assert!(!recovered.partial);
+    assert_eq!(recovered.value, start_parser(test_text)?);
+
+    let recovered = parse_recovering("<parser><!DOCTYPE parser><title>XML_to_JSON</title></parser>");
+    assert_eq!(positions(&recovered), vec![(DiagnosticKind::Syntax, 1, 9)]);
+    assert_eq!(recovered.value, json!({ "parser": { "title": "XML_to_JSON" } }));
+
+    let recovered = parse_recovering("<!DOCTYPE parser");
+    assert_eq!(recovered.diagnostics[0].message, "Document type declaration is not closed");
+    Ok(())
+}
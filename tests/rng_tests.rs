@@ -0,0 +1,215 @@
+#![cfg(feature = "validation")]
+
+mod common;
+
+use XML_to_JSON_Parser::check::*;
+use XML_to_JSON_Parser::rng::*;
+use common::messages;
+use std::fs;
+
+const LIBRARY: &str = r#"# A library with books.
+datatypes xsd = "http://www.w3.org/2001/XMLSchema-datatypes"
+
+start = element library {
+    attribute version { "1.0" }?,
+    book+,
+    element updated { xsd:date }?
+}
+
+## Books have an author or an editor.
+book = element book {
+    attribute id { xsd:positiveInteger },
+    attribute format { "hardcover" | "paperback" }?,
+    element title { attribute lang { xsd:language }?, text },
+    (element author { text }+ | element editor { text }),
+    element year { xsd:integer { minInclusive = "1450" maxExclusive = "2100" } }?
+}
+"#;
+
+const LIBRARY_XML: &str = r#"<?xml version="1.0"?>
+<grammar xmlns="http://relaxng.org/ns/structure/1.0" xmlns:a="http://relaxng.org/ns/compatibility/annotations/1.0"
+         datatypeLibrary="http://www.w3.org/2001/XMLSchema-datatypes">
+    <start>
+        <element name="library">
+            <optional><attribute name="version"><value type="token">1.0</value></attribute></optional>
+            <oneOrMore><ref name="book"/></oneOrMore>
+            <optional><element name="updated"><data type="date"/></element></optional>
+        </element>
+    </start>
+    <define name="book">
+        <a:documentation>Books have an author or an editor.</a:documentation>
+        <element name="book">
+            <attribute name="id"><data type="positiveInteger"/></attribute>
+            <optional>
+                <attribute name="format"><choice><value>hardcover</value><value>paperback</value></choice></attribute>
+            </optional>
+            <element name="title">
+                <optional><attribute name="lang"><data type="language"/></attribute></optional>
+                <text/>
+            </element>
+            <choice>
+                <oneOrMore><element name="author"><text/></element></oneOrMore>
+                <element name="editor"><text/></element>
+            </choice>
+            <optional>
+                <element name="year">
+                    <data type="integer">
+                        <param name="minInclusive">1450</param>
+                        <param name="maxExclusive">2100</param>
+                    </data>
+                </element>
+            </optional>
+        </element>
+    </define>
+</grammar>"#;
+
+#[test]
+fn compact_and_xml_syntax_agree_test() -> anyhow::Result<()> {
+    // Annotations are ignored, token values are compared after whitespace normalization and `author+ | editor` is a choice of a repetition.
+    let valid = r#"<library version=" 1.0 ">
+    <book id="1" format="paperback"><title lang="en">XML_to_JSON</title><author>Artur Nozhenko</author><!-- second --><author>B</author><year>2025</year></book>
+    <book id="2"><title/><editor>Editor</editor></book>
+    <updated>2025-11-10</updated>
+</library>"#;
+    let structure = "<library>\n    <book id=\"1\"><author>A</author></book>\n    <book id=\"2\"><title>T</title></book>\n    <magazine/>\n</library>";
+    let (compact, xml) = (RelaxNg::parse(LIBRARY)?, RelaxNg::parse(LIBRARY_XML)?);
+
+    assert!(compact.validate(valid)?.is_empty());
+    let diagnostics = compact.validate(structure)?;
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.kind == DiagnosticKind::Validity));
+    assert_eq!(messages(&diagnostics), vec![
+        (2, 18, "Element <author> is not expected here, expected <title>"),
+        (2, 5, "Element <book> is incomplete, expected <title>"),
+        (3, 5, "Element <book> is incomplete, expected <author>, <editor>"),
+        (4, 5, "Element <magazine> is not expected here, expected <book>, <updated>"),
+    ]);
+
+    let others = ["<library>text<book><title>T</title><editor>E</editor></book></library>", "<catalog/>", "<library><book id=\"1\"><title/><author/><editor/></book></library>"];
+    for input in [valid, structure].into_iter().chain(others) {
+        assert_eq!(compact.validate(input)?, xml.validate(input)?, "{}", input);
+    }
+    assert_eq!(messages(&compact.validate(others[0])?), vec![
+        (1, 1, "Text is not allowed in element <library>"),
+        (1, 14, "Element <book> is missing required attribute id"),
+    ]);
+    assert_eq!(messages(&compact.validate(others[1])?), vec![(1, 1, "Element <catalog> is not expected here, expected <library>")]);
+    Ok(())
+}
+
+#[test]
+fn value_and_datatype_errors_test() -> anyhow::Result<()> {
+    let input = r#"<library version="2.0"><book id="0" format="ebook" color="red"><title>T</title><author>A</author><year>1200</year></book><updated>10.11.2025</updated></library>"#;
+
+    // A single datatype reports why it rejects the value, choices of values list what they expect.
+    for schema in [LIBRARY, LIBRARY_XML] {
+        assert_eq!(messages(&RelaxNg::parse(schema)?.validate(input)?), vec![
+            (1, 10, "Attribute version: \"2.0\" is not valid, expected \"1.0\""),
+            (1, 30, "Attribute id: \"0\" is out of range for positiveInteger"),
+            (1, 37, "Attribute format: \"ebook\" is not valid, expected \"hardcover\", \"paperback\""),
+            (1, 52, "Attribute color is not allowed on element <book>"),
+            (1, 98, "Element <year>: \"1200\" is less than 1450"),
+            (1, 122, "Element <updated>: \"10.11.2025\" is not a valid date"),
+        ]);
+    }
+
+    let schema = RelaxNg::parse(r#"start = element note { attribute code { xsd:token { pattern = "[A-Z]{2}" } }, (xsd:string { maxLength = "5" } | "none") }"#)?;
+    assert!(schema.validate(r#"<note code=" AB ">short</note>"#)?.is_empty());
+    assert_eq!(messages(&schema.validate(r#"<note code="ab">toolongnote</note>"#)?), vec![
+        (1, 7, "Attribute code: \"ab\" does not match the pattern [A-Z]{2}"),
+        (1, 1, "Element <note>: \"toolongnote\" is not valid, expected \"none\", string"),
+    ]);
+
+    let schema = RelaxNg::parse(r#"start = element note { xsd:string { maxLength = "5" } }"#)?;
+    assert_eq!(messages(&schema.validate("<note>toolongnote</note>")?), vec![(1, 1, "Element <note>: \"toolongnote\" is longer than 5")]);
+    Ok(())
+}
+
+#[test]
+fn attribute_choice_test() -> anyhow::Result<()> {
+    let schema = RelaxNg::parse(r#"
+start = element shape { attribute radius { xsd:decimal } | (attribute width { xsd:decimal }, attribute height { xsd:decimal }) }
+"#)?;
+
+    // Attributes match in any order, also inside a group.
+    for input in [r#"<shape radius="1"/>"#, r#"<shape width="1" height="2"/>"#, r#"<shape height="2" width="1"/>"#] {
+        assert!(schema.validate(input)?.is_empty(), "{}", input);
+    }
+    assert_eq!(messages(&schema.validate("<shape/>")?), vec![(1, 1, "Element <shape> is missing required attributes")]);
+    assert_eq!(messages(&schema.validate(r#"<shape width="1"/>"#)?), vec![(1, 1, "Element <shape> is missing required attribute height")]);
+    assert_eq!(messages(&schema.validate(r#"<shape radius="1" width="2"/>"#)?), vec![(1, 19, "Attribute width is not allowed on element <shape>")]);
+    Ok(())
+}
+
+#[test]
+fn combined_definitions_test() -> anyhow::Result<()> {
+    let schema = RelaxNg::parse(r#"
+start = element doc { inline*, meta }
+inline = element a { empty }
+inline |= element b { empty }
+meta = attribute lang { text }
+meta &= attribute version { text }
+"#)?;
+
+    assert!(schema.validate(r#"<doc version="1" lang="en"><b/><a/></doc>"#)?.is_empty());
+    assert_eq!(messages(&schema.validate(r#"<doc lang="en" version="1"><c/></doc>"#)?), vec![
+        (1, 28, "Element <c> is not expected here, expected <a>, <b>"),
+    ]);
+    assert_eq!(messages(&schema.validate(r#"<doc lang="en"/>"#)?), vec![(1, 1, "Element <doc> is missing required attribute version")]);
+    Ok(())
+}
+
+#[test]
+fn interleave_mixed_and_list_test() -> anyhow::Result<()> {
+    let schema = RelaxNg::parse(r#"
+default namespace = "http://example.com/doc"
+start = element doc { attribute * - id { text }*, (head & body) }
+head = element head { element title { token "Title" | string "Other" }, element keywords { list { xsd:NCName* } }? }
+body = element body { mixed { (element b { text } | element i { text })* } }
+element = element \element { xsd:string - "forbidden" }
+"#)?;
+
+    let input = r#"<doc lang="en"><body>Some <b>bold</b> and <i>italic</i> text</body><head><title>  Title </title><keywords>a b c</keywords></head></doc>"#;
+    assert!(schema.validate(input)?.is_empty());
+
+    let input = "<doc id=\"1\"><head><title>Other </title><keywords>a 1</keywords></head></doc>";
+    assert_eq!(messages(&schema.validate(input)?), vec![
+        (1, 6, "Attribute id is not allowed on element <doc>"),
+        (1, 19, "Element <title>: \"Other \" is not valid, expected \"Other\", \"Title\""),
+        (1, 40, "Element <keywords>: \"a 1\" is not valid, expected NCName"),
+        (1, 1, "Element <doc> is incomplete, expected <body>"),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn include_and_external_ref_test() -> anyhow::Result<()> {
+    let directory = std::env::temp_dir().join("xml_to_json_parser_rng_test");
+    fs::create_dir_all(&directory)?;
+    fs::write(directory.join("common.rnc"), "start = element note { inline }\ninline = text\ncolor = external \"color.rng\"\n")?;
+    fs::write(directory.join("color.rng"), r#"<choice xmlns="http://relaxng.org/ns/structure/1.0"><value>red</value><value>blue</value></choice>"#)?;
+    fs::write(directory.join("note.rng"), r#"<grammar xmlns="http://relaxng.org/ns/structure/1.0">
+    <include href="common.rnc">
+        <define name="inline"><attribute name="color"><ref name="color"/></attribute><text/></define>
+    </include>
+</grammar>"#)?;
+
+    let schema = RelaxNg::from_file(directory.join("note.rng"))?;
+    assert!(schema.validate("<note color=\"red\">Hello</note>")?.is_empty());
+    assert_eq!(messages(&schema.validate("<note>Hello</note>")?), vec![(1, 1, "Element <note> is missing required attribute color")]);
+
+    fs::write(directory.join("loop.rnc"), "include \"loop.rnc\"\nstart = empty\n")?;
+    assert!(format!("{:?}", RelaxNg::from_file(directory.join("loop.rnc")).unwrap_err()).contains("includes itself"));
+    Ok(())
+}
+
+#[test]
+fn invalid_grammar_test() {
+    let error = |schema: &str| RelaxNg::parse(schema).unwrap_err().to_string();
+    assert_eq!(error("start = element a { b }"), "Reference to undefined pattern b");
+    assert_eq!(error("start = a\na = b\nb = a | empty"), "Pattern a refers to itself outside of an element");
+    assert_eq!(error("start = a\na = empty\na = text"), "Pattern a is defined twice without a combine attribute");
+    assert_eq!(error("start = element a {\n  text,\n  element b { empty } | empty\n}"), "Syntax error at line 3: expected `,`, found `|`");
+    assert_eq!(error("start = element a { foo:bar }"), "Syntax error at line 1: expected a declared datatypes prefix, found foo:bar");
+    assert_eq!(error("start = element a { attribute b { xsd:date { minLength = \"x\" } } }"), "Invalid value x of <minLength> in schema");
+    assert!(error("start = external \"http://example.com/a.rnc\"").contains("Only local schema files are supported"));
+}
@@ -0,0 +1,113 @@
+use XML_to_JSON_Parser::schema::*;
+use XML_to_JSON_Parser::start_parser;
+use XML_to_JSON_Parser::tree::parse_document;
+use serde_json::{json, Value};
+
+fn infer(inputs: &[&str]) -> anyhow::Result<Value> {
+    let documents = inputs.iter().map(|input| parse_document(input)).collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(infer_schema(&documents))
+}
+
+fn is_valid(schema: &Value, input: &str) -> anyhow::Result<bool> {
+    let validator = jsonschema::options().should_validate_formats(true).build(schema).map_err(|error| anyhow::anyhow!("{}", error))?;
+    Ok(validator.is_valid(&start_parser(input)?))
+}
+
+#[test]
+fn infer_schema_test() -> anyhow::Result<()> {
+    let schema = infer(&[r#"<parser><title id = "1">XML_to_JSON</title><author>Artur Nozhenko</author></parser>"#])?;
+    assert_eq!(schema, json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "properties": {
+            "parser": {
+                "type": "object",
+                "properties": {
+                    "author": { "type": "string" },
+                    "title": {
+                        "type": "object",
+                        "properties": { "_id": { "type": "string", "pattern": "^[-+]?[0-9]+$" }, "_text": { "type": "string" } },
+                        "additionalProperties": false,
+                        "required": ["_id", "_text"]
+                    }
+                },
+                "additionalProperties": false,
+                "required": ["author", "title"]
+            }
+        },
+        "additionalProperties": false,
+        "required": ["parser"]
+    }));
+    Ok(())
+}
+
+#[test]
+fn merges_samples_test() -> anyhow::Result<()> {
+    let samples = [
+        r#"<feed version="1"><item id="1"><price>10</price><date>2025-11-10</date></item><updated>2025-11-10T13:37:03Z</updated></feed>"#,
+        r#"<feed version="2"><item id="2" sale="true"><price>9.5</price></item><item id="3"><price>8</price></item></feed>"#,
+        r#"<feed><item id="4">sold out</item></feed>"#,
+    ];
+    let schema = infer(&samples)?;
+
+    let feed = &schema["properties"]["feed"];
+    assert_eq!(feed["required"], json!(["item"]));
+    assert_eq!(feed["properties"]["_version"], json!({ "type": "string", "pattern": "^[-+]?[0-9]+$" }));
+    assert_eq!(feed["properties"]["updated"], json!({ "type": "string", "format": "date-time" }));
+
+    // An item is an object with attributes, or an object with `_text` when it only has text. Repeated items are an array.
+    let item = &feed["properties"]["item"]["anyOf"][0];
+    assert_eq!(feed["properties"]["item"]["anyOf"][1], json!({ "type": "array", "items": item, "minItems": 2 }));
+    assert_eq!(item["required"], json!(["_id"]));
+    assert_eq!(item["properties"]["_sale"], json!({ "type": "string", "enum": ["true", "false"] }));
+    assert_eq!(item["properties"]["_text"], json!({ "type": "string" }));
+    assert_eq!(item["properties"]["price"]["pattern"], json!("^[-+]?([0-9]+\\.?[0-9]*|\\.[0-9]+)([eE][-+]?[0-9]+)?$"));
+    assert_eq!(item["properties"]["date"], json!({ "type": "string", "format": "date" }));
+
+    for sample in samples { assert!(is_valid(&schema, sample)?); }
+    assert!(is_valid(&schema, r#"<feed><item id="5"><price>1e3</price></item></feed>"#)?);
+    assert!(!is_valid(&schema, r#"<feed><item id="5"><price>free</price></item></feed>"#)?);
+    assert!(!is_valid(&schema, r#"<feed><item id="5"/><extra/></feed>"#)?);
+    assert!(!is_valid(&schema, r#"<feed><updated>yesterday</updated></feed>"#)?);
+    Ok(())
+}
+
+#[test]
+fn string_or_object_test() -> anyhow::Result<()> {
+    let samples = ["<list><entry>text</entry></list>", "<list><entry><name>a</name></entry></list>", "<list><entry/></list>"];
+    let schema = infer(&samples)?;
+    let entry = &schema["properties"]["list"]["properties"]["entry"];
+
+    assert_eq!(entry["anyOf"][0], json!({ "type": "string" }));
+    assert_eq!(entry["anyOf"][1]["properties"]["name"], json!({ "type": "string" }));
+    assert_eq!(entry["anyOf"][1].get("required"), None);
+    for sample in samples { assert!(is_valid(&schema, sample)?); }
+    Ok(())
+}
+
+#[test]
+fn shape_test() -> anyhow::Result<()> {
+    let documents = [parse_document("<list><entry>1</entry><entry>2</entry><!-- comment --></list>")?, parse_document("<list/>")?];
+    let shape = Shape::from_documents(&documents);
+    let list = &shape.children["list"];
+    let entry = &list.children["entry"];
+
+    assert_eq!((shape.objects, list.objects, list.parents), (2, 2, 2));
+    assert_eq!((entry.parents, entry.max_occurs, entry.string), (1, 2, Some(ScalarType::Integer)));
+    assert!(!entry.is_required_in(list));
+    Ok(())
+}
+
+#[test]
+fn scalar_type_test() {
+    let cases = [
+        ("true", ScalarType::Boolean), ("-12", ScalarType::Integer), ("+0.5", ScalarType::Number), (".5e-3", ScalarType::Number),
+        ("1.", ScalarType::Number), ("1e", ScalarType::String), (".", ScalarType::String), ("2025-11-10", ScalarType::Date),
+        ("2025-13-10", ScalarType::String), ("2025-11-10T13:37:03.5+02:00", ScalarType::DateTime),
+        ("2025-11-10T13:37:03", ScalarType::String), ("2025-11-10Tä3:37:03Z", ScalarType::String), ("NaN", ScalarType::String),
+    ];
+    for (text, expected) in cases { assert_eq!(ScalarType::of(text), expected, "{}", text); }
+
+    assert_eq!(ScalarType::Integer.merge(ScalarType::Number), ScalarType::Number);
+    assert_eq!(ScalarType::Integer.merge(ScalarType::Boolean), ScalarType::String);
+}
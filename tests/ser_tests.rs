@@ -0,0 +1,137 @@
+use XML_to_JSON_Parser::de::from_str;
+use XML_to_JSON_Parser::ser::*;
+use XML_to_JSON_Parser::start_parser;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "parser")]
+struct Parser {
+    #[serde(rename = "_id")]
+    id: u32,
+    title: Title,
+    author: String,
+    tag: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    year: Option<u16>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Title {
+    #[serde(rename = "_lang")]
+    lang: String,
+    #[serde(rename = "_text")]
+    text: String,
+}
+
+fn parser() -> Parser {
+    Parser {
+        id: 1,
+        title: Title { lang: "en".to_string(), text: "XML_to_JSON".to_string() },
+        author: "Artur Nozhenko".to_string(),
+        tag: vec!["xml".to_string(), "json".to_string()],
+        year: None,
+    }
+}
+
+#[test]
+fn basic_struct_test() -> anyhow::Result<()> {
+    let expected = r#"<parser id="1"><title lang="en">XML_to_JSON</title><author>Artur Nozhenko</author><tag>xml</tag><tag>json</tag></parser>"#;
+    assert_eq!(to_string(&parser())?, expected);
+    Ok(())
+}
+
+#[test]
+fn round_trip_test() -> anyhow::Result<()> {
+    let xml = to_string(&parser())?;
+    assert_eq!(from_str::<Parser>(&xml)?, parser());
+    Ok(())
+}
+
+#[test]
+fn same_conventions_as_json_output_test() -> anyhow::Result<()> {
+    let title = Title { lang: "en".to_string(), text: "XML_to_JSON".to_string() };
+    let xml = to_string(&title)?;
+    assert_eq!(xml, r#"<Title lang="en">XML_to_JSON</Title>"#);
+    assert_eq!(start_parser(&xml)?, json!({ "Title": serde_json::to_value(&title)? }));
+    Ok(())
+}
+
+#[test]
+fn escapes_text_and_attributes_test() -> anyhow::Result<()> {
+    let title = Title { lang: "\"<en>\"".to_string(), text: "a < b & c".to_string() };
+    let xml = to_string(&title)?;
+    assert_eq!(xml, r#"<Title lang="&quot;&lt;en>&quot;">a &lt; b &amp; c</Title>"#);
+    assert_eq!(from_str::<Title>(&xml)?, title);
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Shape {
+    Circle {
+        #[serde(rename = "_r")]
+        r: f32,
+    },
+    Square(String),
+    Point,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Shapes(Vec<Shape>);
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "drawing")]
+struct Drawing {
+    main: Shape,
+    layer: Vec<Shape>,
+    shapes: Shapes,
+}
+
+#[test]
+fn enums_test() -> anyhow::Result<()> {
+    let drawing = Drawing {
+        main: Shape::Circle { r: 2.5 },
+        layer: vec![Shape::Point, Shape::Square("small".to_string())],
+        shapes: Shapes(vec![Shape::Square("big".to_string()), Shape::Point]),
+    };
+
+    let xml = to_string(&drawing)?;
+    let expected = r#"<drawing><main><circle r="2.5"/></main><layer>point</layer><layer><square>small</square></layer><shapes><square>big</square><point/></shapes></drawing>"#;
+    assert_eq!(xml, expected);
+    assert_eq!(from_str::<Drawing>(&xml)?, drawing);
+    Ok(())
+}
+
+#[test]
+fn root_name_test() -> anyhow::Result<()> {
+    let map = BTreeMap::from([("_id", "1"), ("title", "XML_to_JSON")]);
+    assert!(to_string(&map).is_err());
+    assert_eq!(to_string_with_root(&map, "parser")?, r#"<parser id="1"><title>XML_to_JSON</title></parser>"#);
+    assert_eq!(to_string(&Shape::Point)?, "<point/>");
+    Ok(())
+}
+
+#[test]
+fn non_scalar_attribute_test() {
+    #[derive(Serialize)]
+    struct Invalid {
+        #[serde(rename = "_id")]
+        id: Vec<u32>,
+    }
+
+    assert!(to_string(&Invalid { id: vec![1] }).is_err());
+}
+
+#[test]
+fn invalid_names_test() -> anyhow::Result<()> {
+    let error = |map: BTreeMap<&str, &str>| to_string_with_root(&map, "parser").unwrap_err().to_string();
+    assert_eq!(error(BTreeMap::from([("_", "1")])), "Invalid attribute name \"\"");
+    assert_eq!(error(BTreeMap::from([("a b", "1")])), "Invalid element name \"a b\"");
+    assert_eq!(error(BTreeMap::from([("1x", "1")])), "Invalid element name \"1x\"");
+    assert_eq!(error(BTreeMap::from([("_1x", "1")])), "Invalid attribute name \"1x\"");
+    assert!(to_string_with_root(&json!({ "title": "XML_to_JSON" }), "my parser").is_err());
+    assert_eq!(to_string_with_root(&BTreeMap::from([("_xml:lang", "en"), ("a-b.c", "1")]), "parser")?, r#"<parser xml:lang="en"><a-b.c>1</a-b.c></parser>"#);
+    Ok(())
+}
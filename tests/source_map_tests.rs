@@ -0,0 +1,77 @@
+use XML_to_JSON_Parser::source_map::*;
+use XML_to_JSON_Parser::start_parser;
+use serde_json::json;
+
+#[test]
+fn every_value_has_location_test() -> anyhow::Result<()> {
+    let test_text = "<parser>\n    <title id = \"1\">XML_to_JSON</title>\n    <author>Artur Nozhenko</author>\n</parser>";
+    let (value, source_map) = start_parser_with_source_map(test_text)?;
+    assert_eq!(value, start_parser(test_text)?);
+
+    let pointers: Vec<&str> = source_map.keys().map(String::as_str).collect();
+    assert_eq!(pointers, vec!["/parser", "/parser/author", "/parser/title", "/parser/title/_id", "/parser/title/_text"]);
+    for pointer in source_map.keys() { assert!(value.pointer(pointer).is_some(), "{} is not in JSON", pointer); }
+    Ok(())
+}
+
+#[test]
+fn locations_test() -> anyhow::Result<()> {
+    let test_text = "<parser>\n    <title id = \"1\">XML_to_JSON</title>\n    <author>Artur Nozhenko</author>\n</parser>";
+    let (_, source_map) = start_parser_with_source_map(test_text)?;
+
+    let title = source_map["/parser/title"];
+    assert_eq!((title.line, title.column), (2, 5));
+    assert_eq!(&test_text[title.start..title.end], "<title id = \"1\">XML_to_JSON</title>");
+
+    let id = source_map["/parser/title/_id"];
+    assert_eq!((id.line, id.column), (2, 12));
+    assert_eq!(&test_text[id.start..id.end], "id = \"1\"");
+
+    let text = source_map["/parser/title/_text"];
+    assert_eq!(&test_text[text.start..text.end], "XML_to_JSON");
+
+    let author = source_map["/parser/author"];
+    assert_eq!((author.line, author.column), (3, 5));
+    Ok(())
+}
+
+#[test]
+fn text_of_mixed_content_has_no_location_test() -> anyhow::Result<()> {
+    let (_, source_map) = start_parser_with_source_map("<parser id=\"1\">text<title>t</title></parser>")?;
+    assert!(!source_map.contains_key("/parser/_text"));
+    Ok(())
+}
+
+#[test]
+fn pointer_escaping_test() -> anyhow::Result<()> {
+    let (value, source_map) = start_parser_with_source_map("<a.b><c-d x:y=\"1\"/></a.b>")?;
+    for pointer in source_map.keys() { assert!(value.pointer(pointer).is_some(), "{} is not in JSON", pointer); }
+    Ok(())
+}
+
+#[test]
+fn repeated_elements_test() -> anyhow::Result<()> {
+    let test_text = "<parser><item>1</item><title/><item id=\"2\">2</item></parser>";
+    let (value, source_map) = start_parser_with_source_map(test_text)?;
+    let pointers: Vec<&str> = source_map.keys().map(String::as_str).collect();
+    assert_eq!(pointers, vec!["/parser", "/parser/item/0", "/parser/item/1", "/parser/item/1/_id", "/parser/item/1/_text", "/parser/title"]);
+    for pointer in source_map.keys() { assert!(value.pointer(pointer).is_some(), "{} is not in JSON", pointer); }
+
+    let second = source_map["/parser/item/1"];
+    assert_eq!(&test_text[second.start..second.end], "<item id=\"2\">2</item>");
+    Ok(())
+}
+
+#[test]
+fn source_map_to_json_test() -> anyhow::Result<()> {
+    let (_, source_map) = start_parser_with_source_map("<parser>XML_to_JSON</parser>")?;
+    let expected = json!({ "/parser": { "line": 1, "column": 1, "start": 0, "end": 28 } });
+    assert_eq!(source_map_to_json(&source_map), expected);
+    Ok(())
+}
+
+#[test]
+fn invalid_xml_test() {
+    assert!(start_parser_with_source_map("<parser></qwerty>").is_err());
+    assert!(start_parser_with_source_map("").is_err());
+}
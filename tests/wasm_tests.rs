@@ -0,0 +1,45 @@
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use XML_to_JSON_Parser::wasm::xml_to_json;
+use serde::Serialize;
+use serde_json::{json, Value};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+const TEST_TEXT: &str = r#"<parser><title id="1">XML_to_JSON</title><author>Artur Nozhenko</author></parser>"#;
+
+fn options(options: Value) -> JsValue {
+    options.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn object_output_test() {
+    let result = xml_to_json(TEST_TEXT, JsValue::UNDEFINED).unwrap();
+    let result: Value = serde_wasm_bindgen::from_value(result).unwrap();
+    assert_eq!(result, json!({ "parser": { "title": { "_id": "1", "_text": "XML_to_JSON" }, "author": "Artur Nozhenko" } }));
+}
+
+#[wasm_bindgen_test]
+fn string_output_test() {
+    let result = xml_to_json(TEST_TEXT, options(json!({ "output": "json" }))).unwrap();
+    let result: Value = serde_json::from_str(&result.as_string().unwrap()).unwrap();
+    assert_eq!(result["parser"]["author"], "Artur Nozhenko");
+
+    let result = xml_to_json(TEST_TEXT, options(json!({ "output": "yaml" }))).unwrap();
+    assert!(result.as_string().unwrap().contains("author: Artur Nozhenko"));
+}
+
+#[wasm_bindgen_test]
+fn lenient_and_recover_options_test() {
+    let result = xml_to_json("<ul><li>one<li>two</ul>", options(json!({ "lenient": true }))).unwrap();
+    assert_eq!(serde_wasm_bindgen::from_value::<Value>(result).unwrap(), json!({ "ul": { "li": ["one", "two"] } }));
+
+    let result = xml_to_json("<parser><title>XML_to_JSON</parser>", options(json!({ "recover": true }))).unwrap();
+    assert_eq!(serde_wasm_bindgen::from_value::<Value>(result).unwrap(), json!({ "parser": { "title": "XML_to_JSON" } }));
+}
+
+#[wasm_bindgen_test]
+fn errors_test() {
+    assert!(xml_to_json("<parser><title>XML_to_JSON</qwerty></parser>", JsValue::UNDEFINED).is_err());
+    assert!(xml_to_json(TEST_TEXT, options(json!({ "strict": true }))).is_err());
+}
@@ -0,0 +1,333 @@
+#![cfg(feature = "validation")]
+
+mod common;
+
+use XML_to_JSON_Parser::check::*;
+use XML_to_JSON_Parser::start_parser;
+use XML_to_JSON_Parser::xsd::*;
+use common::messages;
+use serde_json::{json, Value};
+use std::fs;
+
+const LIBRARY: &str = r#"<?xml version="1.0"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+    <xs:element name="library">
+        <xs:complexType>
+            <xs:sequence>
+                <xs:element name="book" type="Book" maxOccurs="unbounded"/>
+                <xs:element ref="updated" minOccurs="0"/>
+            </xs:sequence>
+            <xs:attribute name="version" type="xs:decimal" fixed="1.0"/>
+        </xs:complexType>
+    </xs:element>
+    <xs:element name="updated" type="xs:date"/>
+    <xs:complexType name="Book">
+        <xs:sequence>
+            <xs:element name="title" type="Title"/>
+            <xs:choice>
+                <xs:element name="author" type="xs:string" maxOccurs="3"/>
+                <xs:element name="editor" type="xs:string"/>
+            </xs:choice>
+            <xs:element name="year" type="Year" minOccurs="0"/>
+        </xs:sequence>
+        <xs:attribute name="id" type="xs:positiveInteger" use="required"/>
+        <xs:attribute name="format" type="Format"/>
+    </xs:complexType>
+    <xs:complexType name="Title">
+        <xs:simpleContent>
+            <xs:extension base="xs:string">
+                <xs:attribute name="lang" type="xs:language"/>
+            </xs:extension>
+        </xs:simpleContent>
+    </xs:complexType>
+    <xs:simpleType name="Year">
+        <xs:restriction base="xs:integer">
+            <xs:minInclusive value="1450"/>
+            <xs:maxExclusive value="2100"/>
+        </xs:restriction>
+    </xs:simpleType>
+    <xs:simpleType name="Format">
+        <xs:restriction base="xs:token">
+            <xs:enumeration value="hardcover"/>
+            <xs:enumeration value="paperback"/>
+        </xs:restriction>
+    </xs:simpleType>
+</xs:schema>"#;
+
+#[test]
+fn valid_document_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(LIBRARY)?;
+    let input = r#"<library version="1.0">
+    <book id="1" format=" paperback "><title lang="en">XML_to_JSON</title><author>Artur Nozhenko</author><year>2025</year></book>
+    <book id="2"><title>Second</title><editor>Editor</editor></book>
+    <updated>2025-11-10</updated>
+</library>"#;
+
+    assert!(schema.validate(input)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn reports_structure_errors_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(LIBRARY)?;
+    let input = "<library>\n    <book id=\"1\"><author>A</author></book>\n    <book id=\"2\"><title>T</title></book>\n    <magazine/>\n</library>";
+    let diagnostics = schema.validate(input)?;
+
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.kind == DiagnosticKind::Validity));
+    assert_eq!(messages(&diagnostics), vec![
+        (4, 5, "Element <magazine> is not expected here, expected <book>, <updated>"),
+        (2, 18, "Element <author> is not expected here, expected <title>"),
+        (3, 5, "Element <book> is incomplete, expected <author>, <editor>"),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn reports_value_errors_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(LIBRARY)?;
+    let input = r#"<library version="2.0"><book id="0" format="ebook" color="red"><title>T</title><author>A</author><author>B</author><author>C</author><author>D</author><year>1200</year></book><updated>10.11.2025</updated></library>"#;
+
+    assert_eq!(messages(&schema.validate(input)?), vec![
+        (1, 10, "Attribute version must have the fixed value \"1.0\""),
+        (1, 30, "Attribute id: \"0\" is out of range for positiveInteger"),
+        (1, 37, "Attribute format: \"ebook\" is not one of \"hardcover\", \"paperback\""),
+        (1, 52, "Attribute color is not allowed on element <book>"),
+        (1, 134, "Element <author> is not expected here, expected <year>"),
+        (1, 152, "Element <year>: \"1200\" is less than 1450"),
+        (1, 176, "Element <updated>: \"10.11.2025\" is not a valid date"),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn missing_attribute_and_text_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(LIBRARY)?;
+    let diagnostics = schema.validate("<library>text<book><title>T</title><editor>E</editor></book></library>")?;
+
+    assert_eq!(messages(&diagnostics), vec![
+        (1, 1, "Text is not allowed in element <library>"),
+        (1, 14, "Element <book> is missing required attribute id"),
+    ]);
+    assert_eq!(messages(&schema.validate("<catalog/>")?), vec![(1, 1, "No declaration for root element <catalog>")]);
+    Ok(())
+}
+
+#[test]
+fn all_any_and_derivation_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(r#"<schema xmlns="http://www.w3.org/2001/XMLSchema">
+    <element name="point">
+        <complexType>
+            <complexContent>
+                <extension base="Base">
+                    <sequence>
+                        <any processContents="skip" minOccurs="0" maxOccurs="unbounded"/>
+                    </sequence>
+                </extension>
+            </complexContent>
+        </complexType>
+    </element>
+    <complexType name="Base">
+        <all>
+            <element name="x" type="double"/>
+            <element name="y" type="double"/>
+            <element name="label" minOccurs="0">
+                <simpleType>
+                    <list itemType="NCName"/>
+                </simpleType>
+            </element>
+        </all>
+        <anyAttribute/>
+    </complexType>
+</schema>"#)?;
+
+    assert!(schema.validate(r#"<point unit="cm"><y>2</y><label>a b</label><x>-1.5e3</x><extra><z/></extra></point>"#)?.is_empty());
+    assert_eq!(messages(&schema.validate("<point><y>2</y><label>1a</label></point>")?), vec![
+        (1, 1, "Element <point> is incomplete, expected <x>"),
+        (1, 16, "Element <label>: \"1a\" is not a valid NCName"),
+    ]);
+    Ok(())
+}
+
+#[test]
+fn pattern_and_union_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+    <xs:element name="code">
+        <xs:simpleType>
+            <xs:union memberTypes="xs:boolean">
+                <xs:simpleType>
+                    <xs:restriction base="xs:string">
+                        <xs:pattern value="[A-Z]{2}\d+"/>
+                        <xs:maxLength value="5"/>
+                    </xs:restriction>
+                </xs:simpleType>
+            </xs:union>
+        </xs:simpleType>
+    </xs:element>
+</xs:schema>"#)?;
+
+    for valid in ["true", "AB12", " 0 "] { assert!(schema.validate(&format!("<code>{}</code>", valid))?.is_empty(), "{}", valid); }
+    for invalid in ["AB1234", "ab1", "AB12x"] { assert_eq!(schema.validate(&format!("<code>{}</code>", invalid))?.len(), 1, "{}", invalid); }
+
+    let pattern = r##"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"><xs:element name="id">
+    <xs:simpleType><xs:restriction base="xs:string"><xs:pattern value="\i\c*"/><xs:pattern value="#\d+"/></xs:restriction></xs:simpleType>
+</xs:element></xs:schema>"##;
+    let schema = Schema::parse(pattern)?;
+    assert!(schema.validate("<id>a-1</id>")?.is_empty() && schema.validate("<id>#12</id>")?.is_empty());
+    assert_eq!(schema.validate("<id>1a</id>")?[0].message, r##"Element <id>: "1a" does not match the pattern \i\c* | #\d+"##);
+    Ok(())
+}
+
+#[test]
+fn include_test() -> anyhow::Result<()> {
+    let directory = std::env::temp_dir().join("xml_to_json_parser_xsd_test");
+    fs::create_dir_all(&directory)?;
+    fs::write(directory.join("main.xsd"), r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+    <xs:include schemaLocation="types.xsd"/>
+    <xs:element name="price" type="Price"/>
+</xs:schema>"#)?;
+    fs::write(directory.join("types.xsd"), r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+    <xs:include schemaLocation="main.xsd"/>
+    <xs:simpleType name="Price">
+        <xs:restriction base="xs:decimal">
+            <xs:fractionDigits value="2"/>
+        </xs:restriction>
+    </xs:simpleType>
+</xs:schema>"#)?;
+
+    let schema = Schema::from_file(directory.join("main.xsd"))?;
+    assert!(schema.validate("<price>9.50</price>")?.is_empty());
+    assert_eq!(messages(&schema.validate("<price>9.505</price>")?), vec![(1, 1, "Element <price>: \"9.505\" has more than 2 fraction digits")]);
+    Ok(())
+}
+
+#[test]
+fn schema_errors_test() {
+    let remote = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"><xs:import schemaLocation="https://example.com/a.xsd"/></xs:schema>"#;
+    assert!(Schema::parse(remote).unwrap_err().to_string().contains("Only local schema files are supported"));
+
+    let unknown = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"><xs:element name="a" type="Missing"/></xs:schema>"#;
+    assert_eq!(Schema::parse(unknown).unwrap_err().to_string(), "Unknown type Missing in schema");
+    assert!(Schema::parse("<root/>").is_err());
+}
+
+fn is_valid_json(schema: &Value, input: &str) -> anyhow::Result<bool> {
+    let validator = jsonschema::validator_for(schema).map_err(|error| anyhow::anyhow!("{}", error))?;
+    Ok(validator.is_valid(&start_parser(input)?))
+}
+
+#[test]
+fn to_json_schema_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+    <xs:element name="item">
+        <xs:complexType>
+            <xs:sequence>
+                <xs:element name="price" type="xs:decimal"/>
+                <xs:element name="note" type="xs:string" minOccurs="0"/>
+            </xs:sequence>
+            <xs:attribute name="id" type="xs:ID" use="required"/>
+        </xs:complexType>
+    </xs:element>
+</xs:schema>"#)?.to_json_schema();
+
+    assert_eq!(schema, json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "patternProperties": { "^([^:]+:)?item$": { "$ref": "#/$defs/element.item" } },
+        "additionalProperties": false,
+        "minProperties": 1,
+        "maxProperties": 1,
+        "$defs": {
+            "element.item": {
+                "type": "object",
+                "patternProperties": {
+                    "^_([^:]+:)?id$": { "type": "string", "pattern": "^(?:[\\p{L}_][\\p{L}\\p{N}._\\-]*)$" },
+                    "^([^:]+:)?note$": { "anyOf": [{ "type": "string" }, { "type": "object", "maxProperties": 0 }] },
+                    "^([^:]+:)?price$": { "type": "string", "pattern": "^(?:[+-]?([0-9]+(\\.[0-9]*)?|\\.[0-9]+))$" },
+                    "^_xmlns(:|$)": { "type": "string" },
+                    "^_xsi:": { "type": "string" }
+                },
+                "allOf": [
+                    { "not": { "propertyNames": { "not": { "pattern": "^_([^:]+:)?id$" } } } },
+                    { "not": { "propertyNames": { "not": { "pattern": "^([^:]+:)?price$" } } } }
+                ],
+                "additionalProperties": false
+            }
+        }
+    }));
+    Ok(())
+}
+
+#[test]
+fn json_schema_accepts_converted_documents_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(LIBRARY)?.to_json_schema();
+    let valid = [
+        r#"<library version="1.0"><book id="1" format="paperback"><title lang="en">XML_to_JSON</title><author>A</author><author>B</author><year>2025</year></book></library>"#,
+        r#"<library><book id="2"><title>Second</title><editor>Editor</editor></book><book id="3"><title/><editor>E</editor></book><updated>2025-11-10</updated></library>"#,
+        "<updated>2025-11-10</updated>",
+    ];
+    for input in valid {
+        assert!(Schema::parse(LIBRARY)?.validate(input)?.is_empty(), "{}", input);
+        assert!(is_valid_json(&schema, input)?, "{}", input);
+    }
+
+    let invalid = [
+        r#"<library><book id="1"><editor>E</editor></book></library>"#,
+        r#"<library><book id="x"><title>T</title><editor>E</editor></book></library>"#,
+        r#"<library><book id="1" format="ebook"><title>T</title><editor>E</editor></book></library>"#,
+        r#"<library><book id="1"><title>T</title><editor>E</editor><pages>1</pages></book></library>"#,
+        "<updated>10.11.2025</updated>",
+        "<magazine/>",
+    ];
+    for input in invalid { assert!(!is_valid_json(&schema, input)?, "{}", input); }
+    Ok(())
+}
+
+#[test]
+fn json_schema_arrays_and_namespaces_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(LIBRARY)?.to_json_schema();
+    let book = &schema["$defs"]["element.library"]["patternProperties"]["^([^:]+:)?book$"];
+    assert_eq!(book["anyOf"][1], json!({ "type": "array", "items": { "$ref": "#/$defs/type.Book" }, "minItems": 2 }));
+
+    // Namespace declarations, `xsi:` attributes and prefixes are ignored by validation, so the JSON Schema allows them too.
+    let valid = [
+        r#"<library><book id="1"><title>A</title><author>A</author><author>B</author></book><book id="2"><title>B</title><editor>E</editor></book></library>"#,
+        r#"<library xmlns="urn:library" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="urn:library library.xsd"><book id="1"><title>A</title><editor>E</editor></book></library>"#,
+        r#"<lib:library xmlns:lib="urn:library" version="1.0"><lib:book id="1"><lib:title>A</lib:title><lib:author>A</lib:author><lib:author>B</lib:author></lib:book></lib:library>"#,
+    ];
+    for input in valid {
+        assert!(Schema::parse(LIBRARY)?.validate(input)?.is_empty(), "{}", input);
+        assert!(is_valid_json(&schema, input)?, "{}", input);
+    }
+
+    let invalid = [
+        r#"<library><book id="1"><title>A</title><editor>E</editor><editor>F</editor></book></library>"#,
+        r#"<lib:library xmlns:lib="urn:library"><lib:book id="1"><lib:editor>E</lib:editor></lib:book></lib:library>"#,
+        r#"<lib:library xmlns:lib="urn:library"><lib:book id="1"><lib:title>A</lib:title><lib:editor>E</lib:editor><lib:pages>1</lib:pages></lib:book></lib:library>"#,
+    ];
+    for input in invalid {
+        assert!(!Schema::parse(LIBRARY)?.validate(input)?.is_empty(), "{}", input);
+        assert!(!is_valid_json(&schema, input)?, "{}", input);
+    }
+    Ok(())
+}
+
+#[test]
+fn json_schema_text_and_recursion_test() -> anyhow::Result<()> {
+    let schema = Schema::parse(r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+    <xs:element name="section">
+        <xs:complexType mixed="true">
+            <xs:sequence>
+                <xs:element ref="section" minOccurs="0" maxOccurs="unbounded"/>
+                <xs:any processContents="lax" minOccurs="0"/>
+            </xs:sequence>
+            <xs:attribute name="level" type="xs:int"/>
+        </xs:complexType>
+    </xs:element>
+</xs:schema>"#)?.to_json_schema();
+
+    for input in ["<section>text</section>", r#"<section level="1">text</section>"#, r#"<section><section level="2"><b/></section></section>"#] {
+        assert!(is_valid_json(&schema, input)?, "{}", input);
+    }
+    assert!(!is_valid_json(&schema, r#"<section><section level="two"/></section>"#)?);
+    Ok(())
+}